@@ -2,8 +2,10 @@ mod avatar;
 mod message_entry;
 mod snow;
 mod sticker;
+mod zoomable_picture;
 
 pub(crate) use self::avatar::Avatar;
 pub(crate) use self::message_entry::MessageEntry;
 pub(crate) use self::snow::Snow;
 pub(crate) use self::sticker::Sticker;
+pub(crate) use self::zoomable_picture::ZoomablePicture;
@@ -176,7 +176,13 @@ impl Sticker {
                     }
                 }
             }
-            _ => unimplemented!(),
+            StickerFormat::Webm => {
+                let media = gtk::MediaFile::for_filename(&path);
+                media.set_loop(looped);
+                media.set_muted(true);
+                media.play();
+                gtk::Picture::for_paintable(&media).upcast()
+            }
         };
 
         // Skip if widget was recycled by ListView
@@ -0,0 +1,324 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, glib, graphene};
+
+const MAX_ZOOM: f64 = 8.0;
+const ZOOM_STEP: f64 = 1.25;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default)]
+    pub(crate) struct ZoomablePicture {
+        pub(super) paintable: RefCell<Option<gdk::Paintable>>,
+        pub(super) paintable_handler_ids: RefCell<Vec<glib::SignalHandlerId>>,
+        pub(super) zoom: Cell<f64>,
+        /// The number of clockwise quarter turns
+        pub(super) rotation: Cell<u32>,
+        /// The offset of the center of the paintable from the center of the widget
+        pub(super) offset: Cell<(f64, f64)>,
+        pub(super) drag_start_offset: Cell<(f64, f64)>,
+        pub(super) gesture_start_zoom: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ZoomablePicture {
+        const NAME: &'static str = "ComponentsZoomablePicture";
+        type Type = super::ZoomablePicture;
+        type ParentType = gtk::Widget;
+    }
+
+    impl ObjectImpl for ZoomablePicture {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![
+                    glib::ParamSpecObject::builder::<gdk::Paintable>("paintable")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecDouble::builder("zoom")
+                        .minimum(1.0)
+                        .maximum(MAX_ZOOM)
+                        .default_value(1.0)
+                        .explicit_notify()
+                        .build(),
+                ]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "paintable" => obj.set_paintable(value.get::<Option<&gdk::Paintable>>().unwrap()),
+                "zoom" => obj.set_zoom(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "paintable" => obj.paintable().to_value(),
+                "zoom" => obj.zoom().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            self.zoom.set(1.0);
+
+            obj.set_overflow(gtk::Overflow::Hidden);
+            obj.set_hexpand(true);
+            obj.set_vexpand(true);
+
+            // Pan the zoomed paintable
+            let drag = gtk::GestureDrag::new();
+            drag.connect_drag_begin(clone!(@weak obj => move |_, _, _| {
+                let imp = obj.imp();
+                imp.drag_start_offset.set(imp.offset.get());
+            }));
+            drag.connect_drag_update(clone!(@weak obj => move |_, x, y| {
+                let (start_x, start_y) = obj.imp().drag_start_offset.get();
+                obj.set_offset(start_x + x, start_y + y);
+            }));
+            obj.add_controller(drag);
+
+            // Zoom with a pinch on touchscreens and touchpads
+            let zoom = gtk::GestureZoom::new();
+            zoom.connect_begin(clone!(@weak obj => move |_, _| {
+                obj.imp().gesture_start_zoom.set(obj.zoom());
+            }));
+            zoom.connect_scale_changed(clone!(@weak obj => move |_, scale| {
+                obj.set_zoom(obj.imp().gesture_start_zoom.get() * scale);
+            }));
+            obj.add_controller(zoom);
+
+            // Zoom with the mouse wheel
+            let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+            scroll.connect_scroll(
+                clone!(@weak obj => @default-return gtk::Inhibit(false), move |_, _, dy| {
+                    obj.set_zoom(obj.zoom() * ZOOM_STEP.powf(-dy));
+                    gtk::Inhibit(true)
+                }),
+            );
+            obj.add_controller(scroll);
+
+            // Toggle the zoom on double click
+            let click = gtk::GestureClick::new();
+            click.connect_pressed(clone!(@weak obj => move |_, n_press, _, _| {
+                if n_press == 2 {
+                    obj.set_zoom(if obj.zoom() > 1.0 { 1.0 } else { 2.0 });
+                }
+            }));
+            obj.add_controller(click);
+        }
+
+        fn dispose(&self) {
+            self.obj().disconnect_paintable_handlers();
+        }
+    }
+
+    impl WidgetImpl for ZoomablePicture {
+        fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
+            self.parent_size_allocate(width, height, baseline);
+
+            let (x, y) = self.offset.get();
+            self.obj().set_offset(x, y);
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+
+            let paintable = match obj.paintable() {
+                Some(paintable) => paintable,
+                None => return,
+            };
+            let (width, height) = match obj.displayed_size() {
+                Some(size) => size,
+                None => return,
+            };
+            let (offset_x, offset_y) = self.offset.get();
+            let rotation = self.rotation.get();
+
+            // The size of the paintable before the rotation
+            let (width, height) = if rotation % 2 == 1 {
+                (height, width)
+            } else {
+                (width, height)
+            };
+
+            snapshot.save();
+            snapshot.translate(&graphene::Point::new(
+                (obj.width() as f64 / 2.0 + offset_x) as f32,
+                (obj.height() as f64 / 2.0 + offset_y) as f32,
+            ));
+            snapshot.rotate(90.0 * rotation as f32);
+            snapshot.translate(&graphene::Point::new(
+                (-width / 2.0) as f32,
+                (-height / 2.0) as f32,
+            ));
+            paintable.snapshot(snapshot, width, height);
+            snapshot.restore();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A widget showing a paintable that can be zoomed, panned and rotated.
+    pub(crate) struct ZoomablePicture(ObjectSubclass<imp::ZoomablePicture>)
+        @extends gtk::Widget;
+}
+
+impl Default for ZoomablePicture {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl ZoomablePicture {
+    pub(crate) fn paintable(&self) -> Option<gdk::Paintable> {
+        self.imp().paintable.borrow().clone()
+    }
+
+    /// Sets the shown paintable, keeping the current zoom and rotation.
+    pub(crate) fn set_paintable(&self, paintable: Option<&impl IsA<gdk::Paintable>>) {
+        let paintable = paintable.map(|p| p.as_ref().clone());
+        if self.paintable() == paintable {
+            return;
+        }
+
+        self.disconnect_paintable_handlers();
+
+        if let Some(ref paintable) = paintable {
+            let handler_ids = vec![
+                paintable.connect_invalidate_contents(clone!(@weak self as obj => move |_| {
+                    obj.queue_draw();
+                })),
+                paintable.connect_invalidate_size(clone!(@weak self as obj => move |_| {
+                    obj.queue_allocate();
+                    obj.queue_draw();
+                })),
+            ];
+            self.imp().paintable_handler_ids.replace(handler_ids);
+        }
+
+        self.imp().paintable.replace(paintable);
+        self.queue_allocate();
+        self.queue_draw();
+
+        self.notify("paintable");
+    }
+
+    pub(crate) fn zoom(&self) -> f64 {
+        self.imp().zoom.get()
+    }
+
+    /// Sets the zoom, relative to the size at which the paintable fits in the widget.
+    pub(crate) fn set_zoom(&self, zoom: f64) {
+        let zoom = zoom.clamp(1.0, MAX_ZOOM);
+        let old_zoom = self.zoom();
+        if old_zoom == zoom {
+            return;
+        }
+
+        self.imp().zoom.set(zoom);
+
+        // Keep the same point of the paintable in the center of the widget
+        let (x, y) = self.imp().offset.get();
+        let ratio = zoom / old_zoom;
+        self.set_offset(x * ratio, y * ratio);
+
+        if zoom > 1.0 {
+            self.set_cursor_from_name(Some("grab"));
+        } else {
+            self.set_cursor(None);
+        }
+
+        self.queue_draw();
+        self.notify("zoom");
+    }
+
+    pub(crate) fn zoom_in(&self) {
+        self.set_zoom(self.zoom() * ZOOM_STEP);
+    }
+
+    pub(crate) fn zoom_out(&self) {
+        self.set_zoom(self.zoom() / ZOOM_STEP);
+    }
+
+    /// Rotates the paintable clockwise by 90 degrees.
+    pub(crate) fn rotate(&self) {
+        let imp = self.imp();
+        imp.rotation.set((imp.rotation.get() + 1) % 4);
+
+        let (x, y) = imp.offset.get();
+        self.set_offset(x, y);
+        self.queue_draw();
+    }
+
+    /// Resets the zoom, the rotation and the position of the paintable.
+    pub(crate) fn reset(&self) {
+        let imp = self.imp();
+        imp.rotation.set(0);
+        imp.offset.set((0.0, 0.0));
+
+        self.set_zoom(1.0);
+        self.queue_draw();
+    }
+
+    /// Moves the paintable, without letting it leave the visible area.
+    fn set_offset(&self, x: f64, y: f64) {
+        let (max_x, max_y) = match self.displayed_size() {
+            Some((width, height)) => (
+                ((width - self.width() as f64) / 2.0).max(0.0),
+                ((height - self.height() as f64) / 2.0).max(0.0),
+            ),
+            None => (0.0, 0.0),
+        };
+
+        self.imp()
+            .offset
+            .set((x.clamp(-max_x, max_x), y.clamp(-max_y, max_y)));
+        self.queue_draw();
+    }
+
+    /// Returns the size of the rotated and zoomed paintable.
+    fn displayed_size(&self) -> Option<(f64, f64)> {
+        let paintable = self.paintable()?;
+        let width = paintable.intrinsic_width() as f64;
+        let height = paintable.intrinsic_height() as f64;
+
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+
+        let (width, height) = if self.imp().rotation.get() % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        };
+
+        let fit_scale = (self.width() as f64 / width).min(self.height() as f64 / height);
+        let scale = fit_scale * self.zoom();
+
+        Some((width * scale, height * scale))
+    }
+
+    fn disconnect_paintable_handlers(&self) {
+        let imp = self.imp();
+
+        if let Some(paintable) = imp.paintable.borrow().as_ref() {
+            for handler_id in imp.paintable_handler_ids.take() {
+                paintable.disconnect(handler_id);
+            }
+        }
+    }
+}
@@ -16,6 +16,7 @@ mod imp {
     use glib::subclass::Signal;
     use once_cell::sync::Lazy;
     use once_cell::unsync::OnceCell;
+    use std::cell::RefCell;
 
     use crate::strings;
 
@@ -90,6 +91,7 @@ mod imp {
     "#)]
     pub(crate) struct ContactsWindow {
         pub(super) session: OnceCell<Session>,
+        pub(super) contacts_changed_handler_id: RefCell<Option<glib::SignalHandlerId>>,
         #[template_child]
         pub(super) sort_model: TemplateChild<gtk::SortListModel>,
         #[template_child]
@@ -122,6 +124,12 @@ mod imp {
             });
             SIGNALS.as_ref()
         }
+
+        fn dispose(&self) {
+            if let Some(handler_id) = self.contacts_changed_handler_id.take() {
+                self.session.get().unwrap().disconnect(handler_id);
+            }
+        }
     }
 
     impl WidgetImpl for ContactsWindow {}
@@ -162,7 +170,16 @@ impl ContactsWindow {
             .property("transient-for", parent)
             .build();
 
-        obj.imp().session.set(session).unwrap();
+        // Keep the list up to date while the window is open
+        let handler_id = session.connect_contacts_changed(clone!(@weak obj => move |_| {
+            spawn(clone!(@weak obj => async move {
+                obj.fetch_contacts().await;
+            }));
+        }));
+
+        let imp = obj.imp();
+        imp.session.set(session).unwrap();
+        imp.contacts_changed_handler_id.replace(Some(handler_id));
 
         spawn(clone!(@weak obj => async move {
             obj.fetch_contacts().await;
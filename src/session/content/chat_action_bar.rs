@@ -5,20 +5,23 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::{gio, glib, CompositeTemplate};
 use tdlib::enums::{
-    ChatAction, ChatMemberStatus, FormattedText, InputMessageContent, MessageContent,
-    MessageSender as TdMessageSender, UserType,
+    self, ChatAction, ChatMemberStatus, FormattedText, InputFile, InputMessageContent,
+    KeyboardButtonType, MessageContent, MessageSender as TdMessageSender, ReplyMarkup, UserType,
 };
 use tdlib::{functions, types};
 
 use crate::components::MessageEntry;
 use crate::session::content::SendPhotoDialog;
 use crate::tdlib::{
-    BasicGroup, BoxedDraftMessage, BoxedFormattedText, Chat, ChatType, SecretChatState, Supergroup,
+    BasicGroup, BoxedDraftMessage, BoxedFormattedText, BoxedReplyMarkup, Chat, ChatType, Message,
+    SecretChatState, Supergroup,
 };
 use crate::utils::{block_on, spawn, temp_dir};
 use crate::{expressions, strings};
 
 const PHOTO_MIME_TYPES: &[&str] = &["image/png", "image/jpeg"];
+const VIDEO_MIME_TYPES: &[&str] = &["video/*"];
+const AUDIO_MIME_TYPES: &[&str] = &["audio/*"];
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 enum ChatActionBarState {
@@ -45,6 +48,12 @@ mod imp {
         pub(super) basic_group_signal_group: OnceCell<glib::SignalGroup>,
         pub(super) supergroup_signal_group: OnceCell<glib::SignalGroup>,
         pub(super) bindings: RefCell<Vec<gtk::ExpressionWatch>>,
+        pub(super) web_page_preview_source_id: RefCell<Option<glib::SourceId>>,
+        pub(super) web_page_preview_url: RefCell<Option<String>>,
+        pub(super) dismissed_web_page_url: RefCell<Option<String>>,
+        pub(super) hide_keyboard_after_use: Cell<bool>,
+        /// The id of the message whose custom keyboard has been revealed automatically
+        pub(super) revealed_keyboard_message_id: Cell<i64>,
         #[template_child]
         pub(super) top_bar_revealer: TemplateChild<gtk::Revealer>,
         #[template_child]
@@ -54,8 +63,20 @@ mod imp {
         #[template_child]
         pub(super) top_bar_message_label: TemplateChild<gtk::Inscription>,
         #[template_child]
+        pub(super) web_page_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) web_page_title_label: TemplateChild<gtk::Inscription>,
+        #[template_child]
+        pub(super) web_page_description_label: TemplateChild<gtk::Inscription>,
+        #[template_child]
+        pub(super) keyboard_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) keyboard_box: TemplateChild<gtk::Box>,
+        #[template_child]
         pub(super) message_entry: TemplateChild<MessageEntry>,
         #[template_child]
+        pub(super) keyboard_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
         pub(super) send_message_button: TemplateChild<gtk::Button>,
         #[template_child]
         pub(super) select_file_button: TemplateChild<gtk::Button>,
@@ -84,6 +105,13 @@ mod imp {
                     widget.cancel_action();
                 },
             );
+            klass.install_action(
+                "chat-action-bar.dismiss-web-page-preview",
+                None,
+                move |widget, _, _| {
+                    widget.dismiss_web_page_preview();
+                },
+            );
             klass.install_action_async(
                 "chat-action-bar.select-file",
                 None,
@@ -186,22 +214,17 @@ mod imp {
                 .unwrap()
                 .set_orientation(gtk::Orientation::Vertical);
 
-            self.message_entry.connect_formatted_text_notify(
-                clone!(@weak obj => move |message_entry, _| {
-                    // Enable the send-message action only when the message entry contains
-                    // at least one non-whitespace character
-                    let should_enable = message_entry
-                        .formatted_text()
-                        .map(|f| f.0.text.contains(|c: char| !c.is_whitespace()))
-                        .unwrap_or_default();
-                    obj.action_set_enabled("chat-action-bar.send-message", should_enable);
+            self.message_entry
+                .connect_formatted_text_notify(clone!(@weak obj => move |_, _| {
+                    obj.update_send_message_action();
+
+                    obj.queue_web_page_preview_update();
 
                     // Send typing action
                     spawn(clone!(@weak obj => async move {
                         obj.send_chat_action(ChatAction::Typing).await;
                     }));
-                }),
-            );
+                }));
 
             self.message_entry
                 .connect_paste_clipboard(clone!(@weak obj => move |_| {
@@ -226,7 +249,13 @@ mod imp {
         }
 
         fn dispose(&self) {
+            if let Some(source_id) = self.web_page_preview_source_id.take() {
+                source_id.remove();
+            }
+
             self.top_bar_revealer.unparent();
+            self.web_page_revealer.unparent();
+            self.keyboard_revealer.unparent();
             self.action_bar_stack.unparent();
             if let Some(emoji_chooser) = self.emoji_chooser.take() {
                 emoji_chooser.unparent();
@@ -269,6 +298,12 @@ impl ChatActionBar {
                 obj.update_stack_page();
             }),
         );
+        chat_signal_group.connect_notify_local(
+            Some("reply-markup-message-id"),
+            clone!(@weak self as obj => move |_, _| {
+                obj.update_reply_markup();
+            }),
+        );
         imp.chat_signal_group.set(chat_signal_group).unwrap();
 
         let basic_group_signal_group = glib::SignalGroup::new(BasicGroup::static_type());
@@ -335,6 +370,8 @@ impl ChatActionBar {
 
         self.update_top_bar();
         self.update_send_button();
+        self.update_send_message_action();
+        self.update_select_file_button();
 
         if let ChatActionBarState::Editing(message_id) = state {
             self.load_message_to_edit(message_id);
@@ -402,25 +439,64 @@ impl ChatActionBar {
         }
     }
 
+    /// Enables the send-message action only when the message entry contains at least one
+    /// non-whitespace character, or when editing a media message, whose caption can be removed.
+    fn update_send_message_action(&self) {
+        let has_text = self
+            .imp()
+            .message_entry
+            .formatted_text()
+            .map(|f| f.0.text.contains(|c: char| !c.is_whitespace()))
+            .unwrap_or_default();
+        let is_editing_caption = self
+            .editing_message()
+            .map(|m| !matches!(m.content().0, MessageContent::MessageText(_)))
+            .unwrap_or_default();
+
+        self.action_set_enabled(
+            "chat-action-bar.send-message",
+            has_text || is_editing_caption,
+        );
+    }
+
+    /// Disables the file selection when editing a text message, as its type can't be changed,
+    /// and uses it to replace the file when editing a media message.
+    fn update_select_file_button(&self) {
+        let (is_enabled, tooltip) = match self.editing_message().map(|m| m.content().0) {
+            Some(MessageContent::MessageText(_)) => (false, None),
+            Some(_) => (true, Some(gettext("Replace File"))),
+            None => (true, None),
+        };
+
+        self.action_set_enabled("chat-action-bar.select-file", is_enabled);
+        self.imp()
+            .select_file_button
+            .set_tooltip_text(tooltip.as_deref());
+    }
+
+    /// Returns the message that is being edited, if any.
+    fn editing_message(&self) -> Option<Message> {
+        if let ChatActionBarState::Editing(message_id) = self.imp().state.get() {
+            self.chat().and_then(|c| c.message(message_id))
+        } else {
+            None
+        }
+    }
+
     fn load_message_to_edit(&self, message_id: i64) {
         if let Some(chat) = self.chat() {
             let client_id = chat.session().client_id();
 
             if let Some(message) = chat.message(message_id) {
-                match message.content().0 {
-                    MessageContent::MessageText(data) => {
-                        block_on(async move {
-                            let FormattedText::FormattedText(markdown_text) =
-                                functions::get_markdown_text(data.text, client_id)
-                                    .await
-                                    .unwrap();
-
-                            self.imp()
-                                .message_entry
-                                .set_formatted_text(Some(BoxedFormattedText(markdown_text)));
-                        });
-                    }
-                    _ => unimplemented!(),
+                if let Some(text) = editable_text(message.content().0) {
+                    block_on(async move {
+                        let FormattedText::FormattedText(markdown_text) =
+                            functions::get_markdown_text(text, client_id).await.unwrap();
+
+                        self.imp()
+                            .message_entry
+                            .set_formatted_text(Some(BoxedFormattedText(markdown_text)));
+                    });
                 }
             }
         }
@@ -429,13 +505,103 @@ impl ChatActionBar {
     fn reset(&self) {
         self.set_state(ChatActionBarState::Composing);
         self.imp().message_entry.set_formatted_text(None);
+        self.imp().dismissed_web_page_url.replace(None);
+    }
+
+    /// Updates the link preview of the composed message after a short delay, to avoid
+    /// requesting a preview for every typed character.
+    fn queue_web_page_preview_update(&self) {
+        let imp = self.imp();
+
+        if let Some(source_id) = imp.web_page_preview_source_id.take() {
+            source_id.remove();
+        }
+
+        let source_id = glib::timeout_add_local_once(
+            std::time::Duration::from_millis(500),
+            clone!(@weak self as obj => move || {
+                obj.imp().web_page_preview_source_id.take();
+                spawn(clone!(@weak obj => async move {
+                    obj.update_web_page_preview().await;
+                }));
+            }),
+        );
+        imp.web_page_preview_source_id.replace(Some(source_id));
+    }
+
+    async fn update_web_page_preview(&self) {
+        let imp = self.imp();
+
+        let (chat, formatted_text) = match (self.chat(), imp.message_entry.as_markdown().await) {
+            (Some(chat), Some(formatted_text)) => (chat, formatted_text),
+            _ => {
+                self.set_web_page_preview(None);
+                return;
+            }
+        };
+
+        let client_id = chat.session().client_id();
+        let web_page = match functions::get_web_page_preview(formatted_text, client_id).await {
+            Ok(enums::WebPage::WebPage(web_page)) => Some(web_page),
+            // An error is returned when the text doesn't contain any link to preview
+            Err(_) => None,
+        };
+
+        // Ignore the result if the chat has been changed in the meantime
+        if self.chat().as_ref() == Some(&chat) {
+            self.set_web_page_preview(web_page);
+        }
+    }
+
+    fn set_web_page_preview(&self, web_page: Option<types::WebPage>) {
+        let imp = self.imp();
+
+        let web_page = web_page
+            .filter(|w| imp.dismissed_web_page_url.borrow().as_deref() != Some(w.url.as_str()));
+
+        match web_page {
+            Some(web_page) => {
+                let title = if web_page.title.is_empty() {
+                    &web_page.site_name
+                } else {
+                    &web_page.title
+                };
+                let description = if web_page.description.text.is_empty() {
+                    &web_page.display_url
+                } else {
+                    &web_page.description.text
+                };
+
+                imp.web_page_title_label.set_text(Some(title));
+                imp.web_page_description_label.set_text(Some(description));
+                imp.web_page_revealer.set_reveal_child(true);
+
+                imp.web_page_preview_url.replace(Some(web_page.url));
+            }
+            None => {
+                imp.web_page_revealer.set_reveal_child(false);
+                imp.web_page_preview_url.replace(None);
+            }
+        }
+    }
+
+    fn dismiss_web_page_preview(&self) {
+        let imp = self.imp();
+
+        let url = imp.web_page_preview_url.borrow().clone();
+        imp.dismissed_web_page_url.replace(url);
+        self.set_web_page_preview(None);
     }
 
     async fn compose_text_message(&self) -> Option<InputMessageContent> {
-        if let Some(formatted_text) = self.imp().message_entry.as_markdown().await {
+        let imp = self.imp();
+
+        if let Some(formatted_text) = imp.message_entry.as_markdown().await {
             let content = types::InputMessageText {
                 text: formatted_text,
-                disable_web_page_preview: false,
+                // Only disable the link preview if the user dismissed the current one
+                disable_web_page_preview: imp.dismissed_web_page_url.borrow().is_some()
+                    && imp.web_page_preview_url.borrow().is_none(),
                 clear_draft: true,
             };
 
@@ -463,6 +629,11 @@ impl ChatActionBar {
     }
 
     async fn select_file(&self) {
+        if let Some(message) = self.editing_message() {
+            self.replace_media(message).await;
+            return;
+        }
+
         let dialog = gtk::FileDialog::new();
         let filter = gtk::FileFilter::new();
         let filters = gio::ListStore::new(gtk::FileFilter::static_type());
@@ -484,21 +655,137 @@ impl ChatActionBar {
         }
     }
 
+    /// Replaces the file of the edited media message with a file selected by the user, using the
+    /// text of the message entry as the new caption.
+    async fn replace_media(&self, message: Message) {
+        let dialog = gtk::FileDialog::new();
+        let parent = self.root().and_downcast::<gtk::Window>().unwrap();
+        let content = message.content().0;
+
+        let filter = match content {
+            MessageContent::MessagePhoto(_) => Some((gettext("Images"), PHOTO_MIME_TYPES)),
+            MessageContent::MessageVideo(_) => Some((gettext("Videos"), VIDEO_MIME_TYPES)),
+            MessageContent::MessageAudio(_) => Some((gettext("Audio"), AUDIO_MIME_TYPES)),
+            _ => None,
+        };
+        if let Some((name, mime_types)) = filter {
+            let filter = gtk::FileFilter::new();
+            let filters = gio::ListStore::new(gtk::FileFilter::static_type());
+
+            filter.set_name(Some(&name));
+            for mime in mime_types {
+                filter.add_mime_type(mime);
+            }
+
+            filters.append(&filter);
+            dialog.set_filters(&filters);
+        }
+
+        let file = match dialog.open_future(Some(&parent)).await {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+
+        // Ignore the file if the editing has been canceled in the meantime
+        if self.editing_message().as_ref() != Some(&message) {
+            return;
+        }
+
+        let path = file.path().unwrap().to_str().unwrap().to_string();
+        let input_file = InputFile::Local(types::InputFileLocal { path });
+        let caption = self.imp().message_entry.as_markdown().await;
+
+        let content = match content {
+            MessageContent::MessagePhoto(data) => {
+                InputMessageContent::InputMessagePhoto(types::InputMessagePhoto {
+                    photo: input_file,
+                    thumbnail: None,
+                    added_sticker_file_ids: vec![],
+                    width: 0,
+                    height: 0,
+                    caption,
+                    self_destruct_time: 0,
+                    has_spoiler: data.has_spoiler,
+                })
+            }
+            MessageContent::MessageVideo(data) => {
+                InputMessageContent::InputMessageVideo(types::InputMessageVideo {
+                    video: input_file,
+                    thumbnail: None,
+                    added_sticker_file_ids: vec![],
+                    duration: 0,
+                    width: 0,
+                    height: 0,
+                    supports_streaming: true,
+                    caption,
+                    self_destruct_time: 0,
+                    has_spoiler: data.has_spoiler,
+                })
+            }
+            MessageContent::MessageAudio(_) => {
+                InputMessageContent::InputMessageAudio(types::InputMessageAudio {
+                    audio: input_file,
+                    album_cover_thumbnail: None,
+                    duration: 0,
+                    title: String::new(),
+                    performer: String::new(),
+                    caption,
+                })
+            }
+            _ => InputMessageContent::InputMessageDocument(types::InputMessageDocument {
+                document: input_file,
+                thumbnail: None,
+                disable_content_type_detection: true,
+                caption,
+            }),
+        };
+
+        let chat = message.chat();
+        let client_id = chat.session().client_id();
+
+        let result =
+            functions::edit_message_media(chat.id(), message.id(), None, content, client_id).await;
+        if let Err(e) = result {
+            log::warn!("Error replacing the media of a message: {:?}", e);
+        }
+
+        self.cancel_action();
+    }
+
     async fn edit_message(&self) {
         if let Some(chat) = self.chat() {
             if let ChatActionBarState::Editing(message_id) = self.imp().state.get() {
-                if let Some(message) = self.compose_text_message().await {
-                    let client_id = chat.session().client_id();
-                    let chat_id = chat.id();
+                let client_id = chat.session().client_id();
+                let chat_id = chat.id();
 
-                    let result =
-                        functions::edit_message_text(chat_id, message_id, message, client_id).await;
-                    if let Err(e) = result {
-                        log::warn!("Error editing a text message: {:?}", e);
+                let is_text_message = matches!(
+                    chat.message(message_id).map(|m| m.content().0),
+                    Some(MessageContent::MessageText(_))
+                );
+
+                let result = if is_text_message {
+                    match self.compose_text_message().await {
+                        Some(message) => {
+                            functions::edit_message_text(chat_id, message_id, message, client_id)
+                                .await
+                                .map(|_| ())
+                        }
+                        None => return,
                     }
+                } else {
+                    // The caption of a media message can be removed by leaving the entry empty
+                    let caption = self.imp().message_entry.as_markdown().await;
+
+                    functions::edit_message_caption(chat_id, message_id, None, caption, client_id)
+                        .await
+                        .map(|_| ())
+                };
 
-                    self.cancel_action();
+                if let Err(e) = result {
+                    log::warn!("Error editing a message: {:?}", e);
                 }
+
+                self.cancel_action();
             }
         }
     }
@@ -733,9 +1020,11 @@ impl ChatActionBar {
         }
 
         imp.chat.replace(chat);
+        imp.revealed_keyboard_message_id.set(0);
 
         self.update_stack_page();
         self.update_signal_groups();
+        self.update_reply_markup();
 
         self.notify("chat");
     }
@@ -748,6 +1037,171 @@ impl ChatActionBar {
         self.set_state(ChatActionBarState::Editing(message_id));
     }
 
+    /// Replaces the text of the message entry, e.g. with an inline query for a bot.
+    pub(crate) fn set_text(&self, text: String) {
+        let imp = self.imp();
+
+        imp.message_entry
+            .set_formatted_text(Some(BoxedFormattedText(types::FormattedText {
+                text,
+                entities: vec![],
+            })));
+        imp.message_entry.grab_focus();
+    }
+
+    /// Updates the custom keyboard and the force reply of the chat, which are taken from the
+    /// reply markup of a bot message.
+    fn update_reply_markup(&self) {
+        self.set_reply_markup(None);
+
+        let chat = match self.chat() {
+            Some(chat) => chat,
+            None => return,
+        };
+
+        let message_id = chat.reply_markup_message_id();
+        if message_id == 0 {
+            return;
+        }
+
+        spawn(clone!(@weak self as obj => async move {
+            match chat.fetch_message(message_id).await {
+                Ok(message) => {
+                    // Ignore the result if the chat or its reply markup changed in the meantime
+                    if obj.chat().as_ref() == Some(&chat)
+                        && chat.reply_markup_message_id() == message_id
+                    {
+                        obj.set_reply_markup(message.reply_markup().map(|m| (message_id, m)));
+                    }
+                }
+                Err(e) => log::warn!("Error fetching the reply markup message: {e:?}"),
+            }
+        }));
+    }
+
+    fn set_reply_markup(&self, reply_markup: Option<(i64, BoxedReplyMarkup)>) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.keyboard_box.first_child() {
+            imp.keyboard_box.remove(&child);
+        }
+
+        let mut placeholder = String::new();
+        let mut has_keyboard = false;
+
+        match reply_markup {
+            Some((message_id, BoxedReplyMarkup(ReplyMarkup::ShowKeyboard(data)))) => {
+                for row in data.rows {
+                    let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+                    row_box.set_homogeneous(true);
+
+                    for button in row {
+                        row_box.append(&self.create_keyboard_button(button));
+                    }
+
+                    imp.keyboard_box.append(&row_box);
+                }
+
+                // Reveal the keyboard only once, so that it stays hidden if the user hid it
+                if imp.revealed_keyboard_message_id.replace(message_id) != message_id {
+                    imp.keyboard_button.set_active(true);
+                }
+
+                imp.hide_keyboard_after_use.set(data.one_time);
+                placeholder = data.input_field_placeholder;
+                has_keyboard = true;
+            }
+            Some((message_id, BoxedReplyMarkup(ReplyMarkup::ForceReply(data)))) => {
+                if imp.state.get() == ChatActionBarState::Composing {
+                    self.set_state(ChatActionBarState::Replying(message_id));
+                }
+
+                placeholder = data.input_field_placeholder;
+            }
+            _ => {}
+        }
+
+        imp.keyboard_button.set_visible(has_keyboard);
+        if !has_keyboard {
+            imp.keyboard_button.set_active(false);
+        }
+
+        if placeholder.is_empty() {
+            placeholder = gettext("Message");
+        }
+        imp.message_entry.set_placeholder_text(Some(&placeholder));
+    }
+
+    fn create_keyboard_button(&self, button: types::KeyboardButton) -> gtk::Button {
+        let label = gtk::Label::builder()
+            .label(&button.text)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .build();
+
+        let widget = gtk::Button::builder().child(&label).hexpand(true).build();
+
+        if let KeyboardButtonType::Text = button.r#type {
+            widget.connect_clicked(clone!(@weak self as obj => move |_| {
+                let text = button.text.clone();
+                spawn(clone!(@weak obj => async move {
+                    obj.send_keyboard_button_text(text).await;
+                }));
+            }));
+        } else {
+            // TODO: Support requesting the phone number, the location, polls, users, chats
+            // and web apps
+            widget.set_sensitive(false);
+        }
+
+        widget
+    }
+
+    /// Sends the text of a keyboard button, leaving the message being composed and its draft
+    /// untouched.
+    async fn send_keyboard_button_text(&self, text: String) {
+        let imp = self.imp();
+
+        if imp.hide_keyboard_after_use.get() {
+            imp.keyboard_button.set_active(false);
+        }
+
+        if let Some(chat) = self.chat() {
+            let reply_to_message_id = if let ChatActionBarState::Replying(id) = imp.state.get() {
+                id
+            } else {
+                0
+            };
+            let content = InputMessageContent::InputMessageText(types::InputMessageText {
+                text: types::FormattedText {
+                    text,
+                    entities: vec![],
+                },
+                disable_web_page_preview: false,
+                clear_draft: false,
+            });
+
+            let result = functions::send_message(
+                chat.id(),
+                0,
+                reply_to_message_id,
+                None,
+                content,
+                chat.session().client_id(),
+            )
+            .await;
+            if let Err(e) = result {
+                log::warn!("Error sending a keyboard button text: {:?}", e);
+            }
+
+            // The reply has been used, unless the user chose another action in the meantime
+            if reply_to_message_id != 0
+                && imp.state.get() == ChatActionBarState::Replying(reply_to_message_id)
+            {
+                self.set_state(ChatActionBarState::Composing);
+            }
+        }
+    }
+
     fn update_stack_page(&self) {
         let imp = self.imp();
         if let Some(chat) = self.chat() {
@@ -868,3 +1322,15 @@ async fn save_stream_to_file(
 
     Ok(())
 }
+
+/// Returns the text of a text message, or the caption of a media message that can be edited.
+fn editable_text(content: MessageContent) -> Option<types::FormattedText> {
+    match content {
+        MessageContent::MessageText(data) => Some(data.text),
+        MessageContent::MessagePhoto(data) => Some(data.caption),
+        MessageContent::MessageVideo(data) => Some(data.caption),
+        MessageContent::MessageDocument(data) => Some(data.caption),
+        MessageContent::MessageAudio(data) => Some(data.caption),
+        _ => None,
+    }
+}
@@ -2,18 +2,24 @@ use adw::prelude::*;
 use gettextrs::gettext;
 use glib::clone;
 use gtk::subclass::prelude::*;
-use gtk::{gio, glib, CompositeTemplate};
+use gtk::{gdk, gio, glib, CompositeTemplate};
 use tdlib::enums::ChatMemberStatus;
 use tdlib::functions;
 
+use crate::i18n::ngettext_f;
+use crate::session::content::message_row::MessageReactionPicker;
 use crate::session::content::{
-    ChatActionBar, ChatHistoryError, ChatHistoryModel, ChatHistoryRow, ChatInfoWindow,
+    ChatActionBar, ChatHistoryError, ChatHistoryModel, ChatHistoryRow, ChatHistorySelection,
+    ChatInfoWindow, ChatPinnedMessagesBar, ChatSearchBar, ForwardMessagesDialog,
 };
-use crate::tdlib::{Chat, ChatType, SponsoredMessage};
+use crate::tdlib::{Chat, ChatType, Message, SponsoredMessage};
 use crate::utils::spawn;
-use crate::{expressions, Session};
+use crate::{expressions, strings, Session};
 
 const MIN_N_ITEMS: u32 = 20;
+/// How many times to try loading the messages around a message to scroll to, waiting 100 ms
+/// between the attempts while the history is already being loaded.
+const MAX_SCROLL_LOAD_ATTEMPTS: u32 = 50;
 
 mod imp {
     use super::*;
@@ -28,17 +34,34 @@ mod imp {
         pub(super) compact: Cell<bool>,
         pub(super) chat: RefCell<Option<Chat>>,
         pub(super) model: RefCell<Option<ChatHistoryModel>>,
+        pub(super) new_message_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) selection: RefCell<Option<ChatHistorySelection>>,
+        pub(super) selection_mode: Cell<bool>,
         pub(super) message_menu: OnceCell<gtk::PopoverMenu>,
+        pub(super) message_reaction_picker: OnceCell<MessageReactionPicker>,
         pub(super) is_auto_scrolling: Cell<bool>,
         pub(super) sticky: Cell<bool>,
+        /// The ids of the messages to return to, when jumping back after jumping to a message
+        pub(super) jump_back_stack: RefCell<Vec<i64>>,
+        /// The id of the message whose row is briefly highlighted after scrolling to it, or 0
+        pub(super) highlighted_message_id: Cell<i64>,
+        pub(super) highlight_source_id: RefCell<Option<glib::SourceId>>,
         #[template_child]
         pub(super) window_title: TemplateChild<adw::WindowTitle>,
         #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
         pub(super) scrolled_window: TemplateChild<gtk::ScrolledWindow>,
         #[template_child]
         pub(super) list_view: TemplateChild<gtk::ListView>,
         #[template_child]
+        pub(super) search_bar: TemplateChild<ChatSearchBar>,
+        #[template_child]
+        pub(super) back_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
         pub(super) chat_action_bar: TemplateChild<ChatActionBar>,
+        #[template_child]
+        pub(super) selection_label: TemplateChild<gtk::Label>,
     }
 
     #[glib::object_subclass]
@@ -49,13 +72,15 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             ChatHistoryRow::static_type();
+            ChatPinnedMessagesBar::static_type();
+            ChatSearchBar::static_type();
             klass.bind_template();
 
             klass.install_action("chat-history.view-info", None, move |widget, _, _| {
                 widget.open_info_dialog();
             });
             klass.install_action("chat-history.scroll-down", None, move |widget, _, _| {
-                widget.scroll_down();
+                widget.scroll_to_bottom();
             });
             klass.install_action(
                 "chat-history.reply",
@@ -69,6 +94,95 @@ mod imp {
                 let message_id = variant.and_then(|v| v.get()).unwrap();
                 widget.imp().chat_action_bar.edit_message_id(message_id);
             });
+            klass.install_action(
+                "chat-history.set-text",
+                Some("s"),
+                move |widget, _, variant| {
+                    let text = variant.and_then(|v| v.get()).unwrap();
+                    widget.imp().chat_action_bar.set_text(text);
+                },
+            );
+            klass.install_action("chat-history.select-messages", None, move |widget, _, _| {
+                widget.set_selection_mode(true);
+            });
+            klass.install_action(
+                "chat-history.select-message",
+                Some("x"),
+                move |widget, _, variant| {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.set_selection_mode(true);
+                    if let Some(selection) = widget.imp().selection.borrow().as_ref() {
+                        selection.select_message(message_id);
+                    }
+                },
+            );
+            klass.install_action(
+                "chat-history.cancel-selection",
+                None,
+                move |widget, _, _| {
+                    widget.set_selection_mode(false);
+                },
+            );
+            klass.install_action_async(
+                "chat-history.delete-selected",
+                None,
+                |widget, _, _| async move {
+                    widget.show_delete_selected_dialog().await;
+                },
+            );
+            klass.install_action(
+                "chat-history.forward-selected",
+                None,
+                move |widget, _, _| {
+                    widget.forward_selected_messages();
+                },
+            );
+            klass.install_action("chat-history.copy-selected", None, move |widget, _, _| {
+                widget.copy_selected_messages();
+            });
+            klass.add_binding_action(
+                gdk::Key::Escape,
+                gdk::ModifierType::empty(),
+                "chat-history.cancel-selection",
+                None,
+            );
+            klass.install_action_async(
+                "chat-history.scroll-to-message",
+                Some("x"),
+                |widget, _, variant| async move {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.scroll_to_message(message_id).await;
+                },
+            );
+            klass.install_action("chat-history.search", None, move |widget, _, _| {
+                let search_bar = &widget.imp().search_bar;
+                search_bar.set_search_mode(!search_bar.search_mode());
+            });
+            klass.add_binding_action(
+                gdk::Key::f,
+                gdk::ModifierType::CONTROL_MASK,
+                "chat-history.search",
+                None,
+            );
+            klass.install_action(
+                "chat-history.search-from-sender",
+                Some("x"),
+                move |widget, _, variant| {
+                    let message_id = variant.and_then(|v| v.get()).unwrap();
+                    widget.search_from_sender(message_id);
+                },
+            );
+            klass.install_action_async(
+                "chat-history.jump-to-message",
+                Some("(xx)"),
+                |widget, _, variant| async move {
+                    let (origin_id, message_id) = variant.and_then(|v| v.get()).unwrap();
+                    widget.jump_to_message(origin_id, message_id).await;
+                },
+            );
+            klass.install_action_async("chat-history.jump-back", None, |widget, _, _| async move {
+                widget.jump_back().await;
+            });
             klass.install_action_async(
                 "chat-history.leave-chat",
                 None,
@@ -94,6 +208,12 @@ mod imp {
                     glib::ParamSpecBoolean::builder("sticky")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("selection-mode")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecInt64::builder("highlighted-message-id")
+                        .read_only()
+                        .build(),
                 ]
             });
             PROPERTIES.as_ref()
@@ -112,6 +232,7 @@ mod imp {
                     obj.set_chat(chat);
                 }
                 "sticky" => obj.set_sticky(value.get().unwrap()),
+                "selection-mode" => obj.set_selection_mode(value.get().unwrap()),
                 _ => unimplemented!(),
             }
         }
@@ -123,6 +244,8 @@ mod imp {
                 "compact" => self.compact.get().to_value(),
                 "chat" => obj.chat().to_value(),
                 "sticky" => obj.sticky().to_value(),
+                "selection-mode" => obj.selection_mode().to_value(),
+                "highlighted-message-id" => obj.highlighted_message_id().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -133,6 +256,7 @@ mod imp {
             let obj = self.obj();
 
             obj.setup_expressions();
+            obj.update_selection();
 
             let adj = self.list_view.vadjustment().unwrap();
             adj.connect_value_changed(clone!(@weak obj => move |adj| {
@@ -141,11 +265,12 @@ mod imp {
                 if imp.is_auto_scrolling.get() {
                     if adj.value() + adj.page_size() >= adj.upper() {
                         imp.is_auto_scrolling.set(false);
-                        obj.set_sticky(true);
+                        obj.update_sticky(adj);
                     }
                 } else {
-                    obj.set_sticky(adj.value() + adj.page_size() >= adj.upper());
+                    obj.update_sticky(adj);
                     obj.load_older_messages(adj);
+                    obj.load_newer_messages(adj);
                 }
             }));
 
@@ -217,6 +342,47 @@ impl ChatHistory {
         }
     }
 
+    fn load_newer_messages(&self, adj: &gtk::Adjustment) {
+        if adj.upper() - adj.value() - adj.page_size() < adj.page_size() * 2.0 {
+            if let Some(model) = self.imp().model.borrow().as_ref() {
+                if model.is_newest_loaded() {
+                    return;
+                }
+
+                spawn(clone!(@weak model => async move {
+                    if let Err(ChatHistoryError::Tdlib(e)) = model.load_newer_messages(20).await {
+                        log::warn!("Couldn't load newer chat messages: {:?}", e);
+                    }
+                }));
+            }
+        }
+    }
+
+    async fn load_unread_messages(&self, model: &ChatHistoryModel) {
+        if let Err(e) = model.load_unread_messages(MIN_N_ITEMS as i32 * 2).await {
+            log::warn!("Couldn't load the unread messages: {}", e);
+            return;
+        }
+
+        // Stop if the chat was changed in the meantime
+        if self.imp().model.borrow().as_ref() != Some(model) {
+            return;
+        }
+
+        if let Some(position) = model.unread_divider_position() {
+            self.scroll_to_item(model, position);
+        }
+    }
+
+    /// Searches the messages sent by the sender of the message with the given id.
+    fn search_from_sender(&self, message_id: i64) {
+        if let Some(message) = self.chat().and_then(|chat| chat.message(message_id)) {
+            self.imp()
+                .search_bar
+                .search_from_sender(message.sender().clone());
+        }
+    }
+
     fn open_info_dialog(&self) {
         if let Some(chat) = self.chat() {
             ChatInfoWindow::new(&self.parent_window(), &chat).present();
@@ -274,6 +440,8 @@ impl ChatHistory {
                 .object::<gtk::PopoverMenu>("menu")
                 .unwrap();
 
+            menu.add_child(self.message_reaction_picker(), "reactions");
+
             menu.set_halign(if self.direction() == gtk::TextDirection::Rtl {
                 gtk::Align::End
             } else {
@@ -284,6 +452,16 @@ impl ChatHistory {
         })
     }
 
+    pub(crate) fn message_reaction_picker(&self) -> &MessageReactionPicker {
+        self.imp()
+            .message_reaction_picker
+            .get_or_init(MessageReactionPicker::new)
+    }
+
+    pub(crate) fn add_toast(&self, toast: adw::Toast) {
+        self.imp().toast_overlay.add_toast(toast);
+    }
+
     pub(crate) fn handle_paste_action(&self) {
         self.imp().chat_action_bar.handle_paste_action();
     }
@@ -299,6 +477,15 @@ impl ChatHistory {
 
         let imp = self.imp();
 
+        self.set_selection_mode(false);
+        self.clear_jump_back_stack();
+
+        if let Some(handler_id) = imp.new_message_handler_id.take() {
+            if let Some(old_chat) = self.chat() {
+                old_chat.disconnect(handler_id);
+            }
+        }
+
         if let Some(ref chat) = chat {
             self.action_set_enabled(
                 "chat-history.leave-chat",
@@ -311,6 +498,24 @@ impl ChatHistory {
 
             let model = ChatHistoryModel::new(chat);
 
+            // The model ignores new messages while the newest messages aren't loaded, like when
+            // the chat is opened at the first unread message. Go back to the newest messages to
+            // show a sent message, and load a received one if they are about to be shown.
+            let handler_id = chat.connect_new_message(
+                clone!(@weak self as obj, @weak model => move |_, message| {
+                    if model.is_newest_loaded() {
+                        return;
+                    }
+
+                    if message.is_outgoing() {
+                        obj.scroll_to_bottom();
+                    } else {
+                        obj.load_newer_messages(&obj.imp().scrolled_window.vadjustment());
+                    }
+                }),
+            );
+            imp.new_message_handler_id.replace(Some(handler_id));
+
             // Request sponsored message, if needed
             let list_view_model: gio::ListModel = if matches!(chat.type_(), ChatType::Supergroup(supergroup) if supergroup.is_channel())
             {
@@ -329,24 +534,41 @@ impl ChatHistory {
                 model.clone().upcast()
             };
 
-            spawn(clone!(@weak model => async move {
-                while model.n_items() < MIN_N_ITEMS {
-                    let limit = MIN_N_ITEMS - model.n_items();
-                    match model.load_older_messages(limit as i32).await {
-                        Ok(can_load_more) => if !can_load_more {
-                            break;
-                        }
-                        Err(e) => {
-                            log::warn!("Couldn't load initial history messages: {}", e);
-                            break;
+            // Open the chat at the first unread message, if there's one
+            if chat.unread_count() > 0 && chat.last_read_inbox_message_id() != 0 {
+                spawn(clone!(@weak self as obj, @weak model => async move {
+                    obj.load_unread_messages(&model).await;
+                }));
+            } else {
+                spawn(clone!(@weak model => async move {
+                    while model.n_items() < MIN_N_ITEMS {
+                        let limit = MIN_N_ITEMS - model.n_items();
+                        match model.load_older_messages(limit as i32).await {
+                            Ok(can_load_more) => if !can_load_more {
+                                break;
+                            }
+                            Err(e) => {
+                                log::warn!("Couldn't load initial history messages: {}", e);
+                                break;
+                            }
                         }
                     }
+                }));
+            }
+
+            let selection = ChatHistorySelection::new(&list_view_model);
+            selection.connect_selection_changed(clone!(@weak self as obj => move |_, _, _| {
+                obj.update_selection();
+            }));
+            selection.connect_items_changed(clone!(@weak self as obj => move |_, _, _, _| {
+                if obj.selection_mode() {
+                    obj.update_selection();
                 }
             }));
-
-            let selection = gtk::NoSelection::new(Some(list_view_model));
             imp.list_view.set_model(Some(&selection));
 
+            imp.selection.replace(Some(selection));
+
             imp.model.replace(Some(model));
         }
 
@@ -354,10 +576,155 @@ impl ChatHistory {
         self.notify("chat");
     }
 
+    pub(crate) fn selection_mode(&self) -> bool {
+        self.imp().selection_mode.get()
+    }
+
+    pub(crate) fn set_selection_mode(&self, selection_mode: bool) {
+        if self.selection_mode() == selection_mode {
+            return;
+        }
+
+        let imp = self.imp();
+
+        imp.selection_mode.set(selection_mode);
+        if let Some(selection) = imp.selection.borrow().as_ref() {
+            selection.set_selection_mode(selection_mode);
+        }
+
+        self.update_selection();
+        self.notify("selection-mode");
+    }
+
+    fn selected_messages(&self) -> Vec<Message> {
+        self.imp()
+            .selection
+            .borrow()
+            .as_ref()
+            .map(ChatHistorySelection::selected_messages)
+            .unwrap_or_default()
+    }
+
+    fn update_selection(&self) {
+        let imp = self.imp();
+        let selection_mode = self.selection_mode();
+        let messages = self.selected_messages();
+
+        imp.selection_label.set_label(&ngettext_f(
+            "{num} Selected",
+            "{num} Selected",
+            messages.len() as u32,
+            &[("num", &messages.len().to_string())],
+        ));
+
+        let has_selection = selection_mode && !messages.is_empty();
+        self.action_set_enabled("chat-history.cancel-selection", selection_mode);
+        self.action_set_enabled(
+            "chat-history.delete-selected",
+            has_selection
+                && (messages.iter().all(Message::can_be_deleted_only_for_self)
+                    || messages.iter().all(Message::can_be_deleted_for_all_users)),
+        );
+        self.action_set_enabled(
+            "chat-history.forward-selected",
+            has_selection && messages.iter().all(Message::can_be_forwarded),
+        );
+        self.action_set_enabled("chat-history.copy-selected", has_selection);
+    }
+
+    async fn show_delete_selected_dialog(&self) {
+        let messages = self.selected_messages();
+        let chat = match messages.first() {
+            Some(message) => message.chat(),
+            None => return,
+        };
+
+        let dialog = adw::MessageDialog::new(
+            self.parent_window().as_ref(),
+            Some(&gettext("Confirm Message Deletion")),
+            Some(&ngettext_f(
+                "Do you want to delete {num} message?",
+                "Do you want to delete {num} messages?",
+                messages.len() as u32,
+                &[("num", &messages.len().to_string())],
+            )),
+        );
+        dialog.add_response("cancel", &gettext("_Cancel"));
+        if messages.iter().all(Message::can_be_deleted_only_for_self) {
+            dialog.add_response("delete", &gettext("_Delete for Me"));
+            dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+        }
+        if messages.iter().all(Message::can_be_deleted_for_all_users) {
+            dialog.add_response("revoke", &gettext("Delete for Ever_yone"));
+            dialog.set_response_appearance("revoke", adw::ResponseAppearance::Destructive);
+        }
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        let revoke = match dialog.choose_future().await.as_str() {
+            "delete" => false,
+            "revoke" => true,
+            _ => return,
+        };
+
+        self.set_selection_mode(false);
+
+        let message_ids = messages.iter().map(Message::id).collect();
+        if let Err(e) = chat.delete_messages(message_ids, revoke).await {
+            log::warn!("Error deleting messages (revoke = {}): {:?}", revoke, e);
+        }
+    }
+
+    fn forward_selected_messages(&self) {
+        let messages = self.selected_messages();
+        if messages.is_empty() {
+            return;
+        }
+
+        ForwardMessagesDialog::new(&self.parent_window(), messages).present();
+        self.set_selection_mode(false);
+    }
+
+    /// Copies the text of the selected messages, each one preceded by its sender and time.
+    fn copy_selected_messages(&self) {
+        let text = self
+            .selected_messages()
+            .iter()
+            .map(|message| {
+                let sender = strings::message_sender(message.sender(), true);
+                let time = glib::DateTime::from_unix_utc(message.date() as i64)
+                    .and_then(|t| t.to_local())
+                    .and_then(|t| t.format("%x %X"))
+                    .unwrap();
+
+                format!("{sender}, [{time}]\n{}", strings::message_content(message))
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        self.clipboard().set_text(&text);
+        self.add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+        self.set_selection_mode(false);
+    }
+
     pub(crate) fn sticky(&self) -> bool {
         self.imp().sticky.get()
     }
 
+    /// The history is sticky when it's scrolled to the bottom and it shows the last message
+    /// of the chat.
+    fn update_sticky(&self, adj: &gtk::Adjustment) {
+        let is_newest_loaded = self
+            .imp()
+            .model
+            .borrow()
+            .as_ref()
+            .map(ChatHistoryModel::is_newest_loaded)
+            .unwrap_or(true);
+
+        self.set_sticky(adj.value() + adj.page_size() >= adj.upper() && is_newest_loaded);
+    }
+
     fn set_sticky(&self, sticky: bool) {
         if self.sticky() == sticky {
             return;
@@ -367,6 +734,164 @@ impl ChatHistory {
         self.notify("sticky");
     }
 
+    /// Jumps from the message with id `origin_id` to the message with id `message_id`,
+    /// remembering the origin so that it's possible to jump back to it.
+    async fn jump_to_message(&self, origin_id: i64, message_id: i64) {
+        let imp = self.imp();
+
+        imp.jump_back_stack.borrow_mut().push(origin_id);
+        imp.back_revealer.set_reveal_child(true);
+
+        self.scroll_to_message(message_id).await;
+    }
+
+    async fn jump_back(&self) {
+        let imp = self.imp();
+
+        let message_id = imp.jump_back_stack.borrow_mut().pop();
+        imp.back_revealer
+            .set_reveal_child(!imp.jump_back_stack.borrow().is_empty());
+
+        if let Some(message_id) = message_id {
+            self.scroll_to_message(message_id).await;
+        }
+    }
+
+    fn clear_jump_back_stack(&self) {
+        let imp = self.imp();
+
+        imp.jump_back_stack.borrow_mut().clear();
+        imp.back_revealer.set_reveal_child(false);
+    }
+
+    /// Scrolls to the message with the given id and highlights it. If the message is not
+    /// loaded yet, the history is replaced with the messages around it.
+    async fn scroll_to_message(&self, message_id: i64) {
+        let model = match self.imp().model.borrow().clone() {
+            Some(model) => model,
+            None => return,
+        };
+
+        // Don't follow the bottom of the history while jumping around
+        self.imp().is_auto_scrolling.set(false);
+        self.set_sticky(false);
+
+        let mut attempts = 0;
+        let position = loop {
+            if let Some(position) = model.message_position(message_id) {
+                break position;
+            }
+
+            // Give up if the history keeps being loaded by something else
+            attempts += 1;
+            if attempts > MAX_SCROLL_LOAD_ATTEMPTS {
+                log::warn!("Couldn't load the messages around message {message_id} to scroll to");
+                return;
+            }
+
+            match model.load_messages_around(message_id, 50).await {
+                Ok(()) => {
+                    if model.message_position(message_id).is_none() {
+                        // The message doesn't exist anymore
+                        self.add_toast(adw::Toast::new(&gettext("The message was deleted")));
+                        return;
+                    }
+                }
+                Err(ChatHistoryError::AlreadyLoading) => {
+                    glib::timeout_future(std::time::Duration::from_millis(100)).await;
+                }
+                Err(ChatHistoryError::Tdlib(e)) => {
+                    log::warn!("Couldn't load messages to scroll to: {:?}", e);
+                    return;
+                }
+            }
+
+            // Stop if the chat was changed in the meantime
+            if self.imp().model.borrow().as_ref() != Some(&model) {
+                return;
+            }
+        };
+
+        self.scroll_to_item(&model, position);
+        self.highlight_message(message_id);
+    }
+
+    /// Scrolls to the item at the given position of the chat history model.
+    fn scroll_to_item(&self, model: &ChatHistoryModel, position: u32) {
+        let list_view = &*self.imp().list_view;
+
+        // The list view model can also contain items before the chat history, like the
+        // sponsored message
+        let offset = list_view
+            .model()
+            .map(|m| m.n_items() - model.n_items())
+            .unwrap_or_default();
+
+        list_view
+            .activate_action(
+                "list.scroll-to-item",
+                Some(&(offset + position).to_variant()),
+            )
+            .unwrap();
+    }
+
+    /// Briefly highlights the row of the message with the given id. The rows check the
+    /// highlighted message when they are bound, so this also works for the rows that the list
+    /// view hasn't created yet.
+    fn highlight_message(&self, message_id: i64) {
+        let imp = self.imp();
+
+        if let Some(source_id) = imp.highlight_source_id.take() {
+            source_id.remove();
+        }
+
+        self.set_highlighted_message_id(message_id);
+
+        let source_id = glib::timeout_add_local_once(
+            std::time::Duration::from_millis(1500),
+            clone!(@weak self as obj => move || {
+                obj.imp().highlight_source_id.take();
+                obj.set_highlighted_message_id(0);
+            }),
+        );
+        imp.highlight_source_id.replace(Some(source_id));
+    }
+
+    pub(crate) fn highlighted_message_id(&self) -> i64 {
+        self.imp().highlighted_message_id.get()
+    }
+
+    fn set_highlighted_message_id(&self, message_id: i64) {
+        if self.highlighted_message_id() == message_id {
+            return;
+        }
+
+        self.imp().highlighted_message_id.set(message_id);
+        self.notify("highlighted-message-id");
+    }
+
+    /// Scrolls to the last message of the chat, loading it first if needed.
+    fn scroll_to_bottom(&self) {
+        self.clear_jump_back_stack();
+
+        if let Some(model) = self.imp().model.borrow().clone() {
+            if !model.is_newest_loaded() {
+                spawn(clone!(@weak self as obj, @weak model => async move {
+                    if let Err(ChatHistoryError::Tdlib(e)) =
+                        model.load_newest_messages(MIN_N_ITEMS as i32).await
+                    {
+                        log::warn!("Couldn't load the newest chat messages: {:?}", e);
+                    }
+
+                    obj.scroll_down();
+                }));
+                return;
+            }
+        }
+
+        self.scroll_down();
+    }
+
     fn scroll_down(&self) {
         let imp = self.imp();
 
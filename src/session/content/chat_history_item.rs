@@ -9,7 +9,11 @@ use crate::tdlib::Message;
 #[boxed_type(name = "ContentChatHistoryItemType")]
 pub(crate) enum ChatHistoryItemType {
     Message(Message),
+    /// The messages of a media album, sorted from the oldest to the newest
+    Album(Vec<Message>),
     DayDivider(DateTime),
+    /// The divider above the first unread message, with the id of the last read message
+    UnreadDivider(i64),
 }
 
 mod imp {
@@ -60,32 +64,59 @@ impl ChatHistoryItem {
         glib::Object::builder().property("type", type_).build()
     }
 
+    pub(crate) fn for_album(messages: Vec<Message>) -> Self {
+        let type_ = ChatHistoryItemType::Album(messages);
+        glib::Object::builder().property("type", type_).build()
+    }
+
     pub(crate) fn for_day_divider(day: DateTime) -> Self {
         let type_ = ChatHistoryItemType::DayDivider(day);
         glib::Object::builder().property("type", type_).build()
     }
 
+    pub(crate) fn for_unread_divider(last_read_message_id: i64) -> Self {
+        let type_ = ChatHistoryItemType::UnreadDivider(last_read_message_id);
+        glib::Object::builder().property("type", type_).build()
+    }
+
     pub(crate) fn type_(&self) -> &ChatHistoryItemType {
         self.imp().type_.get().unwrap()
     }
 
-    pub(crate) fn message(&self) -> Option<&Message> {
-        if let ChatHistoryItemType::Message(message) = self.type_() {
-            Some(message)
-        } else {
-            None
+    /// Returns the messages of this item, sorted from the oldest to the newest.
+    pub(crate) fn messages(&self) -> &[Message] {
+        match self.type_() {
+            ChatHistoryItemType::Message(message) => std::slice::from_ref(message),
+            ChatHistoryItemType::Album(messages) => messages,
+            ChatHistoryItemType::DayDivider(_) | ChatHistoryItemType::UnreadDivider(_) => &[],
         }
     }
 
+    /// Returns the id of the media album of this item, or 0 if the item can't be grouped into
+    /// an album.
+    pub(crate) fn media_album_id(&self) -> i64 {
+        self.messages()
+            .first()
+            .map(album_id_of_message)
+            .unwrap_or_default()
+    }
+
     pub(crate) fn message_timestamp(&self) -> Option<DateTime> {
-        if let ChatHistoryItemType::Message(message) = self.type_() {
-            Some(
-                glib::DateTime::from_unix_utc(message.date().into())
-                    .and_then(|t| t.to_local())
-                    .unwrap(),
-            )
-        } else {
-            None
-        }
+        self.messages().last().map(|message| {
+            glib::DateTime::from_unix_utc(message.date().into())
+                .and_then(|t| t.to_local())
+                .unwrap()
+        })
+    }
+}
+
+/// Returns the id of the media album the message should be grouped into, or 0 if it should be
+/// shown on its own. Only photo and video albums are grouped.
+pub(crate) fn album_id_of_message(message: &Message) -> i64 {
+    use tdlib::enums::MessageContent::*;
+
+    match message.content().0 {
+        MessagePhoto(_) | MessageVideo(_) => message.media_album_id(),
+        _ => 0,
     }
 }
@@ -5,7 +5,7 @@ use gtk::{gio, glib};
 use std::cmp::Ordering;
 use thiserror::Error;
 
-use crate::session::content::{ChatHistoryItem, ChatHistoryItemType};
+use crate::session::content::{album_id_of_message, ChatHistoryItem, ChatHistoryItemType};
 use crate::tdlib::{Chat, Message};
 
 #[derive(Error, Debug)]
@@ -27,6 +27,12 @@ mod imp {
     pub(crate) struct ChatHistoryModel {
         pub(super) chat: WeakRef<Chat>,
         pub(super) is_loading: Cell<bool>,
+        /// Whether the history reaches the last message of the chat. When it doesn't, new
+        /// messages are not added, as they would not be contiguous to the loaded ones.
+        pub(super) is_newest_loaded: Cell<bool>,
+        /// The id of the last read message, below which the unread divider is shown, or 0 if
+        /// there's no unread divider
+        pub(super) last_read_message_id: Cell<i64>,
         pub(super) list: RefCell<VecDeque<ChatHistoryItem>>,
     }
 
@@ -86,9 +92,12 @@ impl ChatHistoryModel {
         let obj: ChatHistoryModel = glib::Object::new();
 
         obj.imp().chat.set(Some(chat));
+        obj.imp().is_newest_loaded.set(true);
 
         chat.connect_new_message(clone!(@weak obj => move |_, message| {
-            obj.push_front(message);
+            if obj.is_newest_loaded() {
+                obj.push_front(message);
+            }
         }));
         chat.connect_deleted_message(clone!(@weak obj => move |_, message| {
             obj.remove(message);
@@ -112,13 +121,16 @@ impl ChatHistoryModel {
             .borrow()
             .iter()
             .rev()
-            .find_map(|item| item.message())
+            .find_map(|item| item.messages().first())
             .map(|m| m.id())
             .unwrap_or_default();
 
         imp.is_loading.set(true);
 
-        let result = self.chat().get_chat_history(oldest_message_id, limit).await;
+        let result = self
+            .chat()
+            .get_chat_history(oldest_message_id, 0, limit)
+            .await;
 
         imp.is_loading.set(false);
 
@@ -132,6 +144,234 @@ impl ChatHistoryModel {
         Ok(true)
     }
 
+    /// Loads newer messages, when the history doesn't reach the last message of the chat.
+    ///
+    /// Returns `true` when more messages can be loaded.
+    pub(crate) async fn load_newer_messages(&self, limit: i32) -> Result<bool, ChatHistoryError> {
+        let imp = self.imp();
+
+        if imp.is_newest_loaded.get() {
+            return Ok(false);
+        }
+
+        if imp.is_loading.get() {
+            return Err(ChatHistoryError::AlreadyLoading);
+        }
+
+        let newest_message_id = imp
+            .list
+            .borrow()
+            .iter()
+            .find_map(|item| item.messages().last())
+            .map(|m| m.id())
+            .unwrap_or_default();
+
+        imp.is_loading.set(true);
+
+        let result = self
+            .chat()
+            .get_chat_history(newest_message_id, -limit, limit + 1)
+            .await;
+
+        imp.is_loading.set(false);
+
+        // The result also contains the newest loaded message and the ones older than it
+        let messages: Vec<Message> = result
+            .map_err(ChatHistoryError::Tdlib)?
+            .into_iter()
+            .filter(|m| m.id() > newest_message_id)
+            .collect();
+
+        if messages.is_empty() {
+            imp.is_newest_loaded.set(true);
+            return Ok(false);
+        }
+
+        for message in messages.into_iter().rev() {
+            self.push_front(message);
+        }
+
+        self.update_is_newest_loaded();
+        Ok(!self.is_newest_loaded())
+    }
+
+    /// Replaces the loaded history with the messages around the given message.
+    pub(crate) async fn load_messages_around(
+        &self,
+        message_id: i64,
+        limit: i32,
+    ) -> Result<(), ChatHistoryError> {
+        let imp = self.imp();
+
+        if imp.is_loading.get() {
+            return Err(ChatHistoryError::AlreadyLoading);
+        }
+
+        imp.is_loading.set(true);
+
+        let result = self
+            .chat()
+            .get_chat_history(message_id, -limit / 2, limit)
+            .await;
+
+        imp.is_loading.set(false);
+
+        let messages = result.map_err(ChatHistoryError::Tdlib)?;
+
+        imp.is_newest_loaded.set(false);
+        self.clear();
+
+        self.append(messages);
+        self.update_is_newest_loaded();
+
+        Ok(())
+    }
+
+    /// Replaces the loaded history with the messages around the first unread message of the
+    /// chat, which is marked by an unread divider.
+    pub(crate) async fn load_unread_messages(&self, limit: i32) -> Result<(), ChatHistoryError> {
+        let last_read_message_id = self.chat().last_read_inbox_message_id();
+        self.imp().last_read_message_id.set(last_read_message_id);
+
+        self.load_messages_around(last_read_message_id, limit).await
+    }
+
+    /// Replaces the loaded history with the newest messages of the chat, if it doesn't already
+    /// reach them.
+    pub(crate) async fn load_newest_messages(&self, limit: i32) -> Result<(), ChatHistoryError> {
+        let imp = self.imp();
+
+        if imp.is_newest_loaded.get() {
+            return Ok(());
+        }
+
+        if imp.is_loading.get() {
+            return Err(ChatHistoryError::AlreadyLoading);
+        }
+
+        self.clear();
+        imp.is_newest_loaded.set(true);
+
+        self.load_older_messages(limit).await.map(|_| ())
+    }
+
+    pub(crate) fn is_newest_loaded(&self) -> bool {
+        self.imp().is_newest_loaded.get()
+    }
+
+    fn update_is_newest_loaded(&self) {
+        let newest_message_id = self
+            .imp()
+            .list
+            .borrow()
+            .iter()
+            .find_map(|item| item.messages().last())
+            .map(|m| m.id());
+        let last_message_id = self.chat().last_message().map(|m| m.id());
+
+        if newest_message_id.is_some() && newest_message_id >= last_message_id {
+            self.imp().is_newest_loaded.set(true);
+        }
+    }
+
+    fn clear(&self) {
+        let removed = {
+            let mut list = self.imp().list.borrow_mut();
+            let removed = list.len();
+            list.clear();
+            removed
+        };
+
+        self.upcast_ref::<gio::ListModel>()
+            .items_changed(0, removed as u32, 0);
+    }
+
+    /// Returns the position of the unread divider, if it's in the history.
+    pub(crate) fn unread_divider_position(&self) -> Option<u32> {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .position(|item| matches!(item.type_(), ChatHistoryItemType::UnreadDivider(_)))
+            .map(|position| position as u32)
+    }
+
+    /// Inserts the unread divider above the first unread message, once both the first unread
+    /// message and the last read one are loaded.
+    fn insert_unread_divider(&self) {
+        let imp = self.imp();
+        let last_read_message_id = imp.last_read_message_id.get();
+
+        if last_read_message_id == 0 || self.unread_divider_position().is_some() {
+            return;
+        }
+
+        let position = {
+            let list = imp.list.borrow();
+            let mut position = None;
+            let mut has_read_message = false;
+
+            for (index, item) in list.iter().enumerate() {
+                match item.messages().first() {
+                    Some(message) if message.id() > last_read_message_id => {
+                        position = Some(index + 1);
+                    }
+                    Some(_) => {
+                        has_read_message = true;
+                        break;
+                    }
+                    None => {}
+                }
+            }
+
+            match position {
+                Some(position) if has_read_message => position,
+                _ => return,
+            }
+        };
+
+        imp.list.borrow_mut().insert(
+            position,
+            ChatHistoryItem::for_unread_divider(last_read_message_id),
+        );
+
+        self.upcast_ref::<gio::ListModel>()
+            .items_changed(position as u32, 0, 1);
+    }
+
+    /// Removes the unread divider, if there are no more messages below it.
+    fn remove_unneeded_unread_divider(&self) {
+        let position = match self.unread_divider_position() {
+            Some(position) => position as usize,
+            None => return,
+        };
+
+        let is_needed = self
+            .imp()
+            .list
+            .borrow()
+            .range(..position)
+            .any(|item| !item.messages().is_empty());
+
+        if !is_needed {
+            self.imp().list.borrow_mut().remove(position);
+
+            self.upcast_ref::<gio::ListModel>()
+                .items_changed(position as u32, 1, 0);
+        }
+    }
+
+    /// Returns the position of the item that contains the message with the given id, if it is
+    /// already loaded.
+    pub(crate) fn message_position(&self, message_id: i64) -> Option<u32> {
+        self.imp()
+            .list
+            .borrow()
+            .iter()
+            .position(|item| item.messages().iter().any(|m| m.id() == message_id))
+            .map(|position| position as u32)
+    }
+
     fn items_changed(&self, position: u32, removed: u32, added: u32) {
         let imp = self.imp();
 
@@ -233,28 +473,72 @@ impl ChatHistoryModel {
     }
 
     fn push_front(&self, message: Message) {
+        let album_id = album_id_of_message(&message);
+
+        if album_id != 0 {
+            let front = self.imp().list.borrow().front().cloned();
+            if let Some(front) = front.filter(|item| item.media_album_id() == album_id) {
+                let mut messages = front.messages().to_vec();
+                messages.push(message);
+
+                self.replace_item(0, ChatHistoryItem::for_album(messages));
+                return;
+            }
+        }
+
         self.imp()
             .list
             .borrow_mut()
             .push_front(ChatHistoryItem::for_message(message));
 
         self.items_changed(0, 0, 1);
+        self.insert_unread_divider();
     }
 
     fn append(&self, messages: Vec<Message>) {
         let imp = self.imp();
-        let added = messages.len();
+        let mut messages = messages.into_iter().peekable();
+
+        // An album can be split between two loaded pages, so the oldest album of the history
+        // may need to be completed first
+        let oldest_item = imp
+            .list
+            .borrow()
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, item)| !item.messages().is_empty())
+            .map(|(position, item)| (position, item.clone()));
 
-        imp.list.borrow_mut().reserve(added);
+        if let Some((position, item)) = oldest_item {
+            let album_id = item.media_album_id();
 
-        for message in messages {
-            imp.list
-                .borrow_mut()
-                .push_back(ChatHistoryItem::for_message(message));
+            if album_id != 0 {
+                let mut album = item.messages().to_vec();
+                while let Some(message) =
+                    messages.next_if(|message| album_id_of_message(message) == album_id)
+                {
+                    album.insert(0, message);
+                }
+
+                if album.len() > item.messages().len() {
+                    self.replace_item(position, ChatHistoryItem::for_album(album));
+                }
+            }
         }
 
+        let items = group_albums(messages);
+        let added = items.len();
+
+        if added == 0 {
+            return;
+        }
+
+        imp.list.borrow_mut().extend(items);
+
         let index = imp.list.borrow().len() - added;
         self.items_changed(index as u32, 0, added as u32);
+        self.insert_unread_divider();
     }
 
     fn remove(&self, message: Message) {
@@ -262,8 +546,8 @@ impl ChatHistoryModel {
 
         // Put this in a block, so that we only need to borrow the list once and the runtime
         // borrow checker does not panic in Self::items_changed when it borrows the list again.
-        let index = {
-            let mut list = imp.list.borrow_mut();
+        let (index, item) = {
+            let list = imp.list.borrow();
 
             // The elements in this list are ordered. While the day dividers are ordered
             // only by their date time, the messages are additionally sorted by their id. We
@@ -273,6 +557,15 @@ impl ChatHistoryModel {
                     ChatHistoryItemType::Message(other_message) => {
                         message.id().cmp(&other_message.id())
                     }
+                    ChatHistoryItemType::Album(album) => {
+                        if message.id() > album.last().unwrap().id() {
+                            Ordering::Greater
+                        } else if message.id() < album.first().unwrap().id() {
+                            Ordering::Less
+                        } else {
+                            Ordering::Equal
+                        }
+                    }
                     ChatHistoryItemType::DayDivider(date_time) => {
                         let ordering = glib::DateTime::from_unix_utc(message.date() as i64)
                             .unwrap()
@@ -285,17 +578,178 @@ impl ChatHistoryModel {
                             ordering
                         }
                     }
+                    ChatHistoryItemType::UnreadDivider(last_read_message_id) => {
+                        if message.id() > *last_read_message_id {
+                            Ordering::Greater
+                        } else {
+                            Ordering::Less
+                        }
+                    }
                 })
-                .unwrap();
+                .ok();
+
+            // The message may not be in the loaded part of the history, for example when it
+            // has been received while the newest messages weren't loaded
+            let index = match index {
+                Some(index) => index,
+                None => return,
+            };
+
+            (index, list[index].clone())
+        };
+
+        // Only remove the message from its album, if there are other messages left in it
+        if let ChatHistoryItemType::Album(album) = item.type_() {
+            if !album.iter().any(|m| m.id() == message.id()) {
+                return;
+            }
+
+            let mut remaining: Vec<Message> = album
+                .iter()
+                .filter(|m| m.id() != message.id())
+                .cloned()
+                .collect();
+
+            if !remaining.is_empty() {
+                let item = if remaining.len() == 1 {
+                    ChatHistoryItem::for_message(remaining.remove(0))
+                } else {
+                    ChatHistoryItem::for_album(remaining)
+                };
+
+                self.replace_item(index, item);
+                return;
+            }
+        }
+
+        imp.list.borrow_mut().remove(index);
+        self.items_changed(index as u32, 1, 0);
+        self.remove_unneeded_unread_divider();
+    }
+
+    /// Replaces the item at the given position and updates the day dividers around it, as the
+    /// item may now be on another day, e.g. after adding or removing a message of an album.
+    fn replace_item(&self, position: usize, item: ChatHistoryItem) {
+        let (start, removed, added) = {
+            let mut list = self.imp().list.borrow_mut();
+            let timestamp = item.message_timestamp();
+            let day = timestamp.as_ref().map(|t| t.ymd());
+            let mut start = position;
+            let mut removed = 1;
+            let mut added = 1;
+
+            list[position] = item;
+
+            // The day divider after the item, which is needed if the next older message is
+            // on another day
+            let older_day = list
+                .range(position + 1..)
+                .find_map(|item| item.message_timestamp())
+                .map(|t| t.ymd());
+            let needs_divider = timestamp.is_some() && day != older_day;
+            let has_divider = matches!(
+                list.get(position + 1).map(|item| item.type_()),
+                Some(ChatHistoryItemType::DayDivider(_))
+            );
+
+            if has_divider {
+                removed += 1;
+
+                if needs_divider {
+                    list[position + 1] = ChatHistoryItem::for_day_divider(timestamp.unwrap());
+                    added += 1;
+                } else {
+                    list.remove(position + 1);
+                }
+            } else if needs_divider {
+                list.insert(
+                    position + 1,
+                    ChatHistoryItem::for_day_divider(timestamp.unwrap()),
+                );
+                added += 1;
+            }
+
+            // The day divider before the item, which belongs to the next newer message and is
+            // needed if that message is on another day
+            let newer = list
+                .range(..position)
+                .enumerate()
+                .rev()
+                .find_map(|(index, item)| item.message_timestamp().map(|t| (index, t)));
+
+            if let Some((newer_position, newer_timestamp)) = newer {
+                let divider_position = (newer_position + 1..position).find(|index| {
+                    matches!(list[*index].type_(), ChatHistoryItemType::DayDivider(_))
+                });
+                let needs_divider = day.is_some() && day != Some(newer_timestamp.ymd());
+
+                match divider_position {
+                    Some(divider_position) if !needs_divider => {
+                        list.remove(divider_position);
+
+                        start = divider_position;
+                        removed += position - divider_position;
+                        added += position - divider_position - 1;
+                    }
+                    None if needs_divider => {
+                        let divider_position = newer_position + 1;
+                        list.insert(
+                            divider_position,
+                            ChatHistoryItem::for_day_divider(newer_timestamp),
+                        );
+
+                        start = divider_position;
+                        removed += position - divider_position;
+                        added += position - divider_position + 1;
+                    }
+                    _ => {}
+                }
+            }
 
-            list.remove(index);
-            index as u32
+            (start as u32, removed as u32, added as u32)
         };
 
-        self.items_changed(index, 1, 0);
+        self.upcast_ref::<gio::ListModel>()
+            .items_changed(start, removed, added);
     }
 
     pub(crate) fn chat(&self) -> Chat {
         self.imp().chat.upgrade().unwrap()
     }
 }
+
+/// Groups the consecutive messages of the same media album into a single item. The messages
+/// must be sorted from the newest to the oldest.
+fn group_albums(messages: impl Iterator<Item = Message>) -> Vec<ChatHistoryItem> {
+    let mut items = Vec::new();
+    let mut album: Vec<Message> = Vec::new();
+
+    for message in messages {
+        let album_id = album_id_of_message(&message);
+        let is_same_album = album_id != 0
+            && album
+                .first()
+                .map(|m| album_id_of_message(m) == album_id)
+                .unwrap_or_default();
+
+        if !is_same_album {
+            items.extend(album_to_item(std::mem::take(&mut album)));
+        }
+
+        album.push(message);
+    }
+
+    items.extend(album_to_item(album));
+    items
+}
+
+fn album_to_item(mut album: Vec<Message>) -> Option<ChatHistoryItem> {
+    match album.len() {
+        0 => None,
+        1 => Some(ChatHistoryItem::for_message(album.remove(0))),
+        _ => {
+            album.reverse();
+            Some(ChatHistoryItem::for_album(album))
+        }
+    }
+}
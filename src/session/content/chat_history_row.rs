@@ -1,14 +1,17 @@
 use adw::prelude::BinExt;
 use adw::subclass::prelude::BinImpl;
 use gettextrs::gettext;
+use glib::clone;
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use tdlib::enums::MessageContent;
 
-use crate::session::content::{ChatHistoryItem, ChatHistoryItemType, EventRow, MessageRow};
+use crate::session::content::{
+    ChatHistory, ChatHistoryItem, ChatHistoryItemType, EventRow, MessageRow,
+};
 use crate::strings;
-use crate::tdlib::SponsoredMessage;
+use crate::tdlib::{Message, SponsoredMessage};
 
 mod imp {
     use super::*;
@@ -19,6 +22,8 @@ mod imp {
     pub(crate) struct ChatHistoryRow {
         /// An `ChatHistoryItem` or `SponsoredMessage`
         pub(super) item: RefCell<Option<glib::Object>>,
+        pub(super) chat_history: glib::WeakRef<ChatHistory>,
+        pub(super) highlight_handler_id: RefCell<Option<glib::SignalHandlerId>>,
     }
 
     #[glib::object_subclass]
@@ -57,7 +62,17 @@ mod imp {
         }
     }
 
-    impl WidgetImpl for ChatHistoryRow {}
+    impl WidgetImpl for ChatHistoryRow {
+        fn map(&self) {
+            self.parent_map();
+            self.obj().connect_chat_history();
+        }
+
+        fn unmap(&self) {
+            self.obj().disconnect_chat_history();
+            self.parent_unmap();
+        }
+    }
     impl BinImpl for ChatHistoryRow {}
 }
 
@@ -115,6 +130,9 @@ impl ChatHistoryRow {
                             _ => self.update_or_create_message_row(message.to_owned().upcast()),
                         }
                     }
+                    ChatHistoryItemType::Album(messages) => {
+                        self.update_or_create_album_row(messages.clone());
+                    }
                     ChatHistoryItemType::DayDivider(date) => {
                         let fmt = if date.year() == glib::DateTime::now_local().unwrap().year() {
                             // Translators: This is a date format in the day divider without the year
@@ -128,6 +146,10 @@ impl ChatHistoryRow {
                         let child = self.get_or_create_event_row();
                         child.set_label(&date);
                     }
+                    ChatHistoryItemType::UnreadDivider(_) => {
+                        self.get_or_create_event_row()
+                            .set_label(&gettext("Unread Messages"));
+                    }
                 }
             } else if let Some(sponsored_message) = item.downcast_ref::<SponsoredMessage>() {
                 let content = &sponsored_message.content().0;
@@ -142,9 +164,73 @@ impl ChatHistoryRow {
         }
 
         self.imp().item.replace(item);
+        self.update_highlight();
         self.notify("item");
     }
 
+    /// Follows the message highlighted by the chat history the row is in.
+    fn connect_chat_history(&self) {
+        let imp = self.imp();
+
+        let chat_history = match self.ancestor(ChatHistory::static_type()) {
+            Some(chat_history) => chat_history.downcast::<ChatHistory>().unwrap(),
+            None => return,
+        };
+
+        let handler_id = chat_history.connect_notify_local(
+            Some("highlighted-message-id"),
+            clone!(@weak self as obj => move |_, _| {
+                obj.update_highlight();
+            }),
+        );
+        imp.chat_history.set(Some(&chat_history));
+        imp.highlight_handler_id.replace(Some(handler_id));
+
+        self.update_highlight();
+    }
+
+    fn disconnect_chat_history(&self) {
+        let imp = self.imp();
+
+        if let Some(handler_id) = imp.highlight_handler_id.take() {
+            if let Some(chat_history) = imp.chat_history.upgrade() {
+                chat_history.disconnect(handler_id);
+            }
+        }
+        imp.chat_history.set(None);
+    }
+
+    /// Highlights the list row if the item contains the highlighted message.
+    fn update_highlight(&self) {
+        let row = match self.parent() {
+            Some(row) => row,
+            None => return,
+        };
+
+        let highlighted_message_id = self
+            .imp()
+            .chat_history
+            .upgrade()
+            .map(|chat_history| chat_history.highlighted_message_id())
+            .unwrap_or_default();
+        let is_highlighted = highlighted_message_id != 0
+            && self
+                .item()
+                .and_downcast::<ChatHistoryItem>()
+                .map(|item| {
+                    item.messages()
+                        .iter()
+                        .any(|m| m.id() == highlighted_message_id)
+                })
+                .unwrap_or_default();
+
+        if is_highlighted {
+            row.add_css_class("highlighted");
+        } else {
+            row.remove_css_class("highlighted");
+        }
+    }
+
     fn update_or_create_message_row(&self, message: glib::Object) {
         match self.child().and_then(|w| w.downcast::<MessageRow>().ok()) {
             Some(child) => child.set_message(message),
@@ -155,6 +241,17 @@ impl ChatHistoryRow {
         }
     }
 
+    fn update_or_create_album_row(&self, messages: Vec<Message>) {
+        match self.child().and_then(|w| w.downcast::<MessageRow>().ok()) {
+            Some(child) => child.set_album(messages),
+            None => {
+                let child = MessageRow::new(&messages[0].clone().upcast());
+                child.set_album(messages);
+                self.set_child(Some(&child));
+            }
+        }
+    }
+
     fn get_or_create_event_row(&self) -> EventRow {
         if let Some(Ok(child)) = self.child().map(|w| w.downcast::<EventRow>()) {
             child
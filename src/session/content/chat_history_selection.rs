@@ -0,0 +1,318 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib};
+use std::collections::HashSet;
+
+use crate::session::content::ChatHistoryItem;
+use crate::tdlib::Message;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default)]
+    pub(crate) struct ChatHistorySelection {
+        pub(super) model: RefCell<Option<gio::ListModel>>,
+        pub(super) signal_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) selection_mode: Cell<bool>,
+        /// The ids of the selected messages
+        pub(super) selected: RefCell<HashSet<i64>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ChatHistorySelection {
+        const NAME: &'static str = "ContentChatHistorySelection";
+        type Type = super::ChatHistorySelection;
+        type Interfaces = (gio::ListModel, gtk::SelectionModel);
+    }
+
+    impl ObjectImpl for ChatHistorySelection {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![
+                    glib::ParamSpecObject::builder::<gio::ListModel>("model")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("selection-mode")
+                        .explicit_notify()
+                        .build(),
+                ]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "model" => obj.set_model(value.get().unwrap()),
+                "selection-mode" => obj.set_selection_mode(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "model" => obj.model().to_value(),
+                "selection-mode" => obj.selection_mode().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            self.obj().disconnect_model_signal();
+        }
+    }
+
+    impl ListModelImpl for ChatHistorySelection {
+        fn item_type(&self) -> glib::Type {
+            glib::Object::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.model
+                .borrow()
+                .as_ref()
+                .map(|m| m.n_items())
+                .unwrap_or_default()
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.model.borrow().as_ref().and_then(|m| m.item(position))
+        }
+    }
+
+    impl SelectionModelImpl for ChatHistorySelection {
+        fn is_selected(&self, position: u32) -> bool {
+            let ids = self.obj().message_ids(position);
+            let selected = self.selected.borrow();
+
+            !ids.is_empty() && ids.iter().all(|id| selected.contains(id))
+        }
+
+        fn selection_in_range(&self, position: u32, n_items: u32) -> gtk::Bitset {
+            let result = gtk::Bitset::new_empty();
+
+            for position in position..position + n_items {
+                if self.is_selected(position) {
+                    result.add(position);
+                }
+            }
+
+            result
+        }
+
+        fn select_item(&self, position: u32, unselect_rest: bool) -> bool {
+            let obj = self.obj();
+
+            if !obj.selection_mode() {
+                return false;
+            }
+
+            // In selection mode, a plain click toggles the clicked item instead of replacing
+            // the whole selection
+            if unselect_rest && self.is_selected(position) {
+                return self.unselect_item(position);
+            }
+
+            obj.set_selected(position, 1, true);
+            true
+        }
+
+        fn unselect_item(&self, position: u32) -> bool {
+            let obj = self.obj();
+
+            if !obj.selection_mode() {
+                return false;
+            }
+
+            obj.set_selected(position, 1, false);
+            true
+        }
+
+        fn select_range(&self, position: u32, n_items: u32, _unselect_rest: bool) -> bool {
+            let obj = self.obj();
+
+            if !obj.selection_mode() {
+                return false;
+            }
+
+            obj.set_selected(position, n_items, true);
+            true
+        }
+
+        fn unselect_range(&self, position: u32, n_items: u32) -> bool {
+            let obj = self.obj();
+
+            if !obj.selection_mode() {
+                return false;
+            }
+
+            obj.set_selected(position, n_items, false);
+            true
+        }
+
+        fn select_all(&self) -> bool {
+            self.select_range(0, self.n_items(), false)
+        }
+
+        fn unselect_all(&self) -> bool {
+            let obj = self.obj();
+
+            if !obj.selection_mode() {
+                return false;
+            }
+
+            obj.clear_selection();
+            true
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A selection model for the chat history, which allows to select the messages of the
+    /// chat only when the selection mode is enabled.
+    pub(crate) struct ChatHistorySelection(ObjectSubclass<imp::ChatHistorySelection>)
+        @implements gio::ListModel, gtk::SelectionModel;
+}
+
+impl ChatHistorySelection {
+    pub(crate) fn new(model: &impl IsA<gio::ListModel>) -> Self {
+        glib::Object::builder().property("model", model).build()
+    }
+
+    /// Returns the ids of the messages of the item at the given position. Items that are not
+    /// messages, like day dividers or sponsored messages, can't be selected.
+    fn message_ids(&self, position: u32) -> Vec<i64> {
+        self.item(position)
+            .and_then(|item| item.downcast::<ChatHistoryItem>().ok())
+            .map(|item| item.messages().iter().map(Message::id).collect())
+            .unwrap_or_default()
+    }
+
+    fn set_selected(&self, position: u32, n_items: u32, selected: bool) {
+        {
+            let mut selected_ids = self.imp().selected.borrow_mut();
+
+            for position in position..position + n_items {
+                for id in self.message_ids(position) {
+                    if selected {
+                        selected_ids.insert(id);
+                    } else {
+                        selected_ids.remove(&id);
+                    }
+                }
+            }
+        }
+
+        self.selection_changed(position, n_items);
+    }
+
+    fn clear_selection(&self) {
+        self.imp().selected.borrow_mut().clear();
+        self.selection_changed(0, self.n_items());
+    }
+
+    /// Selects the item containing the message with the given id.
+    pub(crate) fn select_message(&self, message_id: i64) {
+        let position = (0..self.n_items()).find(|p| self.message_ids(*p).contains(&message_id));
+
+        if let Some(position) = position {
+            self.set_selected(position, 1, true);
+        }
+    }
+
+    /// Returns the selected messages that are in the model, sorted from the oldest to the
+    /// newest.
+    pub(crate) fn selected_messages(&self) -> Vec<Message> {
+        let selected = self.imp().selected.borrow();
+
+        let mut messages: Vec<Message> = (0..self.n_items())
+            .filter_map(|p| self.item(p))
+            .filter_map(|item| item.downcast::<ChatHistoryItem>().ok())
+            .flat_map(|item| item.messages().to_vec())
+            .filter(|message| selected.contains(&message.id()))
+            .collect();
+
+        messages.sort_by_key(Message::id);
+        messages
+    }
+
+    fn model_items_changed(&self, position: u32, removed: u32, added: u32) {
+        if removed > 0 {
+            // Forget about the messages that are not in the model anymore
+            let ids: HashSet<i64> = (0..self.n_items())
+                .flat_map(|p| self.message_ids(p))
+                .collect();
+            self.imp()
+                .selected
+                .borrow_mut()
+                .retain(|id| ids.contains(id));
+        }
+
+        self.items_changed(position, removed, added);
+    }
+
+    fn disconnect_model_signal(&self) {
+        if let Some(model) = self.model() {
+            let handler = self.imp().signal_handler.take().unwrap();
+            model.disconnect(handler);
+        }
+    }
+
+    pub(crate) fn model(&self) -> Option<gio::ListModel> {
+        self.imp().model.borrow().clone()
+    }
+
+    pub(crate) fn set_model(&self, model: Option<gio::ListModel>) {
+        if self.model() == model {
+            return;
+        }
+
+        let n_items_before = self.n_items();
+        self.disconnect_model_signal();
+
+        let imp = self.imp();
+        let n_items = if let Some(ref model) = model {
+            let handler =
+                model.connect_items_changed(clone!(@weak self as obj => move |_, p, r, a| {
+                    obj.model_items_changed(p, r, a);
+                }));
+            imp.signal_handler.replace(Some(handler));
+
+            model.n_items()
+        } else {
+            0
+        };
+
+        imp.model.replace(model);
+        imp.selected.borrow_mut().clear();
+
+        self.items_changed(0, n_items_before, n_items);
+
+        self.notify("model");
+    }
+
+    pub(crate) fn selection_mode(&self) -> bool {
+        self.imp().selection_mode.get()
+    }
+
+    /// Enables or disables the selection mode. Disabling it clears the selection.
+    pub(crate) fn set_selection_mode(&self, selection_mode: bool) {
+        if self.selection_mode() == selection_mode {
+            return;
+        }
+
+        self.imp().selection_mode.set(selection_mode);
+
+        if !selection_mode {
+            self.clear_selection();
+        }
+
+        self.notify("selection-mode");
+    }
+}
@@ -8,6 +8,7 @@ use tdlib::functions;
 use tdlib::types::{BasicGroupFullInfo, SupergroupFullInfo};
 
 use crate::i18n::ngettext_f;
+use crate::session::content::ChatSharedMedia;
 use crate::tdlib::{BasicGroup, BoxedUserStatus, Chat, ChatType, Supergroup, User};
 use crate::utils::spawn;
 use crate::{expressions, strings};
@@ -38,6 +39,7 @@ mod imp {
         type ParentType = adw::Window;
 
         fn class_init(klass: &mut Self::Class) {
+            ChatSharedMedia::static_type();
             klass.bind_template();
         }
 
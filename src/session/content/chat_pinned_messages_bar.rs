@@ -0,0 +1,216 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+
+use crate::i18n::gettext_f;
+use crate::strings;
+use crate::tdlib::{Chat, Message};
+use crate::utils::spawn;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/content-chat-pinned-messages-bar.ui")]
+    pub(crate) struct ChatPinnedMessagesBar {
+        pub(super) chat: RefCell<Option<Chat>>,
+        pub(super) chat_handler: RefCell<Option<glib::SignalHandlerId>>,
+        /// The pinned messages, from the newest to the oldest
+        pub(super) messages: RefCell<Vec<Message>>,
+        pub(super) current_index: Cell<usize>,
+        #[template_child]
+        pub(super) revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Inscription>,
+        #[template_child]
+        pub(super) message_label: TemplateChild<gtk::Inscription>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ChatPinnedMessagesBar {
+        const NAME: &'static str = "ContentChatPinnedMessagesBar";
+        type Type = super::ChatPinnedMessagesBar;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ChatPinnedMessagesBar {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Chat>("chat")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => obj.set_chat(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => obj.chat().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            if let (Some(chat), Some(handler_id)) = (self.chat.take(), self.chat_handler.take()) {
+                chat.disconnect(handler_id);
+            }
+
+            self.revealer.unparent();
+        }
+    }
+
+    impl WidgetImpl for ChatPinnedMessagesBar {}
+}
+
+glib::wrapper! {
+    pub(crate) struct ChatPinnedMessagesBar(ObjectSubclass<imp::ChatPinnedMessagesBar>)
+        @extends gtk::Widget;
+}
+
+#[gtk::template_callbacks]
+impl ChatPinnedMessagesBar {
+    /// Jumps to the shown pinned message and then shows the previous one, so that clicking
+    /// repeatedly cycles through all the pinned messages.
+    #[template_callback]
+    fn on_clicked(&self) {
+        let imp = self.imp();
+        let messages = imp.messages.borrow().clone();
+
+        if let Some(message) = messages.get(imp.current_index.get()) {
+            self.activate_action(
+                "chat-history.scroll-to-message",
+                Some(&message.id().to_variant()),
+            )
+            .unwrap();
+
+            imp.current_index
+                .set((imp.current_index.get() + 1) % messages.len());
+            self.update_current_message();
+        }
+    }
+
+    fn load_pinned_messages(&self) {
+        let chat = match self.chat() {
+            Some(chat) => chat,
+            None => {
+                self.set_messages(Vec::new());
+                return;
+            }
+        };
+
+        spawn(clone!(@weak self as obj => async move {
+            match chat.pinned_messages().await {
+                Ok(messages) => {
+                    // Ignore the result if the chat changed in the meantime
+                    if obj.chat().as_ref() == Some(&chat) {
+                        obj.set_messages(messages);
+                    }
+                }
+                Err(e) => log::warn!("Error loading the pinned messages: {e:?}"),
+            }
+        }));
+    }
+
+    fn set_messages(&self, messages: Vec<Message>) {
+        let imp = self.imp();
+
+        // Keep showing the same message, if it is still pinned
+        let current_id = imp
+            .messages
+            .borrow()
+            .get(imp.current_index.get())
+            .map(Message::id);
+        let current_index = current_id
+            .and_then(|id| messages.iter().position(|m| m.id() == id))
+            .unwrap_or_default();
+
+        imp.current_index.set(current_index);
+        imp.messages.replace(messages);
+
+        self.update_current_message();
+    }
+
+    fn update_current_message(&self) {
+        let imp = self.imp();
+        let messages = imp.messages.borrow();
+
+        match messages.get(imp.current_index.get()) {
+            Some(message) => {
+                let title = if messages.len() > 1 {
+                    // The oldest pinned message is the first one
+                    let number = messages.len() - imp.current_index.get();
+                    gettext_f(
+                        "Pinned Message #{number}",
+                        &[("number", &number.to_string())],
+                    )
+                } else {
+                    gettext("Pinned Message")
+                };
+
+                imp.title_label.set_text(Some(&title));
+                imp.message_label
+                    .set_text(Some(&strings::message_content(message).replace('\n', " ")));
+                imp.revealer.set_reveal_child(true);
+            }
+            None => imp.revealer.set_reveal_child(false),
+        }
+    }
+
+    pub(crate) fn chat(&self) -> Option<Chat> {
+        self.imp().chat.borrow().clone()
+    }
+
+    pub(crate) fn set_chat(&self, chat: Option<Chat>) {
+        if self.chat() == chat {
+            return;
+        }
+
+        let imp = self.imp();
+
+        if let (Some(old_chat), Some(handler_id)) = (imp.chat.take(), imp.chat_handler.take()) {
+            old_chat.disconnect(handler_id);
+        }
+
+        if let Some(ref chat) = chat {
+            let handler_id =
+                chat.connect_pinned_messages_changed(clone!(@weak self as obj => move |_| {
+                    obj.load_pinned_messages();
+                }));
+            imp.chat_handler.replace(Some(handler_id));
+        }
+
+        imp.chat.replace(chat);
+        imp.messages.replace(Vec::new());
+        imp.current_index.set(0);
+
+        self.update_current_message();
+        self.load_pinned_messages();
+
+        self.notify("chat");
+    }
+}
@@ -0,0 +1,396 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+use tdlib::enums::SearchMessagesFilter;
+
+use crate::i18n::gettext_f;
+use crate::strings;
+use crate::tdlib::{Chat, Message, MessageSender};
+use crate::utils::spawn;
+
+const RESULTS_PAGE_SIZE: i32 = 50;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/content-chat-search-bar.ui")]
+    pub(crate) struct ChatSearchBar {
+        pub(super) chat: RefCell<Option<Chat>>,
+        pub(super) search_mode: Cell<bool>,
+        pub(super) sender: RefCell<Option<MessageSender>>,
+        /// The found messages, from the newest to the oldest
+        pub(super) results: RefCell<Vec<Message>>,
+        pub(super) total_count: Cell<i32>,
+        pub(super) next_from_message_id: Cell<i64>,
+        pub(super) can_load_more: Cell<bool>,
+        pub(super) is_loading: Cell<bool>,
+        /// The index of the result the chat history was scrolled to
+        pub(super) current_index: Cell<Option<usize>>,
+        /// Incremented on each new search, to ignore the results of the outdated ones
+        pub(super) search_id: Cell<u32>,
+        #[template_child]
+        pub(super) search_bar: TemplateChild<gtk::SearchBar>,
+        #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub(super) sender_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) sender_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) filter_drop_down: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub(super) counter_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) older_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) newer_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ChatSearchBar {
+        const NAME: &'static str = "ContentChatSearchBar";
+        type Type = super::ChatSearchBar;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ChatSearchBar {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![
+                    glib::ParamSpecObject::builder::<Chat>("chat")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("search-mode")
+                        .explicit_notify()
+                        .build(),
+                ]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => obj.set_chat(value.get().unwrap()),
+                "search-mode" => obj.set_search_mode(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => obj.chat().to_value(),
+                "search-mode" => obj.search_mode().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.search_bar.connect_entry(&*self.search_entry);
+            self.obj().update_state();
+        }
+
+        fn dispose(&self) {
+            self.search_bar.unparent();
+        }
+    }
+
+    impl WidgetImpl for ChatSearchBar {}
+}
+
+glib::wrapper! {
+    pub(crate) struct ChatSearchBar(ObjectSubclass<imp::ChatSearchBar>)
+        @extends gtk::Widget;
+}
+
+#[gtk::template_callbacks]
+impl ChatSearchBar {
+    #[template_callback]
+    fn on_search_mode_changed(&self) {
+        let imp = self.imp();
+
+        if imp.search_bar.is_search_mode() {
+            imp.search_entry.grab_focus();
+        } else {
+            self.reset();
+        }
+    }
+
+    #[template_callback]
+    fn on_search_changed(&self) {
+        self.search();
+    }
+
+    #[template_callback]
+    fn on_sender_button_clicked(&self) {
+        self.set_sender(None);
+        self.search();
+    }
+
+    /// Scrolls the chat history to the next older result, loading more results if needed.
+    #[template_callback]
+    fn select_older_result(&self) {
+        let imp = self.imp();
+        let index = imp.current_index.get().map(|i| i + 1).unwrap_or_default();
+
+        if index < imp.results.borrow().len() {
+            self.select_result(index);
+        } else if imp.can_load_more.get() {
+            spawn(clone!(@weak self as obj => async move {
+                if obj.load_more_results().await
+                    && index < obj.imp().results.borrow().len()
+                {
+                    obj.select_result(index);
+                }
+            }));
+        }
+    }
+
+    /// Scrolls the chat history to the next newer result.
+    #[template_callback]
+    fn select_newer_result(&self) {
+        if let Some(index) = self.imp().current_index.get().filter(|i| *i > 0) {
+            self.select_result(index - 1);
+        }
+    }
+
+    /// Shows the search bar with only the messages sent by the given sender.
+    pub(crate) fn search_from_sender(&self, sender: MessageSender) {
+        self.set_sender(Some(sender));
+        self.set_search_mode(true);
+        self.search();
+    }
+
+    fn set_sender(&self, sender: Option<MessageSender>) {
+        let imp = self.imp();
+
+        if let Some(ref sender) = sender {
+            imp.sender_label
+                .set_label(&strings::message_sender(sender, false));
+        }
+        imp.sender_button.set_visible(sender.is_some());
+
+        imp.sender.replace(sender);
+    }
+
+    fn filter(&self) -> Option<SearchMessagesFilter> {
+        match self.imp().filter_drop_down.selected() {
+            1 => Some(SearchMessagesFilter::Photo),
+            2 => Some(SearchMessagesFilter::Video),
+            3 => Some(SearchMessagesFilter::Document),
+            4 => Some(SearchMessagesFilter::Url),
+            5 => Some(SearchMessagesFilter::VoiceNote),
+            6 => Some(SearchMessagesFilter::Audio),
+            7 => Some(SearchMessagesFilter::Animation),
+            _ => None,
+        }
+    }
+
+    fn is_searching(&self) -> bool {
+        let imp = self.imp();
+
+        !imp.search_entry.text().is_empty()
+            || imp.sender.borrow().is_some()
+            || self.filter().is_some()
+    }
+
+    /// Starts a new search and scrolls to its newest result.
+    fn search(&self) {
+        let imp = self.imp();
+
+        imp.search_id.set(imp.search_id.get().wrapping_add(1));
+        imp.results.borrow_mut().clear();
+        imp.total_count.set(0);
+        imp.next_from_message_id.set(0);
+        imp.can_load_more.set(true);
+        imp.is_loading.set(false);
+        imp.current_index.set(None);
+
+        self.update_state();
+
+        if !self.is_searching() {
+            return;
+        }
+
+        spawn(clone!(@weak self as obj => async move {
+            if obj.load_more_results().await && !obj.imp().results.borrow().is_empty() {
+                obj.select_result(0);
+            }
+        }));
+    }
+
+    /// Loads the next page of results of the current search.
+    ///
+    /// Returns `true` when new results have been loaded.
+    async fn load_more_results(&self) -> bool {
+        let imp = self.imp();
+
+        let chat = match self.chat() {
+            Some(chat) => chat,
+            None => return false,
+        };
+
+        if imp.is_loading.get() || !imp.can_load_more.get() {
+            return false;
+        }
+
+        let search_id = imp.search_id.get();
+        let query = imp.search_entry.text().to_string();
+        let sender = imp.sender.borrow().clone();
+
+        imp.is_loading.set(true);
+        self.update_state();
+
+        let result = chat
+            .search_messages(
+                query,
+                sender.as_ref(),
+                self.filter(),
+                imp.next_from_message_id.get(),
+                0,
+                RESULTS_PAGE_SIZE,
+            )
+            .await;
+
+        // Ignore the results of an outdated search
+        if imp.search_id.get() != search_id {
+            return false;
+        }
+
+        imp.is_loading.set(false);
+
+        let loaded = match result {
+            Ok(found) => {
+                imp.total_count.set(found.total_count);
+                imp.next_from_message_id.set(found.next_from_message_id);
+                imp.can_load_more.set(found.next_from_message_id != 0);
+
+                let loaded = !found.messages.is_empty();
+                imp.results.borrow_mut().extend(found.messages);
+                loaded
+            }
+            Err(e) => {
+                log::warn!("Error searching the chat messages: {e:?}");
+                imp.can_load_more.set(false);
+                false
+            }
+        };
+
+        self.update_state();
+        loaded
+    }
+
+    fn select_result(&self, index: usize) {
+        let imp = self.imp();
+
+        let message_id = match imp.results.borrow().get(index) {
+            Some(message) => message.id(),
+            None => return,
+        };
+
+        imp.current_index.set(Some(index));
+        self.update_state();
+
+        self.activate_action(
+            "chat-history.scroll-to-message",
+            Some(&message_id.to_variant()),
+        )
+        .unwrap();
+    }
+
+    fn update_state(&self) {
+        let imp = self.imp();
+        let n_results = imp.results.borrow().len();
+        let current_index = imp.current_index.get();
+
+        let counter = if !self.is_searching() || (imp.is_loading.get() && n_results == 0) {
+            String::new()
+        } else if n_results == 0 {
+            gettext("No results")
+        } else {
+            let current = current_index.map(|i| i + 1).unwrap_or_default();
+            // The total count is approximate, so make sure that it's never less than the
+            // number of the loaded results
+            let total = (imp.total_count.get() as usize).max(n_results);
+
+            gettext_f(
+                "{current} of {total}",
+                &[
+                    ("current", &current.to_string()),
+                    ("total", &total.to_string()),
+                ],
+            )
+        };
+        imp.counter_label.set_label(&counter);
+        imp.counter_label.set_visible(!counter.is_empty());
+
+        let has_older_result = match current_index {
+            Some(index) => index + 1 < n_results || imp.can_load_more.get(),
+            None => n_results > 0,
+        };
+        imp.older_button.set_sensitive(has_older_result);
+        imp.newer_button
+            .set_sensitive(current_index.map(|i| i > 0).unwrap_or_default());
+    }
+
+    /// Clears the search query and the filters.
+    fn reset(&self) {
+        let imp = self.imp();
+
+        imp.search_entry.set_text("");
+        self.set_sender(None);
+        imp.filter_drop_down.set_selected(0);
+
+        self.search();
+    }
+
+    pub(crate) fn chat(&self) -> Option<Chat> {
+        self.imp().chat.borrow().clone()
+    }
+
+    pub(crate) fn set_chat(&self, chat: Option<Chat>) {
+        if self.chat() == chat {
+            return;
+        }
+
+        self.imp().chat.replace(chat);
+
+        self.set_search_mode(false);
+        self.reset();
+
+        self.notify("chat");
+    }
+
+    pub(crate) fn search_mode(&self) -> bool {
+        self.imp().search_mode.get()
+    }
+
+    pub(crate) fn set_search_mode(&self, search_mode: bool) {
+        if self.search_mode() == search_mode {
+            return;
+        }
+
+        self.imp().search_mode.set(search_mode);
+        self.notify("search-mode");
+    }
+}
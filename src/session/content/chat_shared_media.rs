@@ -0,0 +1,597 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, gio, glib, CompositeTemplate};
+use std::cell::Cell;
+use tdlib::enums::{MessageContent, SearchMessagesFilter};
+use tdlib::types::File;
+
+use crate::strings;
+use crate::tdlib::{Chat, Message};
+use crate::utils::{decode_image_from_path, format_duration, minithumbnail_texture, spawn};
+
+const PAGE_SIZE: i32 = 50;
+const THUMBNAIL_SIZE: i32 = 100;
+
+/// The types of the shared media, each shown in its own page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SharedMediaType {
+    Media,
+    Files,
+    Links,
+    Voice,
+    Music,
+}
+
+impl SharedMediaType {
+    const ALL: [Self; 5] = [
+        Self::Media,
+        Self::Files,
+        Self::Links,
+        Self::Voice,
+        Self::Music,
+    ];
+
+    fn filter(self) -> SearchMessagesFilter {
+        match self {
+            Self::Media => SearchMessagesFilter::PhotoAndVideo,
+            Self::Files => SearchMessagesFilter::Document,
+            Self::Links => SearchMessagesFilter::Url,
+            Self::Voice => SearchMessagesFilter::VoiceNote,
+            Self::Music => SearchMessagesFilter::Audio,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Media => "media",
+            Self::Files => "files",
+            Self::Links => "links",
+            Self::Voice => "voice",
+            Self::Music => "music",
+        }
+    }
+
+    fn title(self) -> String {
+        match self {
+            Self::Media => gettext("Media"),
+            Self::Files => gettext("Files"),
+            Self::Links => gettext("Links"),
+            Self::Voice => gettext("Voice"),
+            Self::Music => gettext("Music"),
+        }
+    }
+
+    fn icon_name(self) -> &'static str {
+        match self {
+            Self::Media => "image-x-generic-symbolic",
+            Self::Files => "folder-documents-symbolic",
+            Self::Links => "insert-link-symbolic",
+            Self::Voice => "audio-input-microphone-symbolic",
+            Self::Music => "audio-x-generic-symbolic",
+        }
+    }
+}
+
+/// The loaded messages of a page and the state of their loading.
+#[derive(Debug)]
+struct SharedMediaPage {
+    type_: SharedMediaType,
+    /// The found messages, from the newest to the oldest
+    list: gio::ListStore,
+    /// The stack switching between the list and the empty page
+    stack: gtk::Stack,
+    next_from_message_id: Cell<i64>,
+    can_load_more: Cell<bool>,
+    is_loading: Cell<bool>,
+}
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use once_cell::unsync::OnceCell;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/content-chat-shared-media.ui")]
+    pub(crate) struct ChatSharedMedia {
+        pub(super) chat: RefCell<Option<Chat>>,
+        pub(super) pages: OnceCell<Vec<SharedMediaPage>>,
+        #[template_child]
+        pub(super) box_: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) stack: TemplateChild<adw::ViewStack>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ChatSharedMedia {
+        const NAME: &'static str = "ContentChatSharedMedia";
+        type Type = super::ChatSharedMedia;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ChatSharedMedia {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Chat>("chat")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => obj.set_chat(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "chat" => obj.chat().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let pages = SharedMediaType::ALL
+                .iter()
+                .enumerate()
+                .map(|(index, type_)| obj.create_page(index, *type_))
+                .collect();
+            self.pages.set(pages).unwrap();
+        }
+
+        fn dispose(&self) {
+            self.box_.unparent();
+        }
+    }
+
+    impl WidgetImpl for ChatSharedMedia {}
+}
+
+glib::wrapper! {
+    /// The media, files, links, voice messages and music shared in a chat.
+    pub(crate) struct ChatSharedMedia(ObjectSubclass<imp::ChatSharedMedia>)
+        @extends gtk::Widget;
+}
+
+#[gtk::template_callbacks]
+impl ChatSharedMedia {
+    /// Loads the messages of the visible page, if they aren't loaded yet.
+    #[template_callback]
+    fn on_visible_page_changed(&self) {
+        let imp = self.imp();
+
+        let pages = match imp.pages.get() {
+            Some(pages) => pages,
+            None => return,
+        };
+        let name = imp.stack.visible_child_name();
+        let index = pages
+            .iter()
+            .position(|page| Some(page.type_.name()) == name.as_deref());
+
+        if let Some(index) = index {
+            if pages[index].list.n_items() == 0 {
+                spawn(clone!(@weak self as obj => async move {
+                    obj.load_more_messages(index).await;
+                }));
+            }
+        }
+    }
+
+    fn create_page(&self, index: usize, type_: SharedMediaType) -> SharedMediaPage {
+        let list = gio::ListStore::new(Message::static_type());
+        let model = gtk::NoSelection::new(Some(list.clone()));
+
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(move |_, list_item| {
+            let child = if type_ == SharedMediaType::Media {
+                create_thumbnail().upcast::<gtk::Widget>()
+            } else {
+                create_row(type_).upcast()
+            };
+            list_item.set_child(Some(&child));
+        });
+        factory.connect_bind(move |_, list_item| {
+            let message = list_item.item().and_downcast::<Message>().unwrap();
+
+            if type_ == SharedMediaType::Media {
+                bind_thumbnail(list_item, &message);
+            } else {
+                bind_row(list_item, &message);
+            }
+        });
+        factory.connect_unbind(|_, list_item| {
+            // Drop the thumbnail of the previous message, so that it's not shown while the
+            // thumbnail of the next one is loading
+            if let Some(picture) = list_item
+                .child()
+                .and_downcast::<gtk::Overlay>()
+                .and_then(|overlay| overlay.child())
+                .and_downcast::<gtk::Picture>()
+            {
+                picture.set_paintable(gdk::Paintable::NONE);
+            }
+        });
+
+        let view: gtk::Widget = if type_ == SharedMediaType::Media {
+            let grid_view = gtk::GridView::builder()
+                .model(&model)
+                .factory(&factory)
+                .min_columns(3)
+                .max_columns(3)
+                .single_click_activate(true)
+                .build();
+            grid_view.connect_activate(clone!(@weak self as obj => move |_, position| {
+                obj.open_message(position, index);
+            }));
+            grid_view.upcast()
+        } else {
+            let list_view = gtk::ListView::builder()
+                .model(&model)
+                .factory(&factory)
+                .single_click_activate(true)
+                .build();
+            list_view.add_css_class("navigation-sidebar");
+            list_view.connect_activate(clone!(@weak self as obj => move |_, position| {
+                obj.open_message(position, index);
+            }));
+            list_view.upcast()
+        };
+
+        let scrolled_window = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .min_content_height(320)
+            .vexpand(true)
+            .child(&view)
+            .build();
+
+        // Load more messages when reaching the end of the page
+        scrolled_window.vadjustment().connect_value_changed(
+            clone!(@weak self as obj => move |adj| {
+                if adj.value() + adj.page_size() * 2.0 >= adj.upper() {
+                    spawn(clone!(@weak obj => async move {
+                        obj.load_more_messages(index).await;
+                    }));
+                }
+            }),
+        );
+
+        let status_page = adw::StatusPage::builder()
+            .icon_name(type_.icon_name())
+            .title(gettext("Nothing Shared Yet"))
+            .build();
+        status_page.add_css_class("compact");
+
+        let stack = gtk::Stack::new();
+        stack.add_named(&scrolled_window, Some("list"));
+        stack.add_named(&status_page, Some("empty"));
+
+        self.imp().stack.add_titled_with_icon(
+            &stack,
+            Some(type_.name()),
+            &type_.title(),
+            type_.icon_name(),
+        );
+
+        SharedMediaPage {
+            type_,
+            list,
+            stack,
+            next_from_message_id: Cell::new(0),
+            can_load_more: Cell::new(true),
+            is_loading: Cell::new(false),
+        }
+    }
+
+    async fn load_more_messages(&self, index: usize) {
+        let page = &self.imp().pages.get().unwrap()[index];
+
+        let chat = match self.chat() {
+            Some(chat) => chat,
+            None => return,
+        };
+
+        if page.is_loading.get() || !page.can_load_more.get() {
+            return;
+        }
+
+        page.is_loading.set(true);
+
+        let result = chat
+            .search_messages(
+                String::new(),
+                None,
+                Some(page.type_.filter()),
+                page.next_from_message_id.get(),
+                0,
+                PAGE_SIZE,
+            )
+            .await;
+
+        // Ignore the result if the chat changed in the meantime
+        if self.chat().as_ref() != Some(&chat) {
+            return;
+        }
+
+        page.is_loading.set(false);
+
+        match result {
+            Ok(found) => {
+                page.next_from_message_id.set(found.next_from_message_id);
+                page.can_load_more.set(found.next_from_message_id != 0);
+                page.list.extend_from_slice(&found.messages);
+            }
+            Err(e) => {
+                log::warn!("Error loading the shared media: {e:?}");
+                page.can_load_more.set(false);
+            }
+        }
+
+        page.stack
+            .set_visible_child_name(if page.list.n_items() > 0 {
+                "list"
+            } else {
+                "empty"
+            });
+    }
+
+    /// Closes the window and shows the message in the chat history.
+    fn open_message(&self, position: u32, index: usize) {
+        let page = &self.imp().pages.get().unwrap()[index];
+
+        if let Some(message) = page.list.item(position).and_downcast::<Message>() {
+            let session = message.chat().session();
+
+            if let Some(window) = self.root().and_downcast::<gtk::Window>() {
+                window.close();
+            }
+
+            session.select_chat(message.chat().id());
+            session.show_message(message.id());
+        }
+    }
+
+    pub(crate) fn chat(&self) -> Option<Chat> {
+        self.imp().chat.borrow().clone()
+    }
+
+    pub(crate) fn set_chat(&self, chat: Option<Chat>) {
+        if self.chat() == chat {
+            return;
+        }
+
+        let imp = self.imp();
+        imp.chat.replace(chat);
+
+        if let Some(pages) = imp.pages.get() {
+            for page in pages {
+                page.list.remove_all();
+                page.next_from_message_id.set(0);
+                page.can_load_more.set(true);
+                page.is_loading.set(false);
+                page.stack.set_visible_child_name("list");
+            }
+        }
+
+        self.on_visible_page_changed();
+
+        self.notify("chat");
+    }
+}
+
+/// Creates the widgets of the thumbnail of a photo or video.
+fn create_thumbnail() -> gtk::Overlay {
+    let picture = gtk::Picture::builder()
+        .content_fit(gtk::ContentFit::Cover)
+        .width_request(THUMBNAIL_SIZE)
+        .height_request(THUMBNAIL_SIZE)
+        .build();
+
+    let duration_label = gtk::Label::builder()
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Start)
+        .css_classes(["osd-indicator"])
+        .build();
+
+    let overlay = gtk::Overlay::new();
+    overlay.set_child(Some(&picture));
+    overlay.add_overlay(&duration_label);
+    overlay
+}
+
+/// Shows the thumbnail of a photo or video, loading the full thumbnail only when the item is
+/// shown.
+fn bind_thumbnail(list_item: &gtk::ListItem, message: &Message) {
+    let (minithumbnail, file, duration) = match message.content().0 {
+        MessageContent::MessagePhoto(data) => {
+            let photo_size = data
+                .photo
+                .sizes
+                .iter()
+                .find(|s| s.r#type == "m")
+                .or_else(|| data.photo.sizes.first())
+                .cloned();
+            (data.photo.minithumbnail, photo_size.map(|s| s.photo), None)
+        }
+        MessageContent::MessageVideo(data) => (
+            data.video.minithumbnail,
+            data.video.thumbnail.map(|t| t.file),
+            Some(data.video.duration),
+        ),
+        _ => (None, None, None),
+    };
+
+    let overlay = list_item.child().and_downcast::<gtk::Overlay>().unwrap();
+    let picture = overlay.child().and_downcast::<gtk::Picture>().unwrap();
+    let duration_label = overlay.last_child().and_downcast::<gtk::Label>().unwrap();
+
+    picture.set_paintable(minithumbnail_texture(minithumbnail).as_ref());
+
+    match duration {
+        Some(duration) => {
+            duration_label.set_label(&format_duration(duration as i64));
+            duration_label.set_visible(true);
+        }
+        None => duration_label.set_visible(false),
+    }
+
+    if let Some(file) = file {
+        load_thumbnail(list_item, message, &picture, file);
+    }
+}
+
+fn load_thumbnail(
+    list_item: &gtk::ListItem,
+    message: &Message,
+    picture: &gtk::Picture,
+    file: File,
+) {
+    let session = message.chat().session();
+
+    spawn(
+        clone!(@weak list_item, @weak message, @weak picture, @weak session => async move {
+            let path = if file.local.is_downloading_completed {
+                file.local.path
+            } else {
+                match session.download_file(file.id).await {
+                    Ok(file) => file.local.path,
+                    Err(e) => {
+                        log::warn!("Failed to download a shared media thumbnail: {e:?}");
+                        return;
+                    }
+                }
+            };
+
+            // The list item may show another message by now
+            if list_item.item().as_ref() != Some(message.upcast_ref()) {
+                return;
+            }
+
+            let result = gio::spawn_blocking(move || decode_image_from_path(&path))
+                .await
+                .unwrap();
+
+            if list_item.item().as_ref() != Some(message.upcast_ref()) {
+                return;
+            }
+
+            match result {
+                Ok(texture) => picture.set_paintable(Some(&texture)),
+                Err(e) => log::warn!("Error decoding a shared media thumbnail: {e:?}"),
+            }
+        }),
+    );
+}
+
+/// Creates the widgets of the row of a file, link, voice message or music.
+fn create_row(type_: SharedMediaType) -> gtk::Box {
+    let title_label = gtk::Inscription::builder()
+        .text_overflow(gtk::InscriptionOverflow::EllipsizeEnd)
+        .build();
+    let subtitle_label = gtk::Inscription::builder()
+        .text_overflow(gtk::InscriptionOverflow::EllipsizeEnd)
+        .css_classes(["dim-label", "caption"])
+        .build();
+
+    let labels_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .valign(gtk::Align::Center)
+        .hexpand(true)
+        .build();
+    labels_box.append(&title_label);
+    labels_box.append(&subtitle_label);
+
+    let icon = gtk::Image::builder()
+        .icon_name(type_.icon_name())
+        .pixel_size(24)
+        .build();
+
+    let row = gtk::Box::builder()
+        .spacing(12)
+        .margin_top(6)
+        .margin_bottom(6)
+        .build();
+    row.append(&icon);
+    row.append(&labels_box);
+    row
+}
+
+/// Shows the title and subtitle of a file, link, voice message or music.
+fn bind_row(list_item: &gtk::ListItem, message: &Message) {
+    let (title, subtitle) = match message.content().0 {
+        MessageContent::MessageDocument(data) => (
+            data.document.file_name,
+            glib::format_size(data.document.document.size as u64).to_string(),
+        ),
+        MessageContent::MessageText(data) => match data.web_page {
+            Some(web_page) if !web_page.title.is_empty() => (web_page.title, web_page.url),
+            Some(web_page) => (web_page.display_url, web_page.url),
+            None => (strings::message_content(message), String::new()),
+        },
+        MessageContent::MessageVoiceNote(data) => (
+            strings::message_sender(message.sender(), true),
+            format_duration(data.voice_note.duration as i64),
+        ),
+        MessageContent::MessageAudio(data) => {
+            let duration = format_duration(data.audio.duration as i64);
+            let title = if data.audio.title.is_empty() {
+                data.audio.file_name
+            } else {
+                data.audio.title
+            };
+            let subtitle = if data.audio.performer.is_empty() {
+                duration
+            } else {
+                format!("{} · {}", data.audio.performer, duration)
+            };
+            (title, subtitle)
+        }
+        _ => (strings::message_content(message), String::new()),
+    };
+
+    let date = glib::DateTime::from_unix_utc(message.date() as i64)
+        .and_then(|t| t.to_local())
+        .and_then(|t| t.format("%x"))
+        .map(|date| date.to_string())
+        .unwrap_or_default();
+    let subtitle = if subtitle.is_empty() {
+        date
+    } else {
+        format!("{subtitle} · {date}")
+    };
+
+    let labels_box = list_item.child().and_then(|row| row.last_child()).unwrap();
+    let title_label = labels_box
+        .first_child()
+        .and_downcast::<gtk::Inscription>()
+        .unwrap();
+    let subtitle_label = labels_box
+        .last_child()
+        .and_downcast::<gtk::Inscription>()
+        .unwrap();
+
+    title_label.set_text(Some(&title.replace('\n', " ")));
+    subtitle_label.set_text(Some(&subtitle));
+}
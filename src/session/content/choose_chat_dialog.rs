@@ -0,0 +1,164 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use glib::clone;
+use gtk::{gio, glib, CompositeTemplate};
+
+use super::can_send_messages_in_chat;
+use crate::session::{search_known_chats, SearchItemRow};
+use crate::tdlib::{Chat, ChatListItem};
+use crate::utils::spawn;
+use crate::Session;
+
+const MAX_FOUND_CHATS: i32 = 50;
+
+mod imp {
+    use super::*;
+    use glib::subclass::Signal;
+    use once_cell::sync::Lazy;
+    use once_cell::unsync::OnceCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/content-choose-chat-dialog.ui")]
+    pub(crate) struct ChooseChatDialog {
+        pub(super) session: OnceCell<Session>,
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub(super) selection: TemplateChild<gtk::NoSelection>,
+        #[template_child]
+        pub(super) options_box: TemplateChild<gtk::Box>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ChooseChatDialog {
+        const NAME: &'static str = "ContentChooseChatDialog";
+        type Type = super::ChooseChatDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            SearchItemRow::static_type();
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ChooseChatDialog {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![Signal::builder("chat-chosen")
+                    .param_types([Chat::static_type()])
+                    .build()]
+            });
+            SIGNALS.as_ref()
+        }
+    }
+
+    impl WidgetImpl for ChooseChatDialog {}
+    impl WindowImpl for ChooseChatDialog {}
+    impl AdwWindowImpl for ChooseChatDialog {}
+}
+
+glib::wrapper! {
+    /// A dialog for choosing one of the chats in which the user can send messages. It's up to
+    /// the handler of `chat-chosen` to close it.
+    pub(crate) struct ChooseChatDialog(ObjectSubclass<imp::ChooseChatDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+pub(crate) trait ChooseChatDialogImpl: AdwWindowImpl {}
+
+unsafe impl<T: ChooseChatDialogImpl> IsSubclassable<T> for ChooseChatDialog {}
+
+#[gtk::template_callbacks]
+impl ChooseChatDialog {
+    pub(crate) fn new(parent_window: &Option<gtk::Window>, session: &Session) -> Self {
+        let dialog: Self = glib::Object::builder()
+            .property("transient-for", parent_window)
+            .build();
+
+        dialog.set_session(session);
+
+        dialog
+    }
+
+    /// Sets the session whose chats are listed. Subclasses need to call this once after
+    /// building the dialog.
+    pub(crate) fn set_session(&self, session: &Session) {
+        self.imp().session.set(session.clone()).unwrap();
+        self.on_search_changed();
+    }
+
+    /// Adds a widget, like a check button, to the options below the chats.
+    pub(crate) fn add_option(&self, widget: &impl IsA<gtk::Widget>) {
+        let options_box = &self.imp().options_box;
+        options_box.append(widget);
+        options_box.set_visible(true);
+    }
+
+    pub(crate) fn add_toast(&self, toast: adw::Toast) {
+        self.imp().toast_overlay.add_toast(toast);
+    }
+
+    pub(crate) fn connect_chat_chosen<F: Fn(&Self, Chat) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("chat-chosen", true, move |values| {
+            let obj = values[0].get().unwrap();
+            let chat = values[1].get().unwrap();
+            f(obj, chat);
+            None
+        })
+    }
+
+    #[template_callback]
+    fn on_search_changed(&self) {
+        let session = self.imp().session.get().unwrap().clone();
+        let query = self.imp().search_entry.text().trim().to_string();
+
+        spawn(clone!(@weak self as obj => async move {
+            let chats = chats_to_send_to(&session, &query).await;
+
+            // Ignore the result if the query changed in the meantime
+            if obj.imp().search_entry.text().trim() != query {
+                return;
+            }
+
+            let list = gio::ListStore::new(Chat::static_type());
+            list.extend_from_slice(&chats);
+            obj.imp().selection.set_model(Some(&list));
+        }));
+    }
+
+    #[template_callback]
+    fn on_list_activate(&self, position: u32) {
+        if let Some(chat) = self.imp().selection.item(position).and_downcast::<Chat>() {
+            self.emit_by_name::<()>("chat-chosen", &[&chat]);
+        }
+    }
+}
+
+/// Returns the chats of the main chat list, or the chats found by the same search as the
+/// sidebar, leaving out the chats in which the user can't send messages.
+async fn chats_to_send_to(session: &Session, query: &str) -> Vec<Chat> {
+    let chats = if query.is_empty() {
+        let chat_list = session.main_chat_list();
+        (0..chat_list.n_items())
+            .filter_map(|position| chat_list.item(position))
+            .map(|item| item.downcast::<ChatListItem>().unwrap().chat())
+            .collect()
+    } else {
+        search_known_chats(session, query, MAX_FOUND_CHATS).await
+    };
+
+    chats
+        .into_iter()
+        .filter(can_send_messages_in_chat)
+        .collect()
+}
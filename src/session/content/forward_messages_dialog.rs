@@ -0,0 +1,135 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::glib;
+use tdlib::functions;
+
+use super::choose_chat_dialog::ChooseChatDialogImpl;
+use super::ChooseChatDialog;
+use crate::tdlib::{Chat, Message};
+use crate::utils::spawn;
+
+mod imp {
+    use super::*;
+    use once_cell::unsync::OnceCell;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct ForwardMessagesDialog {
+        /// The messages to forward, sorted from the oldest to the newest
+        pub(super) messages: OnceCell<Vec<Message>>,
+        pub(super) hide_sender_button: gtk::CheckButton,
+        pub(super) remove_captions_button: gtk::CheckButton,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ForwardMessagesDialog {
+        const NAME: &'static str = "ContentForwardMessagesDialog";
+        type Type = super::ForwardMessagesDialog;
+        type ParentType = ChooseChatDialog;
+    }
+
+    impl ObjectImpl for ForwardMessagesDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let dialog = obj.upcast_ref::<ChooseChatDialog>();
+
+            obj.set_title(Some(&gettext("Forward Messages")));
+
+            self.hide_sender_button
+                .set_label(Some(&gettext("_Hide Sender Name")));
+            self.hide_sender_button.set_use_underline(true);
+            dialog.add_option(&self.hide_sender_button);
+
+            self.remove_captions_button
+                .set_label(Some(&gettext("_Remove Captions")));
+            self.remove_captions_button.set_use_underline(true);
+            self.hide_sender_button
+                .bind_property("active", &self.remove_captions_button, "sensitive")
+                .sync_create()
+                .build();
+            dialog.add_option(&self.remove_captions_button);
+
+            dialog.connect_chat_chosen(|dialog, chat| {
+                dialog
+                    .downcast_ref::<super::ForwardMessagesDialog>()
+                    .unwrap()
+                    .forward_messages(chat);
+            });
+        }
+    }
+
+    impl WidgetImpl for ForwardMessagesDialog {}
+    impl WindowImpl for ForwardMessagesDialog {}
+    impl AdwWindowImpl for ForwardMessagesDialog {}
+    impl ChooseChatDialogImpl for ForwardMessagesDialog {}
+}
+
+glib::wrapper! {
+    /// A `ChooseChatDialog` that forwards messages to the chosen chat.
+    pub(crate) struct ForwardMessagesDialog(ObjectSubclass<imp::ForwardMessagesDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window, ChooseChatDialog;
+}
+
+impl ForwardMessagesDialog {
+    pub(crate) fn new(parent_window: &Option<gtk::Window>, messages: Vec<Message>) -> Self {
+        let dialog: Self = glib::Object::builder()
+            .property("transient-for", parent_window)
+            .build();
+
+        let session = messages.first().unwrap().chat().session();
+        dialog.imp().messages.set(messages).unwrap();
+        dialog
+            .upcast_ref::<ChooseChatDialog>()
+            .set_session(&session);
+
+        dialog
+    }
+
+    fn forward_messages(&self, chat: Chat) {
+        let imp = self.imp();
+
+        let messages = imp.messages.get().unwrap().clone();
+        let send_copy = imp.hide_sender_button.is_active();
+        let remove_caption = send_copy && imp.remove_captions_button.is_active();
+
+        if let Some(from_chat) = messages.first().map(Message::chat) {
+            let message_ids = messages.iter().map(Message::id).collect();
+
+            self.set_sensitive(false);
+
+            spawn(clone!(@weak self as obj => async move {
+                let result = functions::forward_messages(
+                    chat.id(),
+                    0,
+                    from_chat.id(),
+                    message_ids,
+                    None,
+                    send_copy,
+                    remove_caption,
+                    false,
+                    chat.session().client_id(),
+                )
+                .await;
+
+                match result {
+                    Ok(_) => {
+                        obj.close();
+
+                        // Show the chat the messages have been forwarded to
+                        chat.session().imp().sidebar.get().select_chat(chat);
+                    }
+                    Err(e) => {
+                        log::warn!("Error forwarding messages: {:?}", e);
+
+                        obj.set_sensitive(true);
+                        obj.upcast_ref::<ChooseChatDialog>()
+                            .add_toast(adw::Toast::new(&gettext("Failed to forward messages")));
+                    }
+                }
+            }));
+        }
+    }
+}
@@ -0,0 +1,505 @@
+use adw::subclass::prelude::*;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib, CompositeTemplate};
+use tdlib::enums::{MessageContent, SearchMessagesFilter};
+use tdlib::types::File;
+
+use crate::components::ZoomablePicture;
+use crate::session::content::ForwardMessagesDialog;
+use crate::strings;
+use crate::tdlib::Message;
+use crate::utils::{decode_image_from_path, minithumbnail_texture, spawn};
+
+/// The number of photos and videos loaded at once before or after the shown one.
+const GALLERY_PAGE_SIZE: i32 = 20;
+
+mod imp {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/app/drey/paper-plane/ui/content-media-viewer.ui")]
+    pub(crate) struct MediaViewer {
+        /// The photos and videos of the chat, from the newest to the oldest
+        pub(super) messages: RefCell<Vec<Message>>,
+        /// The index of the shown message
+        pub(super) index: Cell<usize>,
+        pub(super) can_load_older: Cell<bool>,
+        pub(super) can_load_newer: Cell<bool>,
+        pub(super) is_loading_older: Cell<bool>,
+        pub(super) is_loading_newer: Cell<bool>,
+        /// The local path of the shown media, once it's loaded
+        pub(super) path: RefCell<Option<String>>,
+        #[template_child]
+        pub(super) picture: TemplateChild<ZoomablePicture>,
+        #[template_child]
+        pub(super) window_title: TemplateChild<adw::WindowTitle>,
+        #[template_child]
+        pub(super) spinner: TemplateChild<gtk::Spinner>,
+        #[template_child]
+        pub(super) media_controls: TemplateChild<gtk::MediaControls>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MediaViewer {
+        const NAME: &'static str = "ContentMediaViewer";
+        type Type = super::MediaViewer;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            ZoomablePicture::static_type();
+            klass.bind_template();
+
+            klass.install_action("media-viewer.previous", None, move |widget, _, _| {
+                widget.show_older_message();
+            });
+            klass.install_action("media-viewer.next", None, move |widget, _, _| {
+                widget.show_newer_message();
+            });
+            klass.install_action("media-viewer.zoom-in", None, move |widget, _, _| {
+                widget.imp().picture.zoom_in();
+            });
+            klass.install_action("media-viewer.zoom-out", None, move |widget, _, _| {
+                widget.imp().picture.zoom_out();
+            });
+            klass.install_action("media-viewer.rotate", None, move |widget, _, _| {
+                widget.imp().picture.rotate();
+            });
+            klass.install_action_async("media-viewer.save-as", None, |widget, _, _| async move {
+                widget.save_as().await;
+            });
+            klass.install_action("media-viewer.copy-image", None, move |widget, _, _| {
+                widget.copy_image();
+            });
+            klass.install_action("media-viewer.forward", None, move |widget, _, _| {
+                widget.forward();
+            });
+
+            klass.add_binding_action(
+                gdk::Key::Left,
+                gdk::ModifierType::empty(),
+                "media-viewer.previous",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::Right,
+                gdk::ModifierType::empty(),
+                "media-viewer.next",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::plus,
+                gdk::ModifierType::empty(),
+                "media-viewer.zoom-in",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::minus,
+                gdk::ModifierType::empty(),
+                "media-viewer.zoom-out",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::r,
+                gdk::ModifierType::empty(),
+                "media-viewer.rotate",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::s,
+                gdk::ModifierType::CONTROL_MASK,
+                "media-viewer.save-as",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::c,
+                gdk::ModifierType::CONTROL_MASK,
+                "media-viewer.copy-image",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::Escape,
+                gdk::ModifierType::empty(),
+                "window.close",
+                None,
+            );
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MediaViewer {
+        fn dispose(&self) {
+            self.obj().stop_playback();
+        }
+    }
+
+    impl WidgetImpl for MediaViewer {}
+    impl WindowImpl for MediaViewer {}
+    impl AdwWindowImpl for MediaViewer {}
+}
+
+glib::wrapper! {
+    /// A fullscreen viewer of the photos and videos of a chat, starting from the given message.
+    pub(crate) struct MediaViewer(ObjectSubclass<imp::MediaViewer>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+impl MediaViewer {
+    pub(crate) fn new(parent_window: &Option<gtk::Window>, message: &Message) -> Self {
+        let viewer: Self = glib::Object::builder()
+            .property("transient-for", parent_window)
+            .build();
+        let imp = viewer.imp();
+
+        imp.messages.replace(vec![message.clone()]);
+        imp.can_load_older.set(true);
+        imp.can_load_newer.set(true);
+
+        viewer.show_current_message();
+        viewer.fullscreen();
+
+        viewer
+    }
+
+    fn current_message(&self) -> Message {
+        let imp = self.imp();
+        imp.messages.borrow()[imp.index.get()].clone()
+    }
+
+    fn show_older_message(&self) {
+        let imp = self.imp();
+        let index = imp.index.get();
+
+        if index + 1 < imp.messages.borrow().len() {
+            imp.index.set(index + 1);
+            self.show_current_message();
+        }
+    }
+
+    fn show_newer_message(&self) {
+        let imp = self.imp();
+        let index = imp.index.get();
+
+        if index > 0 {
+            imp.index.set(index - 1);
+            self.show_current_message();
+        }
+    }
+
+    fn show_current_message(&self) {
+        let imp = self.imp();
+        let message = self.current_message();
+
+        self.stop_playback();
+        imp.path.replace(None);
+        imp.spinner.set_visible(false);
+        imp.picture.reset();
+
+        imp.window_title
+            .set_title(&strings::message_sender(message.sender(), true));
+        imp.window_title.set_subtitle(&date_text(message.date()));
+
+        // Show the minithumbnail until the media is downloaded
+        match message.content().0 {
+            MessageContent::MessagePhoto(data) => {
+                imp.picture
+                    .set_paintable(minithumbnail_texture(data.photo.minithumbnail).as_ref());
+
+                // The photo sizes are sorted from the smallest to the biggest. Show the biggest
+                // of the smaller sizes already downloaded, like the one shown in the chat history.
+                if let Some((photo_size, smaller_sizes)) = data.photo.sizes.split_last() {
+                    if let Some(thumbnail_size) = smaller_sizes
+                        .iter()
+                        .rev()
+                        .find(|s| s.photo.local.is_downloading_completed)
+                    {
+                        self.load_thumbnail(thumbnail_size.photo.local.path.clone());
+                    }
+
+                    self.download_media(&message, photo_size.photo.clone());
+                }
+            }
+            MessageContent::MessageVideo(data) => {
+                imp.picture
+                    .set_paintable(minithumbnail_texture(data.video.minithumbnail).as_ref());
+                self.download_media(&message, data.video.video);
+            }
+            _ => imp.picture.set_paintable(gdk::Paintable::NONE),
+        }
+
+        self.update_actions();
+        self.load_more_messages_if_needed();
+    }
+
+    fn download_media(&self, message: &Message, file: File) {
+        if file.local.is_downloading_completed {
+            self.load_media(file.local.path);
+            return;
+        }
+
+        self.imp().spinner.set_visible(true);
+
+        let message_id = message.id();
+        message.chat().session().download_file_with_updates(
+            file.id,
+            clone!(@weak self as obj => move |file| {
+                // Another message may be shown by now
+                if obj.current_message().id() != message_id {
+                    return;
+                }
+
+                if file.local.is_downloading_completed {
+                    obj.imp().spinner.set_visible(false);
+                    obj.load_media(file.local.path);
+                } else if !file.local.is_downloading_active {
+                    obj.imp().spinner.set_visible(false);
+                }
+            }),
+        );
+    }
+
+    fn load_media(&self, path: String) {
+        let imp = self.imp();
+        let message = self.current_message();
+
+        if let MessageContent::MessageVideo(_) = message.content().0 {
+            let media = gtk::MediaFile::for_filename(&path);
+            media.play();
+
+            imp.picture.set_paintable(Some(&media));
+            imp.media_controls.set_media_stream(Some(&media));
+            imp.media_controls.set_visible(true);
+            imp.path.replace(Some(path));
+
+            self.update_actions();
+        } else {
+            let message_id = message.id();
+
+            spawn(clone!(@weak self as obj => async move {
+                let result = gio::spawn_blocking(clone!(@strong path => move || {
+                    decode_image_from_path(&path)
+                }))
+                .await
+                .unwrap();
+
+                if obj.current_message().id() != message_id {
+                    return;
+                }
+
+                match result {
+                    Ok(texture) => {
+                        let imp = obj.imp();
+                        imp.picture.set_paintable(Some(&texture));
+                        imp.path.replace(Some(path));
+
+                        obj.update_actions();
+                    }
+                    Err(e) => {
+                        log::warn!("Error decoding a photo: {e:?}");
+                    }
+                }
+            }));
+        }
+    }
+
+    /// Shows the thumbnail of the photo, unless the full size photo has already been loaded.
+    fn load_thumbnail(&self, path: String) {
+        let message_id = self.current_message().id();
+
+        spawn(clone!(@weak self as obj => async move {
+            let result = gio::spawn_blocking(move || decode_image_from_path(&path))
+                .await
+                .unwrap();
+
+            let imp = obj.imp();
+            if obj.current_message().id() != message_id || imp.path.borrow().is_some() {
+                return;
+            }
+
+            match result {
+                Ok(texture) => imp.picture.set_paintable(Some(&texture)),
+                Err(e) => log::warn!("Error decoding a photo thumbnail: {e:?}"),
+            }
+        }));
+    }
+
+    fn stop_playback(&self) {
+        let imp = self.imp();
+
+        if let Some(media) = imp.media_controls.media_stream() {
+            media.pause();
+        }
+
+        imp.media_controls.set_media_stream(gtk::MediaStream::NONE);
+        imp.media_controls.set_visible(false);
+    }
+
+    fn load_more_messages_if_needed(&self) {
+        let imp = self.imp();
+        let index = imp.index.get();
+        let n_messages = imp.messages.borrow().len();
+
+        if imp.can_load_older.get() && index + 2 >= n_messages {
+            spawn(clone!(@weak self as obj => async move {
+                obj.load_messages(true).await;
+            }));
+        }
+
+        if imp.can_load_newer.get() && index < 2 {
+            spawn(clone!(@weak self as obj => async move {
+                obj.load_messages(false).await;
+            }));
+        }
+    }
+
+    /// Loads the photos and videos sent before the oldest loaded one, or after the newest one.
+    async fn load_messages(&self, older: bool) {
+        let imp = self.imp();
+
+        let (is_loading, can_load) = if older {
+            (&imp.is_loading_older, &imp.can_load_older)
+        } else {
+            (&imp.is_loading_newer, &imp.can_load_newer)
+        };
+
+        if is_loading.get() || !can_load.get() {
+            return;
+        }
+
+        let from_message = if older {
+            imp.messages.borrow().last().cloned().unwrap()
+        } else {
+            imp.messages.borrow().first().cloned().unwrap()
+        };
+        let from_message_id = from_message.id();
+
+        // A negative offset requires a limit greater than the opposite of the offset
+        let (offset, limit) = if older {
+            (0, GALLERY_PAGE_SIZE)
+        } else {
+            (-GALLERY_PAGE_SIZE, GALLERY_PAGE_SIZE + 1)
+        };
+
+        is_loading.set(true);
+
+        let result = from_message
+            .chat()
+            .search_messages(
+                String::new(),
+                None,
+                Some(SearchMessagesFilter::PhotoAndVideo),
+                from_message_id,
+                offset,
+                limit,
+            )
+            .await;
+
+        is_loading.set(false);
+
+        let found_messages = match result {
+            Ok(found) => found.messages,
+            Err(e) => {
+                log::warn!("Error loading the photos and videos of a chat: {e:?}");
+                can_load.set(false);
+                return;
+            }
+        };
+
+        {
+            let mut messages = imp.messages.borrow_mut();
+
+            if older {
+                let older_messages: Vec<Message> = found_messages
+                    .into_iter()
+                    .filter(|m| m.id() < from_message_id)
+                    .collect();
+
+                can_load.set(!older_messages.is_empty());
+                messages.extend(older_messages);
+            } else {
+                let newer_messages: Vec<Message> = found_messages
+                    .into_iter()
+                    .filter(|m| m.id() > from_message_id)
+                    .collect();
+
+                can_load.set(!newer_messages.is_empty());
+                imp.index.set(imp.index.get() + newer_messages.len());
+                messages.splice(0..0, newer_messages);
+            }
+        }
+
+        self.update_actions();
+    }
+
+    fn update_actions(&self) {
+        let imp = self.imp();
+        let index = imp.index.get();
+        let n_messages = imp.messages.borrow().len();
+        let is_loaded = imp.path.borrow().is_some();
+        let is_photo = imp
+            .picture
+            .paintable()
+            .map(|p| p.is::<gdk::Texture>())
+            .unwrap_or_default();
+
+        self.action_set_enabled("media-viewer.previous", index + 1 < n_messages);
+        self.action_set_enabled("media-viewer.next", index > 0);
+        self.action_set_enabled("media-viewer.save-as", is_loaded);
+        self.action_set_enabled("media-viewer.copy-image", is_loaded && is_photo);
+        self.action_set_enabled(
+            "media-viewer.forward",
+            self.current_message().can_be_forwarded(),
+        );
+    }
+
+    async fn save_as(&self) {
+        let path = match self.imp().path.borrow().clone() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let dialog = gtk::FileDialog::new();
+        if let Some(file_name) = std::path::Path::new(&path).file_name() {
+            dialog.set_initial_name(file_name.to_str());
+        }
+
+        if let Ok(file) = dialog.save_future(Some(self)).await {
+            let destination = file.path().unwrap();
+            let result = gio::spawn_blocking(move || std::fs::copy(path, destination))
+                .await
+                .unwrap();
+
+            if let Err(e) = result {
+                log::warn!("Error saving a media: {e:?}");
+            }
+        }
+    }
+
+    fn copy_image(&self) {
+        if let Some(texture) = self
+            .imp()
+            .picture
+            .paintable()
+            .and_downcast::<gdk::Texture>()
+        {
+            self.clipboard().set_texture(&texture);
+        }
+    }
+
+    fn forward(&self) {
+        let window = Some(self.clone().upcast());
+        ForwardMessagesDialog::new(&window, vec![self.current_message()]).present();
+    }
+}
+
+fn date_text(date: i32) -> String {
+    glib::DateTime::from_unix_utc(date as i64)
+        .and_then(|t| t.to_local())
+        .and_then(|t| t.format("%x %X"))
+        .map(|date| date.to_string())
+        .unwrap_or_default()
+}
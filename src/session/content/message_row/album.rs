@@ -0,0 +1,410 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib, CompositeTemplate};
+use tdlib::enums::MessageContent;
+use tdlib::types::{File, FormattedText, Minithumbnail};
+
+use crate::session::content::message_row::{
+    MediaMosaic, MediaPicture, MessageBase, MessageBaseImpl, MessageBubble,
+};
+use crate::session::content::MediaViewer;
+use crate::tdlib::Message;
+use crate::utils::{decode_image_from_path, format_duration, minithumbnail_texture, spawn};
+use crate::Session;
+
+use super::base::MessageBaseExt;
+
+#[derive(Debug)]
+pub(crate) struct AlbumItem {
+    message: Message,
+    handler_id: glib::SignalHandlerId,
+    /// The content of the message without its caption
+    media: MessageContent,
+}
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageAlbum : $MessageBase {
+        $MessageBubble message_bubble {
+            styles ["media"]
+
+            prefix: $MessageMediaMosaic mosaic {};
+        }
+    }
+    "#)]
+    pub(crate) struct MessageAlbum {
+        /// The first message of the album
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) album: RefCell<Vec<AlbumItem>>,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<MessageBubble>,
+        #[template_child]
+        pub(super) mosaic: TemplateChild<MediaMosaic>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageAlbum {
+        const NAME: &'static str = "MessageAlbum";
+        type Type = super::MessageAlbum;
+        type ParentType = MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageAlbum {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            self.obj().stop_videos();
+
+            for item in self.album.take() {
+                item.message.disconnect(item.handler_id);
+            }
+        }
+    }
+
+    impl WidgetImpl for MessageAlbum {}
+    impl MessageBaseImpl for MessageAlbum {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageAlbum(ObjectSubclass<imp::MessageAlbum>)
+        @extends gtk::Widget, MessageBase;
+}
+
+impl MessageBaseExt for MessageAlbum {
+    type Message = Message;
+
+    fn set_message(&self, message: Self::Message) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == Some(&message) {
+            return;
+        }
+
+        imp.message_bubble.update_from_message(&message, true);
+        imp.message.replace(Some(message));
+
+        self.notify("message");
+    }
+}
+
+impl MessageAlbum {
+    /// Sets the messages of the album, sorted from the oldest to the newest.
+    pub(crate) fn set_album(&self, messages: Vec<Message>) {
+        let imp = self.imp();
+
+        let is_same_album = imp
+            .album
+            .borrow()
+            .iter()
+            .map(|item| &item.message)
+            .eq(messages.iter());
+        if is_same_album {
+            return;
+        }
+
+        for item in imp.album.take() {
+            item.message.disconnect(item.handler_id);
+        }
+
+        if let Some(first) = messages.first() {
+            self.set_message(first.clone());
+        }
+
+        let album = messages
+            .into_iter()
+            .map(|message| {
+                let handler_id =
+                    message.connect_content_notify(clone!(@weak self as obj => move |message, _| {
+                        obj.update_item(message);
+                    }));
+                AlbumItem {
+                    media: media_content(&message),
+                    message,
+                    handler_id,
+                }
+            })
+            .collect();
+        imp.album.replace(album);
+
+        self.update_caption();
+
+        self.stop_videos();
+        imp.mosaic.remove_all();
+        let messages: Vec<Message> = imp
+            .album
+            .borrow()
+            .iter()
+            .map(|item| item.message.clone())
+            .collect();
+        for message in messages {
+            let (widget, aspect_ratio) = self.create_item(&message);
+            imp.mosaic.append(&widget, aspect_ratio);
+        }
+    }
+
+    /// Updates the caption and, only if its media changed, the item of the message.
+    fn update_item(&self, message: &Message) {
+        let imp = self.imp();
+
+        let media = media_content(message);
+        let position = imp
+            .album
+            .borrow()
+            .iter()
+            .position(|item| &item.message == message && item.media != media);
+
+        if let Some(position) = position {
+            imp.album.borrow_mut()[position].media = media;
+
+            let (widget, aspect_ratio) = self.create_item(message);
+            if let Some(old_widget) = self.mosaic_children().nth(position) {
+                stop_video(&old_widget);
+            }
+            imp.mosaic.replace(position, &widget, aspect_ratio);
+        }
+
+        self.update_caption();
+    }
+
+    fn update_caption(&self) {
+        let imp = self.imp();
+
+        // The album caption is the caption of the only message that has one, usually the first
+        let caption = imp
+            .album
+            .borrow()
+            .iter()
+            .map(|item| match item.message.content().0 {
+                MessageContent::MessagePhoto(data) => data.caption,
+                MessageContent::MessageVideo(data) => data.caption,
+                _ => FormattedText::default(),
+            })
+            .find(|caption| !caption.text.is_empty())
+            .unwrap_or_default();
+        imp.message_bubble.set_formatted_text(caption);
+    }
+
+    /// Stops the videos of the album, like `MessageVideo` does when it's reused for another
+    /// message, so that no playback outlives its item.
+    fn stop_videos(&self) {
+        for widget in self.mosaic_children() {
+            stop_video(&widget);
+        }
+    }
+
+    fn mosaic_children(&self) -> impl Iterator<Item = gtk::Widget> {
+        std::iter::successors(self.imp().mosaic.first_child(), |widget| {
+            widget.next_sibling()
+        })
+    }
+
+    /// Creates the item of a photo or video and returns it with its aspect ratio.
+    fn create_item(&self, message: &Message) -> (gtk::Widget, f64) {
+        let session = message.chat().session();
+
+        match message.content().0 {
+            MessageContent::MessagePhoto(mut data) => {
+                let photo_size = match data.photo.sizes.iter().position(|s| s.r#type == "x") {
+                    Some(pos) => Some(data.photo.sizes.swap_remove(pos)),
+                    None => data.photo.sizes.pop(),
+                };
+
+                let picture = create_picture(data.photo.minithumbnail, data.has_spoiler);
+                let aspect_ratio = photo_size
+                    .as_ref()
+                    .map(|s| s.width as f64 / s.height as f64)
+                    .unwrap_or(1.0);
+                picture.set_aspect_ratio(aspect_ratio);
+                if let Some(photo_size) = photo_size {
+                    load_picture(&picture, photo_size.photo, &session);
+                }
+
+                connect_clicked(
+                    &picture,
+                    clone!(@weak self as obj, @weak message => move |_| {
+                        let window = obj.root().and_downcast::<gtk::Window>();
+                        MediaViewer::new(&window, &message).present();
+                    }),
+                );
+
+                (picture.upcast(), aspect_ratio)
+            }
+            MessageContent::MessageVideo(data) => {
+                let picture = create_picture(data.video.minithumbnail, data.has_spoiler);
+                let aspect_ratio = data.video.width as f64 / data.video.height as f64;
+                picture.set_aspect_ratio(aspect_ratio);
+                if let Some(thumbnail) = data.video.thumbnail {
+                    load_picture(&picture, thumbnail.file, &session);
+                }
+
+                let video = data.video.video;
+                connect_clicked(
+                    &picture,
+                    clone!(@weak session => move |picture| {
+                        toggle_video_playback(picture, video.clone(), &session);
+                    }),
+                );
+
+                let duration_label =
+                    gtk::Label::new(Some(&format_duration(data.video.duration as i64)));
+                duration_label.set_halign(gtk::Align::Start);
+                duration_label.set_valign(gtk::Align::Start);
+                duration_label.set_can_target(false);
+                duration_label.add_css_class("osd-indicator");
+
+                let overlay = gtk::Overlay::new();
+                overlay.set_child(Some(&picture));
+                overlay.add_overlay(&duration_label);
+
+                (overlay.upcast(), aspect_ratio)
+            }
+            content => {
+                log::warn!("Unexpected content in a media album: {content:?}");
+                (create_picture(None, false).upcast(), 1.0)
+            }
+        }
+    }
+}
+
+/// Returns the content of the message without its caption, to tell whether its media changed.
+fn media_content(message: &Message) -> MessageContent {
+    match message.content().0 {
+        MessageContent::MessagePhoto(mut data) => {
+            data.caption = FormattedText::default();
+            MessageContent::MessagePhoto(data)
+        }
+        MessageContent::MessageVideo(mut data) => {
+            data.caption = FormattedText::default();
+            MessageContent::MessageVideo(data)
+        }
+        content => content,
+    }
+}
+
+fn create_picture(minithumbnail: Option<Minithumbnail>, has_spoiler: bool) -> MediaPicture {
+    let picture: MediaPicture = glib::Object::new();
+    picture.set_has_spoiler(has_spoiler);
+    picture.set_paintable(minithumbnail_texture(minithumbnail).as_ref());
+    picture
+}
+
+/// Calls the callback when the picture is clicked, unless the click reveals a spoiler.
+fn connect_clicked<F: Fn(&MediaPicture) + 'static>(picture: &MediaPicture, f: F) {
+    let click = gtk::GestureClick::new();
+    click.connect_pressed(clone!(@weak picture => move |click, _, _, _| {
+        if picture.has_spoiler() {
+            click.set_state(gtk::EventSequenceState::Denied);
+        }
+    }));
+    click.connect_released(clone!(@weak picture => move |_, _, _, _| {
+        f(&picture);
+    }));
+    picture.add_controller(click);
+}
+
+/// Pauses the video of an album item, if it's playing.
+fn stop_video(widget: &gtk::Widget) {
+    let picture = match widget.downcast_ref::<gtk::Overlay>() {
+        Some(overlay) => overlay.child(),
+        None => Some(widget.clone()),
+    };
+
+    if let Some(media) = picture
+        .and_downcast::<MediaPicture>()
+        .and_then(|picture| picture.paintable())
+        .and_downcast::<gtk::MediaStream>()
+    {
+        media.pause();
+    }
+}
+
+/// Plays the video inside the picture, or pauses and resumes it if it's already playing.
+fn toggle_video_playback(picture: &MediaPicture, file: File, session: &Session) {
+    if let Some(media) = picture.paintable().and_downcast::<gtk::MediaStream>() {
+        media.set_playing(!media.is_playing());
+        return;
+    }
+
+    spawn(clone!(@weak picture, @weak session => async move {
+        let path = if file.local.is_downloading_completed {
+            file.local.path
+        } else {
+            match session.download_file(file.id).await {
+                Ok(file) => file.local.path,
+                Err(e) => {
+                    log::warn!("Failed to download an album video: {e:?}");
+                    return;
+                }
+            }
+        };
+
+        let media = gtk::MediaFile::for_filename(&path);
+        media.play();
+        picture.set_paintable(Some(&media));
+    }));
+}
+
+fn load_picture(picture: &MediaPicture, file: File, session: &Session) {
+    spawn(clone!(@weak picture, @weak session => async move {
+        let path = if file.local.is_downloading_completed {
+            file.local.path
+        } else {
+            match session.download_file(file.id).await {
+                Ok(file) => file.local.path,
+                Err(e) => {
+                    log::warn!("Failed to download an album item: {e:?}");
+                    return;
+                }
+            }
+        };
+
+        let result = gio::spawn_blocking(move || decode_image_from_path(&path))
+            .await
+            .unwrap();
+
+        match result {
+            // Don't replace a video that started playing in the meantime
+            Ok(_) if picture.paintable().and_downcast::<gtk::MediaStream>().is_some() => {}
+            Ok(texture) => picture.set_paintable(Some(&texture)),
+            Err(e) => log::warn!("Error decoding an album item: {e:?}"),
+        }
+    }));
+}
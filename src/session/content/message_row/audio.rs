@@ -0,0 +1,299 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, glib, CompositeTemplate};
+use tdlib::enums::MessageContent;
+
+use crate::session::content::message_row::{MessageBase, MessageBaseImpl, MessageBubble};
+use crate::tdlib::Message;
+use crate::utils::{minithumbnail_texture, spawn};
+
+use super::audio_playback::{AudioPlayback, AudioPlaybackRow};
+use super::base::MessageBaseExt;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageAudio : $MessageBase {
+        $MessageBubble message_bubble {
+            styles ["audio"]
+
+            prefix: Box {
+                spacing: 9;
+
+                styles ["file"]
+
+                Overlay {
+                    valign: center;
+
+                    Picture cover_picture {
+                        content-fit: cover;
+                        visible: false;
+                    }
+
+                    [overlay]
+                    Button play_button {
+                        halign: center;
+                        valign: center;
+                        icon-name: "media-playback-start-symbolic";
+                        clicked => $on_play_button_clicked() swapped;
+
+                        styles ["circular", "play-button"]
+                    }
+                }
+
+                Box {
+                    orientation: vertical;
+                    valign: center;
+                    hexpand: true;
+
+                    Label title_label {
+                        xalign: 0;
+                        ellipsize: end;
+                    }
+
+                    Label performer_label {
+                        xalign: 0;
+                        ellipsize: end;
+
+                        styles ["caption"]
+                    }
+
+                    Box {
+                        spacing: 6;
+
+                        Scale progress_scale {
+                            hexpand: true;
+                            sensitive: false;
+                            change-value => $on_progress_scale_change_value() swapped;
+
+                            adjustment: Adjustment {
+                                lower: 0;
+                                upper: 1;
+                            };
+                        }
+
+                        Label time_label {
+                            styles ["numeric", "dim-label", "caption"]
+                        }
+                    }
+                }
+            };
+        }
+    }
+    "#)]
+    pub(crate) struct MessageAudio {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) playback: AudioPlayback,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<MessageBubble>,
+        #[template_child]
+        pub(super) cover_picture: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub(super) play_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) performer_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) progress_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub(super) time_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageAudio {
+        const NAME: &'static str = "MessageAudio";
+        type Type = super::MessageAudio;
+        type ParentType = MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageAudio {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            self.playback.stop();
+        }
+    }
+
+    impl WidgetImpl for MessageAudio {}
+    impl MessageBaseImpl for MessageAudio {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageAudio(ObjectSubclass<imp::MessageAudio>)
+        @extends gtk::Widget, MessageBase;
+}
+
+impl MessageBaseExt for MessageAudio {
+    type Message = Message;
+
+    fn set_message(&self, message: Self::Message) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == Some(&message) {
+            return;
+        }
+
+        if let Some(old_message) = imp.message.take() {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        // Stop the playback of the previous audio
+        imp.playback.stop();
+        imp.progress_scale.set_value(0.0);
+        imp.progress_scale.set_sensitive(false);
+
+        imp.message_bubble.update_from_message(&message, false);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message, _| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+        self.update_content(&message);
+
+        imp.message.replace(Some(message));
+        self.notify("message");
+    }
+}
+
+impl AudioPlaybackRow for MessageAudio {
+    fn playback(&self) -> &AudioPlayback {
+        &self.imp().playback
+    }
+
+    fn play_button(&self) -> &gtk::Button {
+        &self.imp().play_button
+    }
+
+    fn time_label(&self) -> &gtk::Label {
+        &self.imp().time_label
+    }
+
+    fn set_playback_progress(&self, progress: f64) {
+        self.imp().progress_scale.set_value(progress);
+    }
+
+    fn set_download_progress(&self, progress: f64) {
+        self.imp().progress_scale.set_value(progress);
+    }
+
+    fn media_loaded(&self) {
+        let imp = self.imp();
+        imp.progress_scale.set_value(0.0);
+        imp.progress_scale.set_sensitive(true);
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessageAudio {
+    #[template_callback]
+    fn on_play_button_clicked(&self) {
+        self.toggle_playback();
+    }
+
+    #[template_callback]
+    fn on_progress_scale_change_value(&self, _scroll: gtk::ScrollType, value: f64) -> bool {
+        self.seek(value);
+        false
+    }
+
+    fn update_content(&self, message: &Message) {
+        if let MessageContent::MessageAudio(data) = message.content().0 {
+            let imp = self.imp();
+
+            imp.message_bubble.set_formatted_text(data.caption);
+
+            let title = if data.audio.title.is_empty() {
+                data.audio.file_name.clone()
+            } else {
+                data.audio.title.clone()
+            };
+            imp.title_label.set_label(&title);
+            imp.performer_label.set_label(&data.audio.performer);
+            imp.performer_label
+                .set_visible(!data.audio.performer.is_empty());
+
+            self.update_cover(message, &data.audio);
+            self.set_audio_file(data.audio.audio, data.audio.duration);
+        }
+    }
+
+    fn update_cover(&self, message: &Message, audio: &tdlib::types::Audio) {
+        let imp = self.imp();
+
+        if let Some(thumbnail) = audio.album_cover_thumbnail.clone() {
+            imp.cover_picture.set_visible(true);
+            imp.play_button.add_css_class("osd");
+
+            if thumbnail.file.local.is_downloading_completed {
+                imp.cover_picture
+                    .set_filename(Some(&thumbnail.file.local.path));
+            } else {
+                imp.cover_picture.set_paintable(
+                    minithumbnail_texture(audio.album_cover_minithumbnail.clone()).as_ref(),
+                );
+
+                let message_id = message.id();
+                let session = message.chat().session();
+                spawn(clone!(@weak self as obj => async move {
+                    match session.download_file(thumbnail.file.id).await {
+                        Ok(file) => {
+                            // The widget may have been recycled in the meantime
+                            if obj.message().id() == message_id {
+                                obj.imp().cover_picture.set_filename(Some(&file.local.path));
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to download an album cover: {e:?}");
+                        }
+                    }
+                }));
+            }
+        } else {
+            imp.cover_picture.set_visible(false);
+            imp.cover_picture.set_paintable(gdk::Paintable::NONE);
+            imp.play_button.remove_css_class("osd");
+        }
+    }
+}
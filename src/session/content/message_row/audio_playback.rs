@@ -0,0 +1,188 @@
+use glib::clone;
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use tdlib::types::File;
+
+use crate::tdlib::Message;
+use crate::utils::format_duration;
+
+use super::base::MessageBaseExt;
+
+/// The playback state of the rows of audio files and voice notes.
+#[derive(Debug, Default)]
+pub(super) struct AudioPlayback {
+    file: RefCell<Option<File>>,
+    duration: Cell<i32>,
+    media: RefCell<Option<gtk::MediaFile>>,
+    play_after_download: Cell<bool>,
+}
+
+impl AudioPlayback {
+    /// Stops the playback, if any, and drops the media.
+    pub(super) fn stop(&self) {
+        if let Some(media) = self.media.take() {
+            media.pause();
+        }
+        self.play_after_download.set(false);
+    }
+}
+
+/// The rows that download and play an audio file with a play button and a time label.
+pub(super) trait AudioPlaybackRow: MessageBaseExt<Message = Message> {
+    fn playback(&self) -> &AudioPlayback;
+
+    fn play_button(&self) -> &gtk::Button;
+
+    fn time_label(&self) -> &gtk::Label;
+
+    /// Shows the playback progress, from 0 to 1.
+    fn set_playback_progress(&self, progress: f64);
+
+    /// Shows the download progress, from 0 to 1.
+    fn set_download_progress(&self, _progress: f64) {}
+
+    /// Called when the media has been loaded and can be seeked.
+    fn media_loaded(&self) {}
+
+    /// Sets the file to play, ignoring the eventual updates of the previous one.
+    fn set_audio_file(&self, file: File, duration: i32) {
+        let playback = self.playback();
+        playback.duration.set(duration);
+        playback.file.replace(None);
+        self.update_file(file);
+    }
+
+    /// Plays or pauses the media, downloading the file first if needed.
+    fn toggle_playback(&self) {
+        let playback = self.playback();
+
+        let media = playback.media.borrow().clone();
+        if let Some(media) = media {
+            if media.is_playing() {
+                media.pause();
+            } else {
+                if media.is_ended() {
+                    media.seek(0);
+                }
+                media.play();
+            }
+            return;
+        }
+
+        let file = match playback.file.borrow().clone() {
+            Some(file) => file,
+            None => return,
+        };
+
+        if file.local.is_downloading_completed {
+            self.load_media(&file.local.path);
+        } else {
+            let session = self.message().chat().session();
+
+            if file.local.is_downloading_active {
+                playback.play_after_download.set(false);
+                session.cancel_download_file(file.id);
+            } else {
+                playback.play_after_download.set(true);
+                session.download_file_with_updates(
+                    file.id,
+                    clone!(@weak self as obj => move |file| {
+                        obj.update_file(file);
+                    }),
+                );
+            }
+        }
+    }
+
+    /// Seeks the media to the given progress, from 0 to 1.
+    fn seek(&self, progress: f64) {
+        if let Some(media) = self.playback().media.borrow().as_ref() {
+            if media.is_seekable() {
+                media.seek((media.duration() as f64 * progress.clamp(0.0, 1.0)) as i64);
+            }
+        }
+    }
+
+    fn update_file(&self, file: File) {
+        let playback = self.playback();
+
+        if let Some(old_file) = playback.file.borrow().as_ref() {
+            if old_file.id != file.id {
+                return;
+            }
+        }
+
+        if playback.media.borrow().is_none() {
+            if file.local.is_downloading_active {
+                let size = file.size.max(file.expected_size) as u64;
+                let downloaded = glib::format_size(file.local.downloaded_size as u64);
+                let full_size = glib::format_size(size);
+
+                self.play_button()
+                    .set_icon_name("media-playback-stop-symbolic");
+                self.set_download_progress(if size > 0 {
+                    file.local.downloaded_size as f64 / size as f64
+                } else {
+                    0.0
+                });
+                self.time_label()
+                    .set_label(&format!("{downloaded} / {full_size}"));
+            } else {
+                self.play_button()
+                    .set_icon_name("media-playback-start-symbolic");
+                self.set_download_progress(0.0);
+                self.time_label()
+                    .set_label(&format_duration(playback.duration.get() as i64));
+            }
+        }
+
+        let path = file.local.path.clone();
+        let is_downloaded = file.local.is_downloading_completed;
+        playback.file.replace(Some(file));
+
+        if is_downloaded && playback.play_after_download.replace(false) {
+            self.load_media(&path);
+        }
+    }
+
+    fn load_media(&self, path: &str) {
+        let media = gtk::MediaFile::for_filename(path);
+        media.connect_playing_notify(clone!(@weak self as obj => move |media| {
+            let icon_name = if media.is_playing() {
+                "media-playback-pause-symbolic"
+            } else {
+                "media-playback-start-symbolic"
+            };
+            obj.play_button().set_icon_name(icon_name);
+        }));
+        media.connect_timestamp_notify(clone!(@weak self as obj => move |media| {
+            obj.update_playback_progress(media);
+        }));
+        media.play();
+
+        self.playback().media.replace(Some(media));
+        self.media_loaded();
+    }
+
+    fn update_playback_progress(&self, media: &gtk::MediaFile) {
+        let duration = if media.duration() > 0 {
+            media.duration()
+        } else {
+            self.playback().duration.get() as i64 * i64::pow(10, 6)
+        };
+        let timestamp = media.timestamp();
+
+        self.set_playback_progress(if duration > 0 {
+            (timestamp as f64 / duration as f64).clamp(0.0, 1.0)
+        } else {
+            0.0
+        });
+
+        self.time_label().set_label(&format!(
+            "{} / {}",
+            format_duration(timestamp / i64::pow(10, 6)),
+            format_duration(duration / i64::pow(10, 6))
+        ));
+    }
+}
@@ -55,10 +55,11 @@ mod imp {
         fn show_message_menu(&self, x: i32, y: i32) {
             let obj = self.obj();
             let chat_history = obj.ancestor(ChatHistory::static_type()).unwrap();
-            let menu = chat_history
-                .downcast_ref::<ChatHistory>()
-                .unwrap()
-                .message_menu();
+            let chat_history = chat_history.downcast_ref::<ChatHistory>().unwrap();
+            let menu = chat_history.message_menu();
+
+            let message = obj.property::<glib::Object>("message").downcast().ok();
+            chat_history.message_reaction_picker().set_message(message);
 
             menu.set_pointing_to(Some(&gdk::Rectangle::new(x, y, 0, 0)));
             menu.unparent();
@@ -3,9 +3,12 @@ use gtk::subclass::prelude::*;
 use gtk::{glib, CompositeTemplate};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use tdlib::types::FormattedText;
 
-use crate::session::content::message_row::{MessageIndicators, MessageLabel, MessageReply};
-use crate::tdlib::{Chat, ChatType, Message, MessageSender, SponsoredMessage};
+use crate::session::content::message_row::{
+    MessageIndicators, MessageLabel, MessageReactions, MessageReply,
+};
+use crate::tdlib::{BoxedFormattedText, Chat, ChatType, Message, MessageSender, SponsoredMessage};
 
 const MAX_WIDTH: i32 = 400;
 const SENDER_COLOR_CLASSES: &[&str] = &[
@@ -47,6 +50,10 @@ mod imp {
                 $MessageLabel message_label {
                     visible: false;
                 }
+
+                $MessageReactions reactions {
+                    visible: false;
+                }
             }
 
             [overlay]
@@ -71,6 +78,8 @@ mod imp {
         #[template_child]
         pub(super) message_label: TemplateChild<MessageLabel>,
         #[template_child]
+        pub(super) reactions: TemplateChild<MessageReactions>,
+        #[template_child]
         pub(super) indicators: TemplateChild<MessageIndicators>,
     }
 
@@ -98,6 +107,9 @@ mod imp {
                         .write_only()
                         .build(),
                     glib::ParamSpecString::builder("label").write_only().build(),
+                    glib::ParamSpecBoxed::builder::<BoxedFormattedText>("formatted-text")
+                        .write_only()
+                        .build(),
                 ]
             });
             PROPERTIES.as_ref()
@@ -109,6 +121,13 @@ mod imp {
             match pspec.name() {
                 "prefix" => obj.set_prefix(value.get().unwrap()),
                 "label" => obj.set_label(value.get().unwrap()),
+                "formatted-text" => obj.set_formatted_text(
+                    value
+                        .get::<Option<BoxedFormattedText>>()
+                        .unwrap()
+                        .map(|t| t.0)
+                        .unwrap_or_default(),
+                ),
                 _ => unimplemented!(),
             }
         }
@@ -157,6 +176,8 @@ impl MessageBubble {
         let imp = self.imp();
 
         imp.indicators.set_message(message.clone().upcast());
+        imp.message_label.set_session(&message.chat().session());
+        imp.reactions.set_message(Some(message));
 
         let is_channel = if let ChatType::Supergroup(data) = message.chat().type_() {
             data.is_channel()
@@ -236,6 +257,9 @@ impl MessageBubble {
 
         imp.indicators
             .set_message(sponsored_message.clone().upcast());
+        imp.message_label
+            .set_session(&sponsored_message.sponsor_chat().session());
+        imp.reactions.set_message(None);
 
         self.remove_css_class("outgoing");
 
@@ -277,6 +301,20 @@ impl MessageBubble {
         self.update_indicators_position();
     }
 
+    pub(crate) fn set_formatted_text(&self, formatted_text: FormattedText) {
+        let imp = self.imp();
+
+        if formatted_text.text.is_empty() {
+            self.set_label(String::new());
+        } else {
+            imp.message_label.set_formatted_text(formatted_text);
+            imp.message_label.set_visible(true);
+
+            self.add_css_class("with-label");
+            self.update_indicators_position();
+        }
+    }
+
     fn update_sender_color(&self, sender_id: Option<i64>) {
         let imp = self.imp();
 
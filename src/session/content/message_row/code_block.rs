@@ -0,0 +1,468 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+
+use crate::utils::escape;
+
+mod imp {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageCodeBlock {
+        Box content {
+            orientation: vertical;
+
+            Box {
+                spacing: 6;
+
+                styles ["header"]
+
+                Label language_label {
+                    hexpand: true;
+                    xalign: 0;
+                    ellipsize: end;
+
+                    styles ["caption-heading", "dim-label"]
+                }
+
+                Button copy_button {
+                    valign: center;
+                    icon-name: "edit-copy-symbolic";
+                    action-name: "code-block.copy";
+
+                    styles ["flat", "circular"]
+                }
+            }
+
+            ScrolledWindow {
+                vscrollbar-policy: never;
+                propagate-natural-width: true;
+                propagate-natural-height: true;
+
+                Label code_label {
+                    selectable: true;
+                    xalign: 0;
+                    yalign: 0;
+
+                    styles ["monospace"]
+                }
+            }
+        }
+    }
+    "#)]
+    pub(crate) struct MessageCodeBlock {
+        pub(super) code: RefCell<String>,
+        pub(super) language: RefCell<String>,
+        pub(super) dark_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        #[template_child]
+        pub(super) content: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) language_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) copy_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) code_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageCodeBlock {
+        const NAME: &'static str = "MessageCodeBlock";
+        type Type = super::MessageCodeBlock;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("messagecodeblock");
+
+            klass.install_action("code-block.copy", None, move |widget, _, _| {
+                widget.copy_code();
+            });
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageCodeBlock {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            self.copy_button
+                .set_tooltip_text(Some(&gettext("Copy Code")));
+
+            // The highlighting colors depend on the color scheme
+            let handler_id =
+                adw::StyleManager::default().connect_dark_notify(clone!(@weak obj => move |_| {
+                    obj.update_code_label();
+                }));
+            self.dark_handler_id.replace(Some(handler_id));
+        }
+
+        fn dispose(&self) {
+            if let Some(handler_id) = self.dark_handler_id.take() {
+                adw::StyleManager::default().disconnect(handler_id);
+            }
+
+            self.content.unparent();
+        }
+    }
+
+    impl WidgetImpl for MessageCodeBlock {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageCodeBlock(ObjectSubclass<imp::MessageCodeBlock>)
+        @extends gtk::Widget;
+}
+
+impl MessageCodeBlock {
+    pub(crate) fn new(code: String, language: String) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+
+        imp.language_label.set_label(&if language.is_empty() {
+            gettext("Code")
+        } else {
+            language.clone()
+        });
+
+        imp.code.replace(code);
+        imp.language.replace(language);
+        obj.update_code_label();
+
+        obj
+    }
+
+    fn update_code_label(&self) {
+        let imp = self.imp();
+        let is_dark = adw::StyleManager::default().is_dark();
+
+        imp.code_label.set_markup(&highlight(
+            &imp.code.borrow(),
+            &imp.language.borrow(),
+            is_dark,
+        ));
+    }
+
+    fn copy_code(&self) {
+        self.clipboard().set_text(&self.imp().code.borrow());
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TokenClass {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+}
+
+impl TokenClass {
+    fn color(self, is_dark: bool) -> &'static str {
+        // Colors from the GNOME HIG palette
+        match (self, is_dark) {
+            (Self::Keyword, false) => "#9141ac",
+            (Self::Keyword, true) => "#dc8add",
+            (Self::Type, false) => "#1a5fb4",
+            (Self::Type, true) => "#99c1f1",
+            (Self::String, false) => "#26a269",
+            (Self::String, true) => "#8ff0a4",
+            (Self::Number, false) => "#c64600",
+            (Self::Number, true) => "#ffbe6f",
+            (Self::Comment, false) => "#77767b",
+            (Self::Comment, true) => "#9a9996",
+        }
+    }
+}
+
+struct LanguageSpec {
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    string_delimiters: &'static [char],
+    ignore_case: bool,
+}
+
+const RUST: LanguageSpec = LanguageSpec {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+        "type", "unsafe", "use", "where", "while",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"'],
+    ignore_case: false,
+};
+
+const PYTHON: LanguageSpec = LanguageSpec {
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+        "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "True",
+        "try", "while", "with", "yield",
+    ],
+    line_comments: &["#"],
+    block_comment: None,
+    string_delimiters: &['"', '\''],
+    ignore_case: false,
+};
+
+const JAVASCRIPT: LanguageSpec = LanguageSpec {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "return",
+        "static",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typeof",
+        "undefined",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"', '\'', '`'],
+    ignore_case: false,
+};
+
+const C_LIKE: LanguageSpec = LanguageSpec {
+    keywords: &[
+        "abstract",
+        "auto",
+        "bool",
+        "break",
+        "case",
+        "catch",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "delete",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extends",
+        "extern",
+        "false",
+        "final",
+        "float",
+        "for",
+        "func",
+        "fun",
+        "go",
+        "goto",
+        "if",
+        "implements",
+        "import",
+        "int",
+        "interface",
+        "long",
+        "namespace",
+        "new",
+        "null",
+        "nullptr",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "short",
+        "signed",
+        "sizeof",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typedef",
+        "union",
+        "unsigned",
+        "using",
+        "val",
+        "var",
+        "virtual",
+        "void",
+        "volatile",
+        "while",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['"', '\''],
+    ignore_case: false,
+};
+
+const SHELL: LanguageSpec = LanguageSpec {
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "until", "while",
+    ],
+    line_comments: &["#"],
+    block_comment: None,
+    string_delimiters: &['"', '\''],
+    ignore_case: false,
+};
+
+const SQL: LanguageSpec = LanguageSpec {
+    keywords: &[
+        "and", "as", "by", "create", "delete", "from", "group", "insert", "into", "join", "left",
+        "limit", "not", "null", "on", "or", "order", "select", "set", "table", "update", "values",
+        "where",
+    ],
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    string_delimiters: &['\''],
+    ignore_case: true,
+};
+
+fn language_spec(language: &str) -> Option<&'static LanguageSpec> {
+    Some(match language.to_lowercase().as_str() {
+        "rust" | "rs" => &RUST,
+        "python" | "py" => &PYTHON,
+        "javascript" | "js" | "typescript" | "ts" | "jsx" | "tsx" => &JAVASCRIPT,
+        "c" | "h" | "cpp" | "c++" | "cs" | "csharp" | "java" | "kotlin" | "kt" | "go"
+        | "golang" | "swift" | "dart" => &C_LIKE,
+        "bash" | "sh" | "shell" | "zsh" => &SHELL,
+        "sql" => &SQL,
+        _ => return None,
+    })
+}
+
+/// Converts the code to Pango markup with syntax highlighting, if the language is known.
+fn highlight(code: &str, language: &str, is_dark: bool) -> String {
+    let spec = match language_spec(language) {
+        Some(spec) => spec,
+        None => return escape(code),
+    };
+
+    let mut output = String::new();
+    let mut rest = code;
+
+    while !rest.is_empty() {
+        let (len, class) = next_token(rest, spec);
+        let token = escape(&rest[..len]);
+
+        match class {
+            Some(class) => output.push_str(&format!(
+                "<span foreground='{}'>{token}</span>",
+                class.color(is_dark)
+            )),
+            None => output.push_str(&token),
+        }
+
+        rest = &rest[len..];
+    }
+
+    output
+}
+
+/// Returns the length in bytes of the token at the start of the text and its class.
+fn next_token(text: &str, spec: &LanguageSpec) -> (usize, Option<TokenClass>) {
+    if spec.line_comments.iter().any(|p| text.starts_with(p)) {
+        let len = text.find('\n').unwrap_or(text.len());
+        return (len, Some(TokenClass::Comment));
+    }
+
+    if let Some((start, end)) = spec.block_comment {
+        if text.starts_with(start) {
+            let len = text[start.len()..]
+                .find(end)
+                .map(|i| start.len() + i + end.len())
+                .unwrap_or(text.len());
+            return (len, Some(TokenClass::Comment));
+        }
+    }
+
+    let first = text.chars().next().unwrap();
+
+    if spec.string_delimiters.contains(&first) {
+        let mut is_escaped = false;
+        let len = text
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| {
+                let is_end = !is_escaped && *c == first;
+                is_escaped = !is_escaped && *c == '\\';
+                is_end
+            })
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(text.len());
+        return (len, Some(TokenClass::String));
+    }
+
+    let word_len = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+
+    if first.is_ascii_digit() {
+        let len = text
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(text.len());
+        (len, Some(TokenClass::Number))
+    } else if word_len > 0 {
+        let word = &text[..word_len];
+        let is_keyword = if spec.ignore_case {
+            spec.keywords.contains(&word.to_lowercase().as_str())
+        } else {
+            spec.keywords.contains(&word)
+        };
+        let class = if is_keyword {
+            Some(TokenClass::Keyword)
+        } else if first.is_uppercase() {
+            Some(TokenClass::Type)
+        } else {
+            None
+        };
+        (word_len, class)
+    } else {
+        (first.len_utf8(), None)
+    }
+}
@@ -0,0 +1,333 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+use tdlib::enums::{self, MessageContent};
+use tdlib::functions;
+use tdlib::types::Contact;
+
+use crate::components::Avatar;
+use crate::session::content::message_row::{MessageBase, MessageBaseImpl, MessageBubble};
+use crate::tdlib::{Message, User};
+use crate::utils::spawn;
+
+use super::base::MessageBaseExt;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageContact : $MessageBase {
+        $MessageBubble message_bubble {
+            styles ["contact"]
+
+            prefix: Box {
+                orientation: vertical;
+                spacing: 6;
+
+                styles ["contact"]
+
+                Box {
+                    spacing: 9;
+
+                    $ComponentsAvatar avatar {
+                        size: 48;
+                    }
+
+                    Box {
+                        orientation: vertical;
+                        valign: center;
+                        hexpand: true;
+
+                        Label name_label {
+                            xalign: 0;
+                            ellipsize: end;
+
+                            styles ["heading"]
+                        }
+
+                        Label phone_label {
+                            xalign: 0;
+                            selectable: true;
+
+                            styles ["numeric", "caption"]
+                        }
+                    }
+
+                    Button copy_button {
+                        valign: center;
+                        icon-name: "edit-copy-symbolic";
+                        action-name: "message-contact.copy-phone-number";
+
+                        styles ["flat", "circular"]
+                    }
+                }
+
+                Box {
+                    spacing: 6;
+                    homogeneous: true;
+
+                    Button message_button {
+                        action-name: "message-contact.send-message";
+                    }
+
+                    Button add_button {
+                        action-name: "message-contact.add-contact";
+                    }
+                }
+            };
+        }
+    }
+    "#)]
+    pub(crate) struct MessageContact {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) contact: RefCell<Option<Contact>>,
+        pub(super) user: RefCell<Option<User>>,
+        pub(super) user_handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<MessageBubble>,
+        #[template_child]
+        pub(super) avatar: TemplateChild<Avatar>,
+        #[template_child]
+        pub(super) name_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) phone_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) copy_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) message_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) add_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageContact {
+        const NAME: &'static str = "MessageContact";
+        type Type = super::MessageContact;
+        type ParentType = MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+
+            klass.install_action(
+                "message-contact.copy-phone-number",
+                None,
+                move |widget, _, _| {
+                    widget.copy_phone_number();
+                },
+            );
+            klass.install_action("message-contact.send-message", None, move |widget, _, _| {
+                widget.send_message();
+            });
+            klass.install_action("message-contact.add-contact", None, move |widget, _, _| {
+                widget.add_contact();
+            });
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageContact {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.copy_button
+                .set_tooltip_text(Some(&gettext("Copy Phone Number")));
+            self.message_button.set_label(&gettext("Message"));
+            self.add_button.set_label(&gettext("Add to Contacts"));
+        }
+
+        fn dispose(&self) {
+            self.obj().set_user(None);
+        }
+    }
+
+    impl WidgetImpl for MessageContact {}
+    impl MessageBaseImpl for MessageContact {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageContact(ObjectSubclass<imp::MessageContact>)
+        @extends gtk::Widget, MessageBase;
+}
+
+impl MessageBaseExt for MessageContact {
+    type Message = Message;
+
+    fn set_message(&self, message: Self::Message) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == Some(&message) {
+            return;
+        }
+
+        if let Some(old_message) = imp.message.take() {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.message_bubble.update_from_message(&message, false);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message, _| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+        self.update_content(&message);
+
+        imp.message.replace(Some(message));
+        self.notify("message");
+    }
+}
+
+impl MessageContact {
+    fn update_content(&self, message: &Message) {
+        if let MessageContent::MessageContact(data) = message.content().0 {
+            let imp = self.imp();
+            let contact = data.contact;
+
+            let name = if contact.last_name.is_empty() {
+                contact.first_name.clone()
+            } else if contact.first_name.is_empty() {
+                contact.last_name.clone()
+            } else {
+                format!("{} {}", contact.first_name, contact.last_name)
+            };
+            imp.name_label.set_label(&name);
+            imp.phone_label
+                .set_label(&format_phone_number(&contact.phone_number));
+
+            let user = if contact.user_id != 0 {
+                Some(message.chat().session().user(contact.user_id))
+            } else {
+                None
+            };
+            imp.avatar.set_item(user.clone().map(|u| u.upcast()));
+            imp.avatar.set_custom_text(Some(&name));
+
+            imp.contact.replace(Some(contact));
+            self.set_user(user);
+        }
+    }
+
+    fn set_user(&self, user: Option<User>) {
+        let imp = self.imp();
+
+        if let Some(old_user) = imp.user.take() {
+            if let Some(handler_id) = imp.user_handler_id.take() {
+                old_user.disconnect(handler_id);
+            }
+        }
+
+        if let Some(ref user) = user {
+            let handler_id =
+                user.connect_is_contact_notify(clone!(@weak self as obj => move |_, _| {
+                    obj.update_actions();
+                }));
+            imp.user_handler_id.replace(Some(handler_id));
+        }
+
+        imp.user.replace(user);
+        self.update_actions();
+    }
+
+    fn update_actions(&self) {
+        let imp = self.imp();
+        let user = imp.user.borrow();
+
+        // Contacts that are not registered on Telegram can't be messaged
+        let can_send_message = user.is_some();
+        let can_add_contact = user
+            .as_ref()
+            .map(|u| !u.is_contact() && u.id() != u.session().me().id())
+            .unwrap_or_default();
+
+        self.action_set_enabled("message-contact.send-message", can_send_message);
+        self.action_set_enabled("message-contact.add-contact", can_add_contact);
+        imp.message_button.set_visible(can_send_message);
+        imp.add_button.set_visible(can_add_contact);
+    }
+
+    fn copy_phone_number(&self) {
+        if let Some(contact) = self.imp().contact.borrow().as_ref() {
+            self.clipboard()
+                .set_text(&format_phone_number(&contact.phone_number));
+        }
+    }
+
+    fn send_message(&self) {
+        let user = match self.imp().user.borrow().clone() {
+            Some(user) => user,
+            None => return,
+        };
+        let session = user.session();
+
+        // Check if a private chat with this user already exists
+        if let Some(chat) = session.try_chat(user.id()) {
+            session.select_chat(chat.id());
+            return;
+        }
+
+        spawn(async move {
+            match functions::create_private_chat(user.id(), true, session.client_id()).await {
+                Ok(enums::Chat::Chat(data)) => session.select_chat(data.id),
+                Err(e) => log::warn!("Failed to create private chat: {:?}", e),
+            }
+        });
+    }
+
+    fn add_contact(&self) {
+        let contact = match self.imp().contact.borrow().clone() {
+            Some(contact) => contact,
+            None => return,
+        };
+        let client_id = self.message().chat().session().client_id();
+
+        spawn(async move {
+            if let Err(e) = functions::add_contact(contact, false, client_id).await {
+                log::warn!("Failed to add contact: {:?}", e);
+            }
+        });
+    }
+}
+
+fn format_phone_number(phone_number: &str) -> String {
+    if phone_number.starts_with('+') {
+        phone_number.to_owned()
+    } else {
+        format!("+{phone_number}")
+    }
+}
@@ -7,7 +7,7 @@ use tdlib::types::File;
 
 use crate::session::content::message_row::{MessageBase, MessageBaseImpl, MessageBubble};
 use crate::tdlib::Message;
-use crate::utils::{parse_formatted_text, spawn};
+use crate::utils::spawn;
 use crate::Session;
 
 use super::base::MessageBaseExt;
@@ -160,8 +160,7 @@ impl MessageDocument {
         if let MessageContent::MessageDocument(data) = message.content().0 {
             let imp = self.imp();
 
-            let message_text = parse_formatted_text(data.caption);
-            imp.message_bubble.set_label(message_text);
+            imp.message_bubble.set_formatted_text(data.caption);
 
             imp.file_name_label.set_label(&data.document.file_name);
 
@@ -1,16 +1,35 @@
+use glib::clone;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use gtk::{glib, pango, CompositeTemplate};
-
+use gtk::{glib, graphene, gsk, pango, CompositeTemplate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tdlib::enums::{self, StickerFullType};
+use tdlib::functions;
+use tdlib::types::{FormattedText, Sticker as TdSticker};
+
+use crate::components::Sticker;
 use crate::session::content::message_row::MessageIndicators;
+use crate::utils::{
+    parse_formatted_text_with_style, spawn, MarkupStyle, CUSTOM_EMOJI_URI_PREFIX, SPOILER_URI,
+};
+use crate::Session;
 
 const OBJECT_REPLACEMENT_CHARACTER: char = '\u{FFFC}';
 const INDICATORS_SPACING: i32 = 6;
+const CUSTOM_EMOJI_SIZE: i32 = 20;
+
+static CUSTOM_EMOJI_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        "<a href='{}(\\d+)'>.*?</a>",
+        regex::escape(CUSTOM_EMOJI_URI_PREFIX)
+    ))
+    .unwrap()
+});
 
 mod imp {
     use super::*;
-    use once_cell::sync::Lazy;
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
 
     #[derive(Debug, Default, CompositeTemplate)]
     #[template(string = r#"
@@ -26,8 +45,16 @@ mod imp {
     "#)]
     pub(crate) struct MessageLabel {
         pub(super) text: RefCell<String>,
+        /// The formatted text the markup has been generated from, if any
+        pub(super) formatted_text: RefCell<Option<FormattedText>>,
         pub(super) indicators: RefCell<Option<MessageIndicators>>,
         pub(super) indicators_size: RefCell<Option<(i32, i32)>>,
+        pub(super) session: glib::WeakRef<Session>,
+        pub(super) custom_emoji_ids: RefCell<Vec<i64>>,
+        pub(super) custom_emojis: RefCell<Vec<Sticker>>,
+        /// The byte indices of the custom emoji placeholders in the text of the label
+        pub(super) custom_emoji_indices: RefCell<Vec<usize>>,
+        pub(super) spoilers_revealed: Cell<bool>,
         #[template_child]
         pub(super) label: TemplateChild<gtk::Label>,
     }
@@ -83,11 +110,30 @@ mod imp {
             }
         }
 
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            self.label.connect_activate_link(
+                clone!(@weak obj => @default-return gtk::Inhibit(false), move |_, uri| {
+                    if uri == SPOILER_URI {
+                        obj.reveal_spoilers();
+                        gtk::Inhibit(true)
+                    } else {
+                        gtk::Inhibit(false)
+                    }
+                }),
+            );
+        }
+
         fn dispose(&self) {
             self.label.unparent();
             if let Some(indicators) = self.indicators.take() {
                 indicators.unparent();
             }
+            for custom_emoji in self.custom_emojis.take() {
+                custom_emoji.unparent();
+            }
         }
     }
 
@@ -105,10 +151,10 @@ mod imp {
                     if indicators_size.width() != old_indicators_size.0
                         || indicators_size.height() != old_indicators_size.1
                     {
-                        obj.update_label_attributes(&indicators_size);
+                        obj.update_label_attributes(Some(&indicators_size));
                     }
                 } else {
-                    obj.update_label_attributes(&indicators_size);
+                    obj.update_label_attributes(Some(&indicators_size));
                 }
 
                 let (mut minimum, mut natural, minimum_baseline, natural_baseline) =
@@ -135,6 +181,31 @@ mod imp {
             if let Some(indicators) = self.indicators.borrow().as_ref() {
                 indicators.allocate(width, height, baseline, None);
             }
+
+            let custom_emojis = self.custom_emojis.borrow();
+            if !custom_emojis.is_empty() {
+                let layout = self.label.layout();
+                let (offset_x, offset_y) = self.label.layout_offsets();
+
+                for (custom_emoji, index) in custom_emojis
+                    .iter()
+                    .zip(self.custom_emoji_indices.borrow().iter())
+                {
+                    let rect = layout.index_to_pos(*index as i32);
+                    let x = offset_x + rect.x().min(rect.x() + rect.width()) / pango::SCALE;
+                    let y =
+                        offset_y + (rect.y() + rect.height()) / pango::SCALE - CUSTOM_EMOJI_SIZE;
+
+                    let transform =
+                        gsk::Transform::new().translate(&graphene::Point::new(x as f32, y as f32));
+                    custom_emoji.allocate(
+                        CUSTOM_EMOJI_SIZE,
+                        CUSTOM_EMOJI_SIZE,
+                        -1,
+                        Some(transform),
+                    );
+                }
+            }
         }
 
         fn request_mode(&self) -> gtk::SizeRequestMode {
@@ -153,10 +224,37 @@ glib::wrapper! {
 }
 
 impl MessageLabel {
-    fn update_label_attributes(&self, indicators_size: &gtk::Requisition) {
+    fn update_label_attributes(&self, indicators_size: Option<&gtk::Requisition>) {
         let imp = self.imp();
-        if let Some(start_index) = imp.label.text().find(OBJECT_REPLACEMENT_CHARACTER) {
-            let attrs = pango::AttrList::new();
+        let text = imp.label.text();
+        let n_custom_emojis = imp.custom_emojis.borrow().len();
+
+        let mut placeholders = text
+            .match_indices(OBJECT_REPLACEMENT_CHARACTER)
+            .map(|(i, _)| i);
+        let custom_emoji_indices: Vec<usize> =
+            placeholders.by_ref().take(n_custom_emojis).collect();
+        let indicators_index = indicators_size.and(placeholders.next());
+
+        if custom_emoji_indices.is_empty() && indicators_index.is_none() {
+            imp.label.set_attributes(None);
+            imp.custom_emoji_indices.replace(custom_emoji_indices);
+            return;
+        }
+
+        let attrs = pango::AttrList::new();
+
+        for start_index in &custom_emoji_indices {
+            let logical_rect = pango::Rectangle::new(
+                0,
+                -(CUSTOM_EMOJI_SIZE - (CUSTOM_EMOJI_SIZE / 4)) * pango::SCALE,
+                CUSTOM_EMOJI_SIZE * pango::SCALE,
+                CUSTOM_EMOJI_SIZE * pango::SCALE,
+            );
+            attrs.insert(placeholder_shape(&logical_rect, *start_index));
+        }
+
+        if let (Some(start_index), Some(indicators_size)) = (indicators_index, indicators_size) {
             let width = indicators_size.width() + INDICATORS_SPACING;
             let height = indicators_size.height();
             let logical_rect = pango::Rectangle::new(
@@ -165,16 +263,11 @@ impl MessageLabel {
                 width * pango::SCALE,
                 height * pango::SCALE,
             );
-            let mut shape = pango::AttrShape::new(&logical_rect, &logical_rect);
-
-            shape.set_start_index(start_index as u32);
-            shape.set_end_index((start_index + OBJECT_REPLACEMENT_CHARACTER.len_utf8()) as u32);
-            attrs.insert(shape);
-
-            imp.label.set_attributes(Some(&attrs));
-        } else {
-            imp.label.set_attributes(None);
+            attrs.insert(placeholder_shape(&logical_rect, start_index));
         }
+
+        imp.label.set_attributes(Some(&attrs));
+        imp.custom_emoji_indices.replace(custom_emoji_indices);
     }
 
     fn is_opposite_text_direction(&self) -> bool {
@@ -189,7 +282,8 @@ impl MessageLabel {
 
     fn update_label(&self) {
         let imp = self.imp();
-        let text = imp.text.borrow();
+        let text = self.extract_custom_emojis(&imp.text.borrow());
+
         if let Some(indicators) = imp.indicators.borrow().as_ref() {
             if !self.is_opposite_text_direction() {
                 imp.label
@@ -199,20 +293,166 @@ impl MessageLabel {
             }
 
             let (_, indicators_size) = indicators.preferred_size();
-            self.update_label_attributes(&indicators_size);
+            self.update_label_attributes(Some(&indicators_size));
         } else {
             imp.label.set_label(&text);
+            self.update_label_attributes(None);
+        }
+    }
+
+    /// Replaces the custom emoji in the markup with placeholders and creates the widgets
+    /// that will be drawn on top of them.
+    fn extract_custom_emojis(&self, markup: &str) -> String {
+        let imp = self.imp();
+
+        // The placeholders are found by their character, so the ones already in the text
+        // would be mistaken for custom emoji or the indicators
+        let markup = markup.replace(OBJECT_REPLACEMENT_CHARACTER, "");
+
+        let custom_emoji_ids: Vec<i64> = CUSTOM_EMOJI_RE
+            .captures_iter(&markup)
+            .filter_map(|captures| captures[1].parse().ok())
+            .collect();
+
+        if *imp.custom_emoji_ids.borrow() != custom_emoji_ids {
+            for custom_emoji in imp.custom_emojis.take() {
+                custom_emoji.unparent();
+            }
+
+            let custom_emojis = custom_emoji_ids
+                .iter()
+                .map(|_| {
+                    let custom_emoji: Sticker = glib::Object::new();
+                    custom_emoji.set_longer_side_size(CUSTOM_EMOJI_SIZE);
+                    custom_emoji.set_parent(self);
+                    custom_emoji
+                })
+                .collect();
+
+            imp.custom_emojis.replace(custom_emojis);
+            imp.custom_emoji_ids.replace(custom_emoji_ids);
+
+            self.load_custom_emojis();
         }
+
+        CUSTOM_EMOJI_RE
+            .replace_all(&markup, OBJECT_REPLACEMENT_CHARACTER.to_string())
+            .into_owned()
+    }
+
+    fn reveal_spoilers(&self) {
+        let imp = self.imp();
+
+        if !imp.spoilers_revealed.replace(true) {
+            if let Some(formatted_text) = imp.formatted_text.borrow().clone() {
+                imp.text.replace(parse_formatted_text_with_style(
+                    formatted_text,
+                    MarkupStyle::MessageLabel {
+                        spoilers_revealed: true,
+                    },
+                ));
+                self.update_label();
+            }
+        }
+    }
+
+    fn load_custom_emojis(&self) {
+        let imp = self.imp();
+
+        let custom_emoji_ids = imp.custom_emoji_ids.borrow().clone();
+        if custom_emoji_ids.is_empty() {
+            return;
+        }
+
+        let session = match imp.session.upgrade() {
+            Some(session) => session,
+            None => return,
+        };
+
+        let mut unique_ids = custom_emoji_ids.clone();
+        unique_ids.sort_unstable();
+        unique_ids.dedup();
+
+        spawn(clone!(@weak self as obj => async move {
+            match functions::get_custom_emoji_stickers(unique_ids, session.client_id()).await {
+                Ok(enums::Stickers::Stickers(data)) => {
+                    // The label may have changed in the meantime
+                    if *obj.imp().custom_emoji_ids.borrow() == custom_emoji_ids {
+                        obj.set_custom_emoji_stickers(data.stickers, session);
+                    }
+                }
+                Err(e) => log::warn!("Error fetching custom emoji stickers: {e:?}"),
+            }
+        }));
+    }
+
+    fn set_custom_emoji_stickers(&self, stickers: Vec<TdSticker>, session: Session) {
+        let imp = self.imp();
+
+        for (custom_emoji, id) in imp
+            .custom_emojis
+            .borrow()
+            .iter()
+            .zip(imp.custom_emoji_ids.borrow().iter())
+        {
+            let sticker = stickers.iter().find(|sticker| {
+                matches!(
+                    &sticker.full_type,
+                    StickerFullType::CustomEmoji(data) if data.custom_emoji_id == *id
+                )
+            });
+
+            if let Some(sticker) = sticker {
+                custom_emoji.update_sticker(sticker.clone(), true, session.clone());
+            }
+        }
+    }
+
+    pub(crate) fn set_session(&self, session: &Session) {
+        let imp = self.imp();
+
+        if imp.session.upgrade().as_ref() == Some(session) {
+            return;
+        }
+
+        imp.session.set(Some(session));
+        self.load_custom_emojis();
     }
 
     pub(crate) fn label(&self) -> String {
         self.imp().text.borrow().clone()
     }
 
+    /// Sets the markup of the label.
     pub(crate) fn set_label(&self, label: String) {
+        self.imp().formatted_text.replace(None);
+        self.set_markup(label);
+    }
+
+    /// Sets a formatted text as the content of the label, rendering its custom emoji and
+    /// obscuring its spoilers.
+    pub(crate) fn set_formatted_text(&self, formatted_text: FormattedText) {
+        let imp = self.imp();
+
+        if imp.formatted_text.borrow().as_ref() == Some(&formatted_text) {
+            return;
+        }
+
+        let markup = parse_formatted_text_with_style(
+            formatted_text.clone(),
+            MarkupStyle::MessageLabel {
+                spoilers_revealed: false,
+            },
+        );
+        imp.formatted_text.replace(Some(formatted_text));
+        self.set_markup(markup);
+    }
+
+    fn set_markup(&self, markup: String) {
         let imp = self.imp();
-        let old = imp.text.replace(label);
+        let old = imp.text.replace(markup);
         if old != *imp.text.borrow() {
+            imp.spoilers_revealed.set(false);
             self.update_label();
             self.notify("label");
         }
@@ -239,3 +479,10 @@ impl MessageLabel {
         }
     }
 }
+
+fn placeholder_shape(logical_rect: &pango::Rectangle, start_index: usize) -> pango::AttrShape {
+    let mut shape = pango::AttrShape::new(logical_rect, logical_rect);
+    shape.set_start_index(start_index as u32);
+    shape.set_end_index((start_index + OBJECT_REPLACEMENT_CHARACTER.len_utf8()) as u32);
+    shape
+}
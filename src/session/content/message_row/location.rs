@@ -0,0 +1,320 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib, CompositeTemplate};
+use tdlib::enums::MessageContent;
+use tdlib::types::Location;
+
+use crate::i18n::gettext_f;
+use crate::session::content::message_row::{MessageBase, MessageBaseImpl, MessageBubble};
+use crate::tdlib::Message;
+use crate::utils::format_duration;
+
+use super::base::MessageBaseExt;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageLocation : $MessageBase {
+        $MessageBubble message_bubble {
+            styles ["location"]
+
+            prefix: Box {
+                spacing: 9;
+
+                styles ["file"]
+
+                Button open_button {
+                    valign: center;
+                    icon-name: "mark-location-symbolic";
+                    action-name: "message-location.open";
+
+                    styles ["circular", "location-button"]
+                }
+
+                Box {
+                    orientation: vertical;
+                    valign: center;
+                    hexpand: true;
+
+                    Label title_label {
+                        xalign: 0;
+                        ellipsize: end;
+
+                        styles ["heading"]
+                    }
+
+                    Label subtitle_label {
+                        xalign: 0;
+                        wrap: true;
+                        wrap-mode: word_char;
+                        selectable: true;
+
+                        styles ["caption"]
+                    }
+
+                    Label live_label {
+                        xalign: 0;
+                        visible: false;
+
+                        styles ["numeric", "dim-label", "caption"]
+                    }
+                }
+            };
+        }
+    }
+    "#)]
+    pub(crate) struct MessageLocation {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) geo_uri: RefCell<Option<String>>,
+        /// The unix time at which the sharing of a live location ends
+        pub(super) expiration_date: Cell<i64>,
+        pub(super) countdown_source_id: RefCell<Option<glib::SourceId>>,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<MessageBubble>,
+        #[template_child]
+        pub(super) open_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) subtitle_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) live_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageLocation {
+        const NAME: &'static str = "MessageLocation";
+        type Type = super::MessageLocation;
+        type ParentType = MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+
+            klass.install_action("message-location.open", None, move |widget, _, _| {
+                widget.open_location();
+            });
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageLocation {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.open_button
+                .set_tooltip_text(Some(&gettext("Open in Maps")));
+        }
+
+        fn dispose(&self) {
+            self.obj().stop_countdown();
+        }
+    }
+
+    impl WidgetImpl for MessageLocation {}
+    impl MessageBaseImpl for MessageLocation {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageLocation(ObjectSubclass<imp::MessageLocation>)
+        @extends gtk::Widget, MessageBase;
+}
+
+impl MessageBaseExt for MessageLocation {
+    type Message = Message;
+
+    fn set_message(&self, message: Self::Message) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == Some(&message) {
+            return;
+        }
+
+        if let Some(old_message) = imp.message.take() {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.message_bubble.update_from_message(&message, false);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message, _| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+        self.update_content(&message);
+
+        imp.message.replace(Some(message));
+        self.notify("message");
+    }
+}
+
+impl MessageLocation {
+    fn update_content(&self, message: &Message) {
+        let imp = self.imp();
+
+        self.stop_countdown();
+
+        match message.content().0 {
+            MessageContent::MessageLocation(data) => {
+                let is_live = data.live_period > 0;
+
+                imp.title_label.set_label(&if is_live {
+                    gettext("Live Location")
+                } else {
+                    gettext("Location")
+                });
+                imp.subtitle_label
+                    .set_label(&format_coordinates(&data.location));
+                imp.live_label.set_visible(is_live);
+
+                if is_live {
+                    // The live period counts from the date the location was sent, so
+                    // `expires_in` would be stale for messages received some time ago
+                    imp.expiration_date
+                        .set(message.date() as i64 + data.live_period as i64);
+                    self.update_live_label();
+
+                    if self.expires_in() > 0 {
+                        self.start_countdown();
+                    }
+                }
+
+                imp.geo_uri.replace(Some(geo_uri(&data.location, None)));
+            }
+            MessageContent::MessageVenue(data) => {
+                let venue = data.venue;
+
+                imp.title_label.set_label(&venue.title);
+                imp.subtitle_label.set_label(&if venue.address.is_empty() {
+                    format_coordinates(&venue.location)
+                } else {
+                    venue.address
+                });
+                imp.live_label.set_visible(false);
+
+                imp.geo_uri
+                    .replace(Some(geo_uri(&venue.location, Some(&venue.title))));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn start_countdown(&self) {
+        let source_id = glib::timeout_add_seconds_local(
+            1,
+            clone!(@weak self as obj => @default-return glib::Continue(false), move || {
+                obj.update_live_label();
+
+                if obj.expires_in() == 0 {
+                    obj.imp().countdown_source_id.take();
+                    glib::Continue(false)
+                } else {
+                    glib::Continue(true)
+                }
+            }),
+        );
+        self.imp().countdown_source_id.replace(Some(source_id));
+    }
+
+    fn stop_countdown(&self) {
+        if let Some(source_id) = self.imp().countdown_source_id.take() {
+            source_id.remove();
+        }
+    }
+
+    fn update_live_label(&self) {
+        let imp = self.imp();
+        let expires_in = self.expires_in();
+
+        imp.live_label.set_label(&if expires_in > 0 {
+            gettext_f("{time} left", &[("time", &format_duration(expires_in))])
+        } else {
+            gettext("Location sharing ended")
+        });
+    }
+
+    /// Returns the seconds left until the sharing of the live location ends.
+    fn expires_in(&self) -> i64 {
+        let now = glib::DateTime::now_utc().unwrap().to_unix();
+        (self.imp().expiration_date.get() - now).max(0)
+    }
+
+    fn open_location(&self) {
+        if let Some(uri) = self.imp().geo_uri.borrow().as_ref() {
+            if let Err(e) = gio::AppInfo::launch_default_for_uri(uri, gio::AppLaunchContext::NONE) {
+                log::warn!("Error opening a location: {e:?}");
+            }
+        }
+    }
+}
+
+fn format_coordinates(location: &Location) -> String {
+    let latitude_direction = if location.latitude >= 0.0 {
+        gettext("N")
+    } else {
+        gettext("S")
+    };
+    let longitude_direction = if location.longitude >= 0.0 {
+        gettext("E")
+    } else {
+        gettext("W")
+    };
+
+    format!(
+        "{:.5}° {}, {:.5}° {}",
+        location.latitude.abs(),
+        latitude_direction,
+        location.longitude.abs(),
+        longitude_direction
+    )
+}
+
+/// Creates a `geo:` URI (RFC 5870) for the location, with an optional label for the map
+/// application.
+fn geo_uri(location: &Location, label: Option<&str>) -> String {
+    let coordinates = format!("{},{}", location.latitude, location.longitude);
+
+    match label {
+        Some(label) if !label.is_empty() => format!(
+            "geo:{coordinates}?q={coordinates}({})",
+            glib::Uri::escape_string(label, None, false)
+        ),
+        _ => format!("geo:{coordinates}"),
+    }
+}
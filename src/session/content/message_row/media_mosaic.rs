@@ -0,0 +1,196 @@
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, graphene, gsk};
+use std::ops::Range;
+
+const MAX_WIDTH: i32 = 400;
+const MAX_ROW_HEIGHT: i32 = 250;
+const MAX_ITEMS_PER_ROW: usize = 3;
+const SPACING: i32 = 2;
+
+mod imp {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct MediaMosaic {
+        /// The children and their aspect ratios
+        pub(super) children: RefCell<Vec<(gtk::Widget, f64)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MediaMosaic {
+        const NAME: &'static str = "MessageMediaMosaic";
+        type Type = super::MediaMosaic;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("mediamosaic");
+        }
+    }
+
+    impl ObjectImpl for MediaMosaic {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().set_overflow(gtk::Overflow::Hidden);
+        }
+
+        fn dispose(&self) {
+            for (child, _) in self.children.take() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for MediaMosaic {
+        fn measure(&self, orientation: gtk::Orientation, for_size: i32) -> (i32, i32, i32, i32) {
+            let children = self.children.borrow();
+
+            if let gtk::Orientation::Horizontal = orientation {
+                let minimum = rows(children.len())
+                    .map(|row| {
+                        children[row.clone()]
+                            .iter()
+                            .map(|(child, _)| child.measure(orientation, -1).0)
+                            .sum::<i32>()
+                            + SPACING * (row.len() as i32 - 1)
+                    })
+                    .max()
+                    .unwrap_or_default();
+
+                (minimum, MAX_WIDTH.max(minimum), -1, -1)
+            } else {
+                let width = if for_size < 0 { MAX_WIDTH } else { for_size };
+                let height = rows(children.len())
+                    .map(|row| row_height(&children[row], width))
+                    .sum::<i32>()
+                    + SPACING * (rows(children.len()).count() as i32 - 1).max(0);
+
+                (height, height, -1, -1)
+            }
+        }
+
+        fn size_allocate(&self, width: i32, height: i32, _baseline: i32) {
+            let children = self.children.borrow();
+            let rows: Vec<Range<usize>> = rows(children.len()).collect();
+            let mut y = 0;
+
+            for (row_index, row) in rows.iter().enumerate() {
+                let row_children = &children[row.clone()];
+                let row_height = if row_index == rows.len() - 1 {
+                    height - y
+                } else {
+                    row_height(row_children, width)
+                };
+                let available_width = width - SPACING * (row_children.len() as i32 - 1);
+                let ratio_sum: f64 = row_children.iter().map(|(_, r)| clamp_ratio(*r)).sum();
+                let mut x = 0;
+
+                for (index, (child, ratio)) in row_children.iter().enumerate() {
+                    // Let the last child fill the remaining space to avoid rounding gaps
+                    let child_width = if index == row_children.len() - 1 {
+                        width - x
+                    } else {
+                        (available_width as f64 * clamp_ratio(*ratio) / ratio_sum) as i32
+                    };
+
+                    child.measure(gtk::Orientation::Horizontal, row_height);
+                    child.allocate(
+                        child_width,
+                        row_height,
+                        -1,
+                        Some(
+                            gsk::Transform::new()
+                                .translate(&graphene::Point::new(x as f32, y as f32)),
+                        ),
+                    );
+
+                    x += child_width + SPACING;
+                }
+
+                y += row_height + SPACING;
+            }
+        }
+
+        fn request_mode(&self) -> gtk::SizeRequestMode {
+            gtk::SizeRequestMode::HeightForWidth
+        }
+    }
+}
+
+glib::wrapper! {
+    pub(crate) struct MediaMosaic(ObjectSubclass<imp::MediaMosaic>)
+        @extends gtk::Widget;
+}
+
+impl Default for MediaMosaic {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl MediaMosaic {
+    /// Appends a child, which is laid out in the grid according to its aspect ratio.
+    pub(crate) fn append(&self, child: &impl IsA<gtk::Widget>, aspect_ratio: f64) {
+        child.set_parent(self);
+        self.imp()
+            .children
+            .borrow_mut()
+            .push((child.clone().upcast(), aspect_ratio));
+        self.queue_resize();
+    }
+
+    /// Replaces the child at the given position, keeping the other children in place.
+    pub(crate) fn replace(
+        &self,
+        position: usize,
+        child: &impl IsA<gtk::Widget>,
+        aspect_ratio: f64,
+    ) {
+        let (old_child, _) = std::mem::replace(
+            &mut self.imp().children.borrow_mut()[position],
+            (child.clone().upcast(), aspect_ratio),
+        );
+        child.insert_after(self, Some(&old_child));
+        old_child.unparent();
+
+        self.queue_resize();
+    }
+
+    pub(crate) fn remove_all(&self) {
+        for (child, _) in self.imp().children.take() {
+            child.unparent();
+        }
+        self.queue_resize();
+    }
+}
+
+/// Splits the children into rows of at most `MAX_ITEMS_PER_ROW` children, placing the fuller
+/// rows at the bottom.
+fn rows(n_children: usize) -> impl Iterator<Item = Range<usize>> {
+    let n_rows = n_children.div_ceil(MAX_ITEMS_PER_ROW);
+    let mut start = 0;
+
+    (0..n_rows).map(move |row| {
+        let len = n_children / n_rows + usize::from(row >= n_rows - n_children % n_rows);
+        let range = start..start + len;
+        start += len;
+        range
+    })
+}
+
+fn row_height(row_children: &[(gtk::Widget, f64)], width: i32) -> i32 {
+    let available_width = width - SPACING * (row_children.len() as i32 - 1);
+    let ratio_sum: f64 = row_children.iter().map(|(_, r)| clamp_ratio(*r)).sum();
+
+    ((available_width as f64 / ratio_sum) as i32).min(MAX_ROW_HEIGHT)
+}
+
+/// Avoids too thin or too wide tiles.
+fn clamp_ratio(aspect_ratio: f64) -> f64 {
+    if aspect_ratio.is_finite() {
+        aspect_ratio.clamp(0.5, 2.0)
+    } else {
+        1.0
+    }
+}
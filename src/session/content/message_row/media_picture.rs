@@ -1,7 +1,7 @@
 use glib::clone;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use gtk::{gdk, glib, CompositeTemplate};
+use gtk::{gdk, glib, graphene, gsk, CompositeTemplate};
 
 const MAX_HEIGHT: i32 = 350;
 
@@ -16,12 +16,22 @@ mod imp {
         Picture picture {
             content-fit: cover;
         }
+
+        Image spoiler_image {
+            icon-name: "eye-not-looking-symbolic";
+            visible: false;
+
+            styles ["osd-indicator", "spoiler-icon"]
+        }
     }
     "#)]
     pub(crate) struct MediaPicture {
         pub(super) aspect_ratio: Cell<f64>,
+        pub(super) has_spoiler: Cell<bool>,
         #[template_child]
         pub(super) picture: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub(super) spoiler_image: TemplateChild<gtk::Image>,
     }
 
     #[glib::object_subclass]
@@ -50,6 +60,9 @@ mod imp {
                     glib::ParamSpecDouble::builder("aspect-ratio")
                         .explicit_notify()
                         .build(),
+                    glib::ParamSpecBoolean::builder("has-spoiler")
+                        .explicit_notify()
+                        .build(),
                 ]
             });
             PROPERTIES.as_ref()
@@ -61,6 +74,7 @@ mod imp {
             match pspec.name() {
                 "paintable" => obj.set_paintable(value.get::<Option<&gdk::Paintable>>().unwrap()),
                 "aspect-ratio" => obj.set_aspect_ratio(value.get().unwrap()),
+                "has-spoiler" => obj.set_has_spoiler(value.get().unwrap()),
                 _ => unimplemented!(),
             }
         }
@@ -71,6 +85,7 @@ mod imp {
             match pspec.name() {
                 "paintable" => obj.paintable().to_value(),
                 "aspect-ratio" => obj.aspect_ratio().to_value(),
+                "has-spoiler" => obj.has_spoiler().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -85,10 +100,21 @@ mod imp {
                 .connect_paintable_notify(clone!(@weak obj => move |_| {
                     obj.notify("paintable");
                 }));
+
+            // Reveal the spoiler on click, without letting the click reach other widgets
+            let click = gtk::GestureClick::new();
+            click.connect_released(clone!(@weak obj => move |click, _, _, _| {
+                if obj.has_spoiler() {
+                    obj.set_has_spoiler(false);
+                    click.set_state(gtk::EventSequenceState::Claimed);
+                }
+            }));
+            obj.add_controller(click);
         }
 
         fn dispose(&self) {
             self.picture.unparent();
+            self.spoiler_image.unparent();
         }
     }
 
@@ -120,6 +146,20 @@ mod imp {
 
         fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
             self.picture.allocate(width, height, baseline, None);
+
+            if self.spoiler_image.is_visible() {
+                let (_, natural_size) = self.spoiler_image.preferred_size();
+                let transform = gsk::Transform::new().translate(&graphene::Point::new(
+                    ((width - natural_size.width()) / 2) as f32,
+                    ((height - natural_size.height()) / 2) as f32,
+                ));
+                self.spoiler_image.allocate(
+                    natural_size.width(),
+                    natural_size.height(),
+                    -1,
+                    Some(transform),
+                );
+            }
         }
 
         fn request_mode(&self) -> gtk::SizeRequestMode {
@@ -156,4 +196,29 @@ impl MediaPicture {
 
         self.notify("aspect-ratio");
     }
+
+    pub(crate) fn has_spoiler(&self) -> bool {
+        self.imp().has_spoiler.get()
+    }
+
+    /// Sets whether the media is hidden behind a spoiler, which is revealed on click.
+    pub(crate) fn set_has_spoiler(&self, has_spoiler: bool) {
+        if self.has_spoiler() == has_spoiler {
+            return;
+        }
+
+        let imp = self.imp();
+        imp.has_spoiler.set(has_spoiler);
+        imp.spoiler_image.set_visible(has_spoiler);
+
+        if has_spoiler {
+            self.add_css_class("spoiler");
+            self.set_cursor_from_name(Some("pointer"));
+        } else {
+            self.remove_css_class("spoiler");
+            self.set_cursor(None);
+        }
+
+        self.notify("has-spoiler");
+    }
 }
@@ -1,26 +1,57 @@
+mod album;
+mod audio;
+mod audio_playback;
 mod base;
 mod bubble;
+mod code_block;
+mod contact;
 mod document;
 mod indicators;
 mod label;
+mod location;
+mod media_mosaic;
 mod media_picture;
 mod photo;
+mod poll;
+mod progress_ring;
+mod reaction_picker;
+mod reactions;
 mod reply;
+mod reply_markup;
 mod sticker;
 mod text;
 mod video;
+mod video_note;
+mod voice_note;
+mod waveform;
+mod web_page;
 
+use self::album::MessageAlbum;
+use self::audio::MessageAudio;
 use self::base::{MessageBase, MessageBaseExt, MessageBaseImpl};
 use self::bubble::MessageBubble;
+use self::code_block::MessageCodeBlock;
+use self::contact::MessageContact;
 use self::document::MessageDocument;
 use self::indicators::MessageIndicators;
 use self::label::MessageLabel;
+use self::location::MessageLocation;
+use self::media_mosaic::MediaMosaic;
 use self::media_picture::MediaPicture;
 use self::photo::MessagePhoto;
+use self::poll::MessagePoll;
+use self::progress_ring::MessageProgressRing;
+pub(crate) use self::reaction_picker::MessageReactionPicker;
+use self::reactions::MessageReactions;
 use self::reply::MessageReply;
+use self::reply_markup::MessageReplyMarkup;
 use self::sticker::MessageSticker;
 use self::text::MessageText;
 use self::video::MessageVideo;
+use self::video_note::MessageVideoNote;
+use self::voice_note::MessageVoiceNote;
+use self::waveform::MessageWaveform;
+use self::web_page::MessageWebPage;
 
 use adw::prelude::*;
 use gettextrs::gettext;
@@ -30,6 +61,7 @@ use gtk::{gio, glib, CompositeTemplate};
 use tdlib::enums::{MessageContent, StickerFormat};
 
 use crate::components::Avatar;
+use crate::session::content::ForwardMessagesDialog;
 use crate::tdlib::{Chat, ChatType, Message, MessageForwardOrigin, MessageSender};
 use crate::utils::spawn;
 
@@ -39,6 +71,7 @@ const SPACING: i32 = 6;
 mod imp {
     use super::*;
     use once_cell::sync::Lazy;
+    use once_cell::unsync::OnceCell;
     use std::cell::RefCell;
 
     #[derive(Debug, Default, CompositeTemplate)]
@@ -48,13 +81,31 @@ mod imp {
             button: 1;
             released => $on_released() swapped;
         }
+
+        Box content_box {
+            orientation: vertical;
+            spacing: 3;
+            hexpand: true;
+            valign: start;
+
+            $MessageReplyMarkup reply_markup {
+                visible: false;
+            }
+        }
     }
     "#)]
     pub(crate) struct MessageRow {
         /// A `Message` or `SponsoredMessage`
         pub(super) message: RefCell<Option<glib::Object>>,
+        /// The messages of the media album shown by this row, if any
+        pub(super) album: RefCell<Vec<Message>>,
         pub(super) content: RefCell<Option<gtk::Widget>>,
         pub(super) avatar: RefCell<Option<Avatar>>,
+        pub(super) message_signal_group: OnceCell<glib::SignalGroup>,
+        #[template_child]
+        pub(super) content_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) reply_markup: TemplateChild<MessageReplyMarkup>,
     }
 
     #[glib::object_subclass]
@@ -70,7 +121,24 @@ mod imp {
             klass.install_action("message-row.reply", None, move |widget, _, _| {
                 widget.reply()
             });
+            klass.install_action("message-row.forward", None, move |widget, _, _| {
+                widget.forward()
+            });
             klass.install_action("message-row.edit", None, move |widget, _, _| widget.edit());
+            klass.install_action("message-row.select", None, move |widget, _, _| {
+                widget.select()
+            });
+            klass.install_action(
+                "message-row.search-from-sender",
+                None,
+                move |widget, _, _| widget.search_from_sender(),
+            );
+            klass.install_action("message-row.pin", None, move |widget, _, _| {
+                widget.toggle_is_pinned()
+            });
+            klass.install_action("message-row.unpin", None, move |widget, _, _| {
+                widget.toggle_is_pinned()
+            });
             klass.install_action("message-row.revoke-delete", None, move |widget, _, _| {
                 widget.show_delete_dialog(true)
             });
@@ -112,14 +180,27 @@ mod imp {
             }
         }
 
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let message_signal_group = glib::SignalGroup::new(Message::static_type());
+            message_signal_group.connect_notify_local(
+                Some("is-pinned"),
+                clone!(@weak obj => move |_, _| {
+                    obj.update_actions();
+                }),
+            );
+            self.message_signal_group.set(message_signal_group).unwrap();
+        }
+
         fn dispose(&self) {
             if let Some(avatar) = self.avatar.borrow().as_ref() {
                 avatar.unparent();
             }
 
-            if let Some(content) = self.content.borrow().as_ref() {
-                content.unparent();
-            }
+            self.content_box.unparent();
         }
     }
 
@@ -155,6 +236,11 @@ impl MessageRow {
         }
     }
 
+    fn forward(&self) {
+        let window = self.root().and_then(|root| root.downcast().ok());
+        ForwardMessagesDialog::new(&window, self.messages()).present();
+    }
+
     fn edit(&self) {
         if let Ok(message) = self.message().downcast::<Message>() {
             self.activate_action("chat-history.edit", Some(&message.id().to_variant()))
@@ -162,13 +248,52 @@ impl MessageRow {
         }
     }
 
+    /// Pins or unpins the message. Albums are pinned through their first message.
+    fn toggle_is_pinned(&self) {
+        if let Some(message) = self.messages().into_iter().next() {
+            spawn(async move {
+                let result = if message.is_pinned() {
+                    message.unpin().await
+                } else {
+                    message.pin(false).await
+                };
+
+                if let Err(e) = result {
+                    log::warn!("Error toggling the pinned state of a message: {e:?}");
+                }
+            });
+        }
+    }
+
+    fn select(&self) {
+        if let Ok(message) = self.message().downcast::<Message>() {
+            self.activate_action(
+                "chat-history.select-message",
+                Some(&message.id().to_variant()),
+            )
+            .unwrap();
+        }
+    }
+
+    fn search_from_sender(&self) {
+        if let Ok(message) = self.message().downcast::<Message>() {
+            self.activate_action(
+                "chat-history.search-from-sender",
+                Some(&message.id().to_variant()),
+            )
+            .unwrap();
+        }
+    }
+
     fn show_delete_dialog(&self, revoke: bool) {
         let window: gtk::Window = self.root().and_then(|root| root.downcast().ok()).unwrap();
+        let is_album = !self.imp().album.borrow().is_empty();
 
-        let message = if revoke {
-            gettext("Do you want to delete this message for <b>everyone</b>?")
-        } else {
-            gettext("Do you want to delete this message?")
+        let message = match (is_album, revoke) {
+            (false, true) => gettext("Do you want to delete this message for <b>everyone</b>?"),
+            (false, false) => gettext("Do you want to delete this message?"),
+            (true, true) => gettext("Do you want to delete these messages for <b>everyone</b>?"),
+            (true, false) => gettext("Do you want to delete these messages?"),
         };
 
         let dialog = adw::MessageDialog::builder()
@@ -186,12 +311,23 @@ impl MessageRow {
             gio::Cancellable::NONE,
             clone!(@weak self as obj => move |response| {
                 if response == "yes" {
-                    if let Ok(message) = obj.message().downcast::<Message>() {
+                    let messages = obj.messages();
+
+                    if let [message] = messages.as_slice() {
+                        let message = message.clone();
                         spawn(async move {
                             if let Err(e) = message.delete(revoke).await {
                                 log::warn!("Error deleting a message (revoke = {}): {:?}", revoke, e);
                             }
                         });
+                    } else if let Some(message) = messages.first() {
+                        let chat = message.chat();
+                        let message_ids = messages.iter().map(Message::id).collect();
+                        spawn(async move {
+                            if let Err(e) = chat.delete_messages(message_ids, revoke).await {
+                                log::warn!("Error deleting an album (revoke = {}): {:?}", revoke, e);
+                            }
+                        });
                     }
                 }
             }));
@@ -201,13 +337,37 @@ impl MessageRow {
         self.imp().message.borrow().clone().unwrap()
     }
 
+    /// Returns the messages the actions of this row apply to: the messages of the album, or
+    /// the message itself.
+    fn messages(&self) -> Vec<Message> {
+        let album = self.imp().album.borrow();
+
+        if !album.is_empty() {
+            album.clone()
+        } else {
+            self.message().downcast::<Message>().into_iter().collect()
+        }
+    }
+
     pub(crate) fn set_message(&self, message: glib::Object) {
+        self.set_message_and_album(message, Vec::new());
+    }
+
+    /// Shows a media album, represented by its first message.
+    pub(crate) fn set_album(&self, messages: Vec<Message>) {
+        let message = messages[0].clone().upcast();
+        self.set_message_and_album(message, messages);
+    }
+
+    fn set_message_and_album(&self, message: glib::Object, album: Vec<Message>) {
         let imp = self.imp();
 
-        if imp.message.borrow().as_ref() == Some(&message) {
+        if imp.message.borrow().as_ref() == Some(&message) && *imp.album.borrow() == album {
             return;
         }
 
+        imp.album.replace(album);
+
         if let Some(message) = message.downcast_ref::<Message>() {
             let show_avatar = if message.is_outgoing() {
                 false
@@ -273,6 +433,12 @@ impl MessageRow {
         }
 
         self.update_content(message.clone());
+        imp.reply_markup
+            .set_message(message.downcast_ref::<Message>());
+        imp.message_signal_group
+            .get()
+            .unwrap()
+            .set_target(message.downcast_ref::<Message>());
 
         imp.message.replace(Some(message));
 
@@ -292,10 +458,19 @@ impl MessageRow {
 
     fn can_edit_message(&self) -> bool {
         if let Some(message) = self.message().downcast_ref::<Message>() {
-            let is_text_message = matches!(message.content().0, MessageContent::MessageText(_));
+            // Only the text of these messages, or their caption, can be edited
+            let is_editable_type = matches!(
+                message.content().0,
+                MessageContent::MessageText(_)
+                    | MessageContent::MessagePhoto(_)
+                    | MessageContent::MessageVideo(_)
+                    | MessageContent::MessageDocument(_)
+                    | MessageContent::MessageAudio(_)
+            );
 
-            // TODO: Support more message types in the future
-            is_text_message && message.can_be_edited() && can_send_messages_in_chat(&message.chat())
+            is_editable_type
+                && message.can_be_edited()
+                && can_send_messages_in_chat(&message.chat())
         } else {
             false
         }
@@ -304,17 +479,42 @@ impl MessageRow {
     fn update_actions(&self) {
         self.action_set_enabled("message-row.reply", self.can_reply_to_message());
         self.action_set_enabled("message-row.edit", self.can_edit_message());
+        self.action_set_enabled(
+            "message-row.select",
+            self.message().downcast_ref::<Message>().is_some(),
+        );
+
+        let messages = self.messages();
+        self.action_set_enabled(
+            "message-row.forward",
+            !messages.is_empty() && messages.iter().all(Message::can_be_forwarded),
+        );
+
+        // Searching by sender is only useful in chats with several senders
+        let can_search_from_sender = match messages.first().map(|m| m.chat().type_()) {
+            Some(ChatType::BasicGroup(_)) => true,
+            Some(ChatType::Supergroup(data)) => !data.is_channel(),
+            _ => false,
+        };
+        self.action_set_enabled("message-row.search-from-sender", can_search_from_sender);
 
-        if let Some(message) = self.message().downcast_ref::<Message>() {
-            self.action_set_enabled("message-row.delete", message.can_be_deleted_only_for_self());
-            self.action_set_enabled(
-                "message-row.revoke-delete",
-                message.can_be_deleted_for_all_users(),
-            );
-        } else {
-            self.action_set_enabled("message-row.delete", false);
-            self.action_set_enabled("message-row.revoke-delete", false);
-        }
+        let (can_pin, can_unpin) = match messages.first() {
+            Some(message) if message.chat().can_pin_messages() => {
+                (!message.is_pinned(), message.is_pinned())
+            }
+            _ => (false, false),
+        };
+        self.action_set_enabled("message-row.pin", can_pin);
+        self.action_set_enabled("message-row.unpin", can_unpin);
+
+        self.action_set_enabled(
+            "message-row.delete",
+            !messages.is_empty() && messages.iter().all(Message::can_be_deleted_only_for_self),
+        );
+        self.action_set_enabled(
+            "message-row.revoke-delete",
+            !messages.is_empty() && messages.iter().all(Message::can_be_deleted_for_all_users),
+        );
     }
 
     fn update_content(&self, message: glib::Object) {
@@ -325,12 +525,29 @@ impl MessageRow {
                 _ => message_.is_outgoing(),
             };
 
+            let album = self.imp().album.borrow().clone();
+
             match message_.content().0 {
-                // FIXME: Re-enable MessageVideo when
-                // https://github.com/paper-plane-developers/paper-plane/issues/410 is fixed
-                MessageContent::MessageAnimation(_) /*| MessageContent::MessageVideo(_)*/ => {
+                _ if !album.is_empty() => {
+                    self.update_specific_content::<_, MessageAlbum>(message_.clone());
+
+                    let content_ref = self.imp().content.borrow();
+                    let content = content_ref.as_ref().unwrap();
+                    content
+                        .downcast_ref::<MessageAlbum>()
+                        .unwrap()
+                        .set_album(album);
+                }
+                // Videos were disabled because of
+                // https://github.com/paper-plane-developers/paper-plane/issues/410. Unlike
+                // animations, they are no longer downloaded and played automatically: they are
+                // only streamed after clicking the play button, and the playback is stopped
+                // when the row is reused for another message. The videos of albums and of the
+                // media viewer follow the same rules.
+                MessageContent::MessageAnimation(_) | MessageContent::MessageVideo(_) => {
                     self.update_specific_content::<_, MessageVideo>(message_.clone());
                 }
+                #[rustfmt::skip]
                 MessageContent::MessageAnimatedEmoji(data)
                     if data.animated_emoji.sticker.clone().map(
                         |s| matches!(s.format, StickerFormat::Webp | StickerFormat::Tgs)
@@ -340,6 +557,7 @@ impl MessageRow {
                 MessageContent::MessagePhoto(_) => {
                     self.update_specific_content::<_, MessagePhoto>(message_.clone());
                 }
+                #[rustfmt::skip]
                 MessageContent::MessageSticker(data)
                     if matches!(data.sticker.format, StickerFormat::Webp | StickerFormat::Tgs) =>
                 {
@@ -348,6 +566,24 @@ impl MessageRow {
                 MessageContent::MessageDocument(_) => {
                     self.update_specific_content::<_, MessageDocument>(message_.clone());
                 }
+                MessageContent::MessageAudio(_) => {
+                    self.update_specific_content::<_, MessageAudio>(message_.clone());
+                }
+                MessageContent::MessageVoiceNote(_) => {
+                    self.update_specific_content::<_, MessageVoiceNote>(message_.clone());
+                }
+                MessageContent::MessageVideoNote(_) => {
+                    self.update_specific_content::<_, MessageVideoNote>(message_.clone());
+                }
+                MessageContent::MessagePoll(_) => {
+                    self.update_specific_content::<_, MessagePoll>(message_.clone());
+                }
+                MessageContent::MessageLocation(_) | MessageContent::MessageVenue(_) => {
+                    self.update_specific_content::<_, MessageLocation>(message_.clone());
+                }
+                MessageContent::MessageContact(_) => {
+                    self.update_specific_content::<_, MessageContact>(message_.clone());
+                }
                 _ => {
                     self.update_specific_content::<_, MessageText>(message);
                 }
@@ -359,13 +595,13 @@ impl MessageRow {
             false
         };
 
-        let content_ref = self.imp().content.borrow();
-        let content = content_ref.as_ref().unwrap();
+        // Align the content box, so that the reply markup has the same width as the content
+        let content_box = &*self.imp().content_box;
 
         if is_outgoing {
-            content.set_halign(gtk::Align::End);
+            content_box.set_halign(gtk::Align::End);
         } else {
-            content.set_halign(gtk::Align::Start);
+            content_box.set_halign(gtk::Align::Start);
         }
     }
 
@@ -373,22 +609,21 @@ impl MessageRow {
     where
         B: MessageBaseExt<Message = M>,
     {
-        let mut content_ref = self.imp().content.borrow_mut();
+        let imp = self.imp();
+        let mut content_ref = imp.content.borrow_mut();
         match content_ref.as_ref().and_then(|c| c.downcast_ref::<B>()) {
             Some(content) => {
                 content.set_message(message);
             }
             None => {
                 if let Some(old_content) = &*content_ref {
-                    old_content.unparent();
+                    imp.content_box.remove(old_content);
                 }
 
                 let content = B::new(&message);
-                content.set_hexpand(true);
-                content.set_valign(gtk::Align::Start);
 
-                // Insert at the end
-                content.insert_before(self, gtk::Widget::NONE);
+                // Insert above the reply markup
+                imp.content_box.prepend(&content);
 
                 *content_ref = Some(content.upcast());
             }
@@ -396,7 +631,7 @@ impl MessageRow {
     }
 }
 
-fn can_send_messages_in_chat(chat: &Chat) -> bool {
+pub(crate) fn can_send_messages_in_chat(chat: &Chat) -> bool {
     use tdlib::enums::ChatMemberStatus::*;
     let member_status = match chat.type_() {
         ChatType::Supergroup(supergroup) => Some(supergroup.status()),
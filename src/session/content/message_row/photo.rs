@@ -7,8 +7,9 @@ use tdlib::enums::MessageContent;
 use crate::session::content::message_row::{
     MediaPicture, MessageBase, MessageBaseImpl, MessageBubble,
 };
-use crate::tdlib::{BoxedMessageContent, Message};
-use crate::utils::{decode_image_from_path, parse_formatted_text, spawn};
+use crate::session::content::MediaViewer;
+use crate::tdlib::{BoxedFormattedText, BoxedMessageContent, Message};
+use crate::utils::{decode_image_from_path, spawn};
 use crate::Session;
 
 use super::base::MessageBaseExt;
@@ -74,9 +75,23 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
 
-            self.obj().connect_scale_factor_notify(|obj| {
+            let obj = self.obj();
+
+            obj.connect_scale_factor_notify(|obj| {
                 obj.update_photo(obj.imp().message.borrow().as_ref().unwrap());
             });
+
+            // Open the media viewer on click, unless the click reveals a spoiler
+            let click = gtk::GestureClick::new();
+            click.connect_pressed(clone!(@weak obj => move |click, _, _, _| {
+                if obj.imp().picture.has_spoiler() {
+                    click.set_state(gtk::EventSequenceState::Denied);
+                }
+            }));
+            click.connect_released(clone!(@weak obj => move |_, _, _, _| {
+                obj.open_media_viewer();
+            }));
+            self.picture.add_controller(click);
         }
     }
 
@@ -117,14 +132,16 @@ impl MessageBaseExt for MessagePhoto {
 
         // Setup caption expression
         let caption_binding = Message::this_expression("content")
-            .chain_closure::<String>(closure!(|_: Message, content: BoxedMessageContent| {
-                if let MessageContent::MessagePhoto(data) = content.0 {
-                    parse_formatted_text(data.caption)
-                } else {
-                    unreachable!();
+            .chain_closure::<BoxedFormattedText>(closure!(
+                |_: Message, content: BoxedMessageContent| {
+                    if let MessageContent::MessagePhoto(data) = content.0 {
+                        BoxedFormattedText(data.caption)
+                    } else {
+                        unreachable!();
+                    }
                 }
-            }))
-            .bind(&*imp.message_bubble, "label", Some(message));
+            ))
+            .bind(&*imp.message_bubble, "formatted-text", Some(message));
         imp.binding.replace(Some(caption_binding));
 
         // Load photo
@@ -140,6 +157,11 @@ impl MessageBaseExt for MessagePhoto {
 }
 
 impl MessagePhoto {
+    fn open_media_viewer(&self) {
+        let window = self.root().and_downcast::<gtk::Window>();
+        MediaViewer::new(&window, &self.message()).present();
+    }
+
     fn update_photo(&self, message: &Message) {
         if let MessageContent::MessagePhoto(mut data) = message.content().0 {
             let imp = self.imp();
@@ -159,6 +181,7 @@ impl MessagePhoto {
 
             imp.picture
                 .set_aspect_ratio(photo_size.width as f64 / photo_size.height as f64);
+            imp.picture.set_has_spoiler(data.has_spoiler);
 
             if photo_size.photo.local.is_downloading_completed {
                 self.load_photo(photo_size.photo.local.path);
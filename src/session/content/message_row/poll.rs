@@ -0,0 +1,463 @@
+use adw::prelude::*;
+use gettextrs::gettext;
+use glib::clone;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib, CompositeTemplate};
+use tdlib::enums::{MessageContent, PollType};
+use tdlib::types::{Poll, PollOption};
+
+use crate::i18n::ngettext_f;
+use crate::session::content::message_row::{MessageBase, MessageBaseImpl, MessageBubble};
+use crate::tdlib::Message;
+use crate::utils::{parse_formatted_text, spawn};
+
+use super::base::MessageBaseExt;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessagePoll : $MessageBase {
+        $MessageBubble message_bubble {
+            styles ["poll"]
+
+            prefix: Box {
+                orientation: vertical;
+                spacing: 6;
+
+                styles ["poll"]
+
+                Label question_label {
+                    xalign: 0;
+                    wrap: true;
+                    wrap-mode: word_char;
+
+                    styles ["heading"]
+                }
+
+                Label type_label {
+                    xalign: 0;
+
+                    styles ["caption", "dim-label"]
+                }
+
+                Box options_box {
+                    orientation: vertical;
+                    spacing: 6;
+                }
+
+                Label explanation_label {
+                    xalign: 0;
+                    wrap: true;
+                    wrap-mode: word_char;
+                    use-markup: true;
+                    visible: false;
+
+                    styles ["caption", "explanation"]
+                }
+
+                Box {
+                    halign: start;
+                    spacing: 6;
+
+                    Button vote_button {
+                        visible: false;
+                        sensitive: false;
+                        clicked => $on_vote_button_clicked() swapped;
+                    }
+
+                    Button retract_button {
+                        visible: false;
+                        clicked => $on_retract_button_clicked() swapped;
+
+                        styles ["flat"]
+                    }
+
+                    Button stop_button {
+                        visible: false;
+                        clicked => $on_stop_button_clicked() swapped;
+
+                        styles ["flat"]
+                    }
+                }
+
+                Label voters_label {
+                    xalign: 0;
+
+                    styles ["caption", "dim-label"]
+                }
+            };
+        }
+    }
+    "#)]
+    pub(crate) struct MessagePoll {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: RefCell<Option<Message>>,
+        /// The options that are checked, but not yet voted, in a multiple choice poll
+        pub(super) selected_options: RefCell<Vec<i32>>,
+        /// The id and the number of options of the poll the selected options belong to
+        pub(super) selected_options_poll: Cell<(i64, usize)>,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<MessageBubble>,
+        #[template_child]
+        pub(super) question_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) type_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) options_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) explanation_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) vote_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) retract_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) stop_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) voters_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessagePoll {
+        const NAME: &'static str = "MessagePoll";
+        type Type = super::MessagePoll;
+        type ParentType = MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessagePoll {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.vote_button.set_label(&gettext("Vote"));
+            self.retract_button.set_label(&gettext("Retract Vote"));
+            self.stop_button.set_label(&gettext("Stop Poll"));
+        }
+    }
+
+    impl WidgetImpl for MessagePoll {}
+    impl MessageBaseImpl for MessagePoll {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessagePoll(ObjectSubclass<imp::MessagePoll>)
+        @extends gtk::Widget, MessageBase;
+}
+
+impl MessageBaseExt for MessagePoll {
+    type Message = Message;
+
+    fn set_message(&self, message: Self::Message) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == Some(&message) {
+            return;
+        }
+
+        if let Some(old_message) = imp.message.take() {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.message_bubble.update_from_message(&message, false);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message, _| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+        self.update_content(&message);
+
+        imp.message.replace(Some(message));
+        self.notify("message");
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessagePoll {
+    #[template_callback]
+    fn on_vote_button_clicked(&self) {
+        let option_ids = self.imp().selected_options.borrow().clone();
+        self.set_poll_answer(option_ids);
+    }
+
+    #[template_callback]
+    fn on_retract_button_clicked(&self) {
+        self.set_poll_answer(Vec::new());
+    }
+
+    #[template_callback]
+    fn on_stop_button_clicked(&self) {
+        let window: gtk::Window = self.root().and_then(|root| root.downcast().ok()).unwrap();
+
+        let dialog = adw::MessageDialog::builder()
+            .heading(gettext("Stop Poll?"))
+            .body(gettext(
+                "If you stop this poll now, nobody will be able to vote in it anymore. This \
+                action cannot be undone.",
+            ))
+            .transient_for(&window)
+            .build();
+
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("stop", &gettext("_Stop"))]);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_response_appearance("stop", adw::ResponseAppearance::Destructive);
+
+        dialog.choose(
+            gio::Cancellable::NONE,
+            clone!(@weak self as obj => move |response| {
+                if response == "stop" {
+                    let message = obj.message();
+                    spawn(async move {
+                        if let Err(e) = message.stop_poll().await {
+                            log::warn!("Error stopping a poll: {e:?}");
+                        }
+                    });
+                }
+            }),
+        );
+    }
+
+    fn set_poll_answer(&self, option_ids: Vec<i32>) {
+        let message = self.message();
+        spawn(async move {
+            if let Err(e) = message.set_poll_answer(option_ids).await {
+                log::warn!("Error setting a poll answer: {e:?}");
+            }
+        });
+    }
+
+    fn update_content(&self, message: &Message) {
+        if let MessageContent::MessagePoll(data) = message.content().0 {
+            let imp = self.imp();
+            let poll = data.poll;
+
+            let (is_quiz, allow_multiple_answers) = match &poll.r#type {
+                PollType::Quiz(_) => (true, false),
+                PollType::Regular(data) => (false, data.allow_multiple_answers),
+            };
+            let has_voted = poll.options.iter().any(|o| o.is_chosen);
+            let show_results = has_voted || poll.is_closed;
+
+            imp.question_label.set_label(&poll.question);
+            imp.type_label.set_label(&poll_type(&poll, is_quiz));
+
+            // Keep the checked options across updates of the same poll, like new votes of
+            // other users
+            let selected_options_poll = (poll.id, poll.options.len());
+            if show_results || imp.selected_options_poll.get() != selected_options_poll {
+                imp.selected_options.borrow_mut().clear();
+                imp.selected_options_poll.set(selected_options_poll);
+            }
+
+            while let Some(child) = imp.options_box.first_child() {
+                imp.options_box.remove(&child);
+            }
+            for (index, option) in poll.options.iter().enumerate() {
+                let row = if show_results {
+                    self.result_row(option, index as i32, &poll)
+                } else if allow_multiple_answers {
+                    self.multiple_choice_row(option, index as i32)
+                } else {
+                    self.single_choice_row(option, index as i32)
+                };
+                imp.options_box.append(&row);
+            }
+
+            match &poll.r#type {
+                PollType::Quiz(data) if has_voted && !data.explanation.text.is_empty() => {
+                    imp.explanation_label
+                        .set_label(&parse_formatted_text(data.explanation.clone()));
+                    imp.explanation_label.set_visible(true);
+                }
+                _ => imp.explanation_label.set_visible(false),
+            }
+
+            imp.vote_button
+                .set_visible(!show_results && allow_multiple_answers);
+            imp.vote_button
+                .set_sensitive(!imp.selected_options.borrow().is_empty());
+            imp.retract_button
+                .set_visible(has_voted && !poll.is_closed && !is_quiz);
+            imp.stop_button
+                .set_visible(message.can_be_edited() && !poll.is_closed);
+
+            imp.voters_label
+                .set_label(&voter_count(poll.total_voter_count, is_quiz));
+        }
+    }
+
+    fn single_choice_row(&self, option: &PollOption, index: i32) -> gtk::Widget {
+        let label = gtk::Label::builder()
+            .label(&option.text)
+            .xalign(0.0)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .build();
+
+        let button = gtk::Button::builder()
+            .child(&label)
+            .sensitive(!option.is_being_chosen)
+            .build();
+        button.add_css_class("poll-option");
+        button.connect_clicked(clone!(@weak self as obj => move |_| {
+            obj.set_poll_answer(vec![index]);
+        }));
+
+        button.upcast()
+    }
+
+    fn multiple_choice_row(&self, option: &PollOption, index: i32) -> gtk::Widget {
+        let check_button = gtk::CheckButton::builder()
+            .label(&option.text)
+            .active(option.is_being_chosen || self.imp().selected_options.borrow().contains(&index))
+            .build();
+        check_button.add_css_class("poll-option");
+        check_button.connect_toggled(clone!(@weak self as obj => move |check_button| {
+            let imp = obj.imp();
+
+            let is_empty = {
+                let mut selected_options = imp.selected_options.borrow_mut();
+                if check_button.is_active() {
+                    selected_options.push(index);
+                } else {
+                    selected_options.retain(|i| *i != index);
+                }
+                selected_options.is_empty()
+            };
+
+            imp.vote_button.set_sensitive(!is_empty);
+        }));
+
+        check_button.upcast()
+    }
+
+    fn result_row(&self, option: &PollOption, index: i32, poll: &Poll) -> gtk::Widget {
+        let percentage_label = gtk::Label::builder()
+            .label(&format!("{}%", option.vote_percentage))
+            .width_chars(4)
+            .xalign(1.0)
+            .build();
+        percentage_label.add_css_class("numeric");
+        percentage_label.add_css_class("heading");
+
+        let text_label = gtk::Label::builder()
+            .label(&option.text)
+            .hexpand(true)
+            .xalign(0.0)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .build();
+
+        let header = gtk::Box::builder().spacing(6).build();
+        header.append(&percentage_label);
+        header.append(&text_label);
+
+        let is_correct_option = match &poll.r#type {
+            PollType::Quiz(data) => Some(data.correct_option_id == index),
+            PollType::Regular(_) => None,
+        };
+        let state_icon = match is_correct_option {
+            Some(true) => Some(("object-select-symbolic", "success")),
+            Some(false) if option.is_chosen => Some(("window-close-symbolic", "error")),
+            None if option.is_chosen => Some(("object-select-symbolic", "accent")),
+            _ => None,
+        };
+        if let Some((icon_name, css_class)) = state_icon {
+            let image = gtk::Image::from_icon_name(icon_name);
+            image.add_css_class(css_class);
+            header.append(&image);
+        }
+
+        let bar = gtk::ProgressBar::builder()
+            .fraction(option.vote_percentage as f64 / 100.0)
+            .build();
+        if option.is_chosen {
+            bar.add_css_class("chosen");
+        }
+
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(3)
+            .build();
+        row.add_css_class("poll-result");
+        row.append(&header);
+        row.append(&bar);
+
+        row.upcast()
+    }
+}
+
+fn poll_type(poll: &Poll, is_quiz: bool) -> String {
+    if poll.is_closed {
+        gettext("Final Results")
+    } else {
+        match (is_quiz, poll.is_anonymous) {
+            (true, true) => gettext("Anonymous Quiz"),
+            (true, false) => gettext("Quiz"),
+            (false, true) => gettext("Anonymous Poll"),
+            (false, false) => gettext("Public Poll"),
+        }
+    }
+}
+
+fn voter_count(count: i32, is_quiz: bool) -> String {
+    if count == 0 {
+        if is_quiz {
+            gettext("No answers yet")
+        } else {
+            gettext("No votes yet")
+        }
+    } else if is_quiz {
+        ngettext_f(
+            "{num} answer",
+            "{num} answers",
+            count as u32,
+            &[("num", &count.to_string())],
+        )
+    } else {
+        ngettext_f(
+            "{num} vote",
+            "{num} votes",
+            count as u32,
+            &[("num", &count.to_string())],
+        )
+    }
+}
@@ -0,0 +1,130 @@
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{cairo, glib, graphene};
+use std::f64::consts::PI;
+
+const LINE_WIDTH: f64 = 3.0;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::Cell;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct MessageProgressRing {
+        pub(super) progress: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageProgressRing {
+        const NAME: &'static str = "MessageProgressRing";
+        type Type = super::MessageProgressRing;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("messageprogressring");
+        }
+    }
+
+    impl ObjectImpl for MessageProgressRing {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecDouble::builder("progress")
+                    .minimum(0.0)
+                    .maximum(1.0)
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "progress" => obj.set_progress(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "progress" => obj.progress().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+
+    impl WidgetImpl for MessageProgressRing {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let progress = self.progress.get();
+            if progress <= 0.0 {
+                return;
+            }
+
+            let obj = self.obj();
+            let width = obj.width() as f64;
+            let height = obj.height() as f64;
+            let radius = (width.min(height) - LINE_WIDTH) / 2.0;
+            if radius <= 0.0 {
+                return;
+            }
+
+            let bounds = graphene::Rect::new(0.0, 0.0, width as f32, height as f32);
+            let cr = snapshot.append_cairo(&bounds);
+
+            let color = obj.color();
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                color.alpha() as f64,
+            );
+            cr.set_line_width(LINE_WIDTH);
+            cr.set_line_cap(cairo::LineCap::Round);
+
+            let start_angle = -PI / 2.0;
+            cr.arc(
+                width / 2.0,
+                height / 2.0,
+                radius,
+                start_angle,
+                start_angle + 2.0 * PI * progress,
+            );
+
+            if let Err(e) = cr.stroke() {
+                log::warn!("Error drawing a progress ring: {e:?}");
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageProgressRing(ObjectSubclass<imp::MessageProgressRing>)
+        @extends gtk::Widget;
+}
+
+impl Default for MessageProgressRing {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl MessageProgressRing {
+    pub(crate) fn progress(&self) -> f64 {
+        self.imp().progress.get()
+    }
+
+    pub(crate) fn set_progress(&self, progress: f64) {
+        if self.progress() == progress {
+            return;
+        }
+
+        self.imp().progress.set(progress);
+        self.queue_draw();
+
+        self.notify("progress");
+    }
+}
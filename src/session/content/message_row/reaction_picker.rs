@@ -0,0 +1,167 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+use tdlib::enums::ReactionType;
+
+use crate::components::Sticker;
+use crate::tdlib::Message;
+use crate::utils::spawn;
+
+use super::reactions::load_custom_emoji;
+
+const CUSTOM_EMOJI_SIZE: i32 = 20;
+
+mod imp {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageReactionPicker {
+        ScrolledWindow scrolled_window {
+            vscrollbar-policy: never;
+            propagate-natural-width: true;
+            max-content-width: 300;
+
+            Box reactions_box {
+                spacing: 3;
+            }
+        }
+    }
+    "#)]
+    pub(crate) struct MessageReactionPicker {
+        pub(super) message: RefCell<Option<Message>>,
+        #[template_child]
+        pub(super) scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub(super) reactions_box: TemplateChild<gtk::Box>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageReactionPicker {
+        const NAME: &'static str = "MessageReactionPicker";
+        type Type = super::MessageReactionPicker;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("messagereactionpicker");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageReactionPicker {
+        fn dispose(&self) {
+            self.scrolled_window.unparent();
+        }
+    }
+
+    impl WidgetImpl for MessageReactionPicker {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageReactionPicker(ObjectSubclass<imp::MessageReactionPicker>)
+        @extends gtk::Widget;
+}
+
+impl Default for MessageReactionPicker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageReactionPicker {
+    pub(crate) fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Sets the message to react to. The picker is hidden if reactions can't be added to the
+    /// message.
+    pub(crate) fn set_message(&self, message: Option<Message>) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.reactions_box.first_child() {
+            imp.reactions_box.remove(&child);
+        }
+        self.set_visible(false);
+
+        imp.message.replace(message.clone());
+
+        if let Some(message) = message {
+            spawn(clone!(@weak self as obj => async move {
+                match message.available_reactions().await {
+                    Ok(reactions) => {
+                        // The menu may have been opened for another message in the meantime
+                        if obj.imp().message.borrow().as_ref() == Some(&message) {
+                            obj.set_reactions(&message, reactions);
+                        }
+                    }
+                    Err(e) => log::warn!("Error getting the available reactions: {:?}", e),
+                }
+            }));
+        }
+    }
+
+    fn set_reactions(&self, message: &Message, reactions: Vec<ReactionType>) {
+        let imp = self.imp();
+        let chosen_reactions: Vec<ReactionType> = message
+            .interaction_info()
+            .reactions()
+            .0
+            .into_iter()
+            .filter(|r| r.is_chosen)
+            .map(|r| r.r#type)
+            .collect();
+
+        for reaction_type in reactions {
+            let is_chosen = chosen_reactions.contains(&reaction_type);
+
+            let button = match &reaction_type {
+                ReactionType::Emoji(data) => gtk::Button::with_label(&data.emoji),
+                ReactionType::CustomEmoji(data) => {
+                    let sticker: Sticker = glib::Object::new();
+                    sticker.set_longer_side_size(CUSTOM_EMOJI_SIZE);
+                    load_custom_emoji(&sticker, data.custom_emoji_id, message);
+
+                    gtk::Button::builder().child(&sticker).build()
+                }
+            };
+            button.add_css_class("flat");
+            button.add_css_class("circular");
+            if is_chosen {
+                button.add_css_class("chosen");
+            }
+
+            button.connect_clicked(clone!(@weak self as obj, @weak message => move |_| {
+                obj.toggle_reaction(&message, reaction_type.clone(), is_chosen);
+            }));
+
+            imp.reactions_box.append(&button);
+        }
+
+        self.set_visible(imp.reactions_box.first_child().is_some());
+    }
+
+    fn toggle_reaction(&self, message: &Message, reaction_type: ReactionType, is_chosen: bool) {
+        if let Some(popover) = self.ancestor(gtk::Popover::static_type()) {
+            popover.downcast::<gtk::Popover>().unwrap().popdown();
+        }
+
+        spawn(clone!(@weak message => async move {
+            let result = if is_chosen {
+                message.remove_reaction(reaction_type).await
+            } else {
+                message.add_reaction(reaction_type).await
+            };
+
+            if let Err(e) = result {
+                log::warn!("Error toggling a message reaction: {:?}", e);
+            }
+        }));
+    }
+}
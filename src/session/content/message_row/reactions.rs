@@ -0,0 +1,196 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+use tdlib::enums::{self, ReactionType, StickerFullType};
+use tdlib::functions;
+use tdlib::types::MessageReaction;
+
+use crate::components::Sticker;
+use crate::tdlib::{Message, MessageInteractionInfo};
+use crate::utils::spawn;
+
+const CUSTOM_EMOJI_SIZE: i32 = 16;
+
+mod imp {
+    use super::*;
+    use once_cell::unsync::OnceCell;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageReactions {
+        FlowBox flow_box {
+            selection-mode: none;
+            column-spacing: 6;
+            row-spacing: 6;
+            max-children-per-line: 20;
+        }
+    }
+    "#)]
+    pub(crate) struct MessageReactions {
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) interaction_info_signal_group: OnceCell<glib::SignalGroup>,
+        #[template_child]
+        pub(super) flow_box: TemplateChild<gtk::FlowBox>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageReactions {
+        const NAME: &'static str = "MessageReactions";
+        type Type = super::MessageReactions;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("messagereactions");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageReactions {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let interaction_info_signal_group =
+                glib::SignalGroup::new(MessageInteractionInfo::static_type());
+            interaction_info_signal_group.connect_notify_local(
+                Some("reactions"),
+                clone!(@weak obj => move |_, _| {
+                    obj.update_reactions();
+                }),
+            );
+            self.interaction_info_signal_group
+                .set(interaction_info_signal_group)
+                .unwrap();
+        }
+
+        fn dispose(&self) {
+            self.flow_box.unparent();
+        }
+    }
+
+    impl WidgetImpl for MessageReactions {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageReactions(ObjectSubclass<imp::MessageReactions>)
+        @extends gtk::Widget;
+}
+
+impl MessageReactions {
+    pub(crate) fn set_message(&self, message: Option<&Message>) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == message {
+            return;
+        }
+
+        imp.interaction_info_signal_group
+            .get()
+            .unwrap()
+            .set_target(message.map(|m| m.interaction_info()));
+        imp.message.replace(message.cloned());
+
+        self.update_reactions();
+    }
+
+    fn update_reactions(&self) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.flow_box.first_child() {
+            imp.flow_box.remove(&child);
+        }
+
+        let message = match imp.message.borrow().clone() {
+            Some(message) => message,
+            None => {
+                self.set_visible(false);
+                return;
+            }
+        };
+
+        let reactions = message.interaction_info().reactions().0;
+        self.set_visible(!reactions.is_empty());
+
+        for reaction in reactions {
+            imp.flow_box.append(&self.create_chip(&message, reaction));
+        }
+    }
+
+    fn create_chip(&self, message: &Message, reaction: MessageReaction) -> gtk::Button {
+        let content = gtk::Box::new(gtk::Orientation::Horizontal, 3);
+
+        match &reaction.r#type {
+            ReactionType::Emoji(data) => {
+                content.append(&gtk::Label::new(Some(&data.emoji)));
+            }
+            ReactionType::CustomEmoji(data) => {
+                let sticker: Sticker = glib::Object::new();
+                sticker.set_longer_side_size(CUSTOM_EMOJI_SIZE);
+                content.append(&sticker);
+
+                load_custom_emoji(&sticker, data.custom_emoji_id, message);
+            }
+        }
+
+        let count_label = gtk::Label::new(Some(&reaction.total_count.to_string()));
+        count_label.add_css_class("numeric");
+        content.append(&count_label);
+
+        let button = gtk::Button::builder().child(&content).build();
+        button.add_css_class("reaction");
+        if reaction.is_chosen {
+            button.add_css_class("chosen");
+        }
+
+        button.connect_clicked(clone!(@weak message => move |_| {
+            let reaction_type = reaction.r#type.clone();
+            let is_chosen = reaction.is_chosen;
+
+            spawn(async move {
+                let result = if is_chosen {
+                    message.remove_reaction(reaction_type).await
+                } else {
+                    message.add_reaction(reaction_type).await
+                };
+
+                if let Err(e) = result {
+                    log::warn!("Error toggling a message reaction: {:?}", e);
+                }
+            });
+        }));
+
+        button
+    }
+}
+
+/// Loads the sticker of a custom emoji reaction into the `Sticker` widget.
+pub(super) fn load_custom_emoji(sticker: &Sticker, custom_emoji_id: i64, message: &Message) {
+    let session = message.chat().session();
+
+    spawn(clone!(@weak sticker, @weak session => async move {
+        match functions::get_custom_emoji_stickers(vec![custom_emoji_id], session.client_id()).await
+        {
+            Ok(enums::Stickers::Stickers(data)) => {
+                let custom_emoji = data.stickers.into_iter().find(|s| {
+                    matches!(
+                        &s.full_type,
+                        StickerFullType::CustomEmoji(data) if data.custom_emoji_id == custom_emoji_id
+                    )
+                });
+
+                if let Some(custom_emoji) = custom_emoji {
+                    sticker.update_sticker(custom_emoji, true, session);
+                }
+            }
+            Err(e) => log::warn!("Error fetching a custom emoji reaction: {e:?}"),
+        }
+    }));
+}
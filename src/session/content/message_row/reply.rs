@@ -17,6 +17,10 @@ mod imp {
     #[properties(wrapper_type = super::MessageReply)]
     #[template(string = r#"
     template $MessageReply : Widget {
+        GestureClick {
+            released => $on_released() swapped;
+        }
+
         Separator separator {
             width-request: 2;
         }
@@ -69,6 +73,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             Self::bind_template(klass);
+            klass.bind_template_instance_callbacks();
             klass.set_layout_manager_type::<gtk::BoxLayout>();
             klass.set_css_name("messagereply");
         }
@@ -96,6 +101,8 @@ mod imp {
                 .set_label(&gettextrs::gettext("Loading…"));
 
             let obj = self.obj();
+            obj.set_cursor_from_name(Some("pointer"));
+
             spawn(clone!(@weak obj => async move {
                 obj.load_replied_message().await;
             }));
@@ -114,6 +121,7 @@ glib::wrapper! {
         @extends gtk::Widget;
 }
 
+#[gtk::template_callbacks]
 impl MessageReply {
     pub(crate) fn new(message: &Message) -> Self {
         glib::Object::builder().property("message", message).build()
@@ -138,6 +146,21 @@ impl MessageReply {
         }
     }
 
+    /// Jumps to the replied message, if it's in the same chat.
+    #[template_callback]
+    fn on_released(&self) {
+        let message = self.message().unwrap();
+        let reply_in_chat_id = message.reply_in_chat_id();
+
+        if reply_in_chat_id == 0 || reply_in_chat_id == message.chat().id() {
+            self.activate_action(
+                "chat-history.jump-to-message",
+                Some(&(message.id(), message.reply_to_message_id()).to_variant()),
+            )
+            .unwrap();
+        }
+    }
+
     pub(crate) fn set_max_char_width(&self, n_chars: i32) {
         self.imp().message_label.set_max_width_chars(n_chars);
         self.imp().sender_label.set_max_width_chars(n_chars);
@@ -0,0 +1,238 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+use tdlib::enums::{InlineKeyboardButtonType, ReplyMarkup};
+use tdlib::types::{CallbackQueryAnswer, InlineKeyboardButton};
+
+use crate::session::content::{ChatHistory, ChooseChatDialog};
+use crate::tdlib::{BoxedReplyMarkup, Message};
+use crate::utils::spawn;
+use crate::Session;
+
+mod imp {
+    use super::*;
+    use once_cell::unsync::OnceCell;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    using Adw 1;
+
+    template $MessageReplyMarkup {
+        Adw.Clamp clamp {
+            maximum-size: 400;
+            tightening-threshold: 400;
+
+            child: Box rows_box {
+                orientation: vertical;
+                spacing: 3;
+            };
+        }
+    }
+    "#)]
+    pub(crate) struct MessageReplyMarkup {
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) message_signal_group: OnceCell<glib::SignalGroup>,
+        #[template_child]
+        pub(super) clamp: TemplateChild<adw::Clamp>,
+        #[template_child]
+        pub(super) rows_box: TemplateChild<gtk::Box>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageReplyMarkup {
+        const NAME: &'static str = "MessageReplyMarkup";
+        type Type = super::MessageReplyMarkup;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("messagereplymarkup");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageReplyMarkup {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let message_signal_group = glib::SignalGroup::new(Message::static_type());
+            message_signal_group.connect_notify_local(
+                Some("reply-markup"),
+                clone!(@weak obj => move |_, _| {
+                    obj.update_rows();
+                }),
+            );
+            self.message_signal_group.set(message_signal_group).unwrap();
+        }
+
+        fn dispose(&self) {
+            self.clamp.unparent();
+        }
+    }
+
+    impl WidgetImpl for MessageReplyMarkup {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageReplyMarkup(ObjectSubclass<imp::MessageReplyMarkup>)
+        @extends gtk::Widget;
+}
+
+impl MessageReplyMarkup {
+    pub(crate) fn set_message(&self, message: Option<&Message>) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == message {
+            return;
+        }
+
+        imp.message_signal_group.get().unwrap().set_target(message);
+        imp.message.replace(message.cloned());
+
+        self.update_rows();
+    }
+
+    fn update_rows(&self) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.rows_box.first_child() {
+            imp.rows_box.remove(&child);
+        }
+
+        let message = imp.message.borrow().clone();
+        let rows = match message.as_ref().and_then(Message::reply_markup) {
+            Some(BoxedReplyMarkup(ReplyMarkup::InlineKeyboard(data))) => data.rows,
+            _ => {
+                // Other reply markups are shown by the chat action bar
+                self.set_visible(false);
+                return;
+            }
+        };
+        let message = message.unwrap();
+
+        for row in rows {
+            let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 3);
+            row_box.set_homogeneous(true);
+
+            for button in row {
+                row_box.append(&self.create_button(&message, button));
+            }
+
+            imp.rows_box.append(&row_box);
+        }
+
+        self.set_visible(imp.rows_box.first_child().is_some());
+    }
+
+    fn create_button(&self, message: &Message, button: InlineKeyboardButton) -> gtk::Button {
+        let label = gtk::Label::builder()
+            .label(&button.text)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .build();
+
+        let widget = gtk::Button::builder().child(&label).hexpand(true).build();
+
+        match &button.r#type {
+            InlineKeyboardButtonType::Url(data) => widget.set_tooltip_text(Some(&data.url)),
+            InlineKeyboardButtonType::LoginUrl(data) => widget.set_tooltip_text(Some(&data.url)),
+            InlineKeyboardButtonType::WebApp(data) => widget.set_tooltip_text(Some(&data.url)),
+            InlineKeyboardButtonType::Callback(_) | InlineKeyboardButtonType::SwitchInline(_) => {}
+            _ => {
+                // TODO: Support games, payments, password protected callbacks and user buttons
+                widget.set_sensitive(false);
+            }
+        }
+
+        widget.connect_clicked(clone!(@weak self as obj, @weak message => move |_| {
+            obj.activate_button(&message, button.r#type.clone());
+        }));
+
+        widget
+    }
+
+    fn activate_button(&self, message: &Message, button_type: InlineKeyboardButtonType) {
+        let session = message.chat().session();
+
+        match button_type {
+            InlineKeyboardButtonType::Url(data) => session.open_link(data.url),
+            InlineKeyboardButtonType::LoginUrl(data) => session.open_link(data.url),
+            InlineKeyboardButtonType::WebApp(data) => session.open_link(data.url),
+            InlineKeyboardButtonType::Callback(data) => {
+                spawn(clone!(@weak self as obj, @weak message => async move {
+                    match message.get_callback_query_answer(data.data).await {
+                        Ok(answer) => obj.show_callback_query_answer(&message, answer),
+                        Err(e) => log::warn!("Error getting a callback query answer: {e:?}"),
+                    }
+                }));
+            }
+            InlineKeyboardButtonType::SwitchInline(data) => {
+                // The inline query is addressed to the bot that sent the message
+                if let Some(bot) = message.sender().as_user() {
+                    let text = format!("@{} {}", bot.username(), data.query);
+
+                    if data.in_current_chat {
+                        self.activate_action("chat-history.set-text", Some(&text.to_variant()))
+                            .unwrap();
+                    } else {
+                        self.choose_chat_for_inline_query(text, &session);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Lets the user choose the chat in which to write the inline query and opens it.
+    fn choose_chat_for_inline_query(&self, text: String, session: &Session) {
+        let chat_history = match self.ancestor(ChatHistory::static_type()) {
+            Some(chat_history) => chat_history,
+            None => return,
+        };
+
+        let window = self.root().and_downcast::<gtk::Window>();
+        let dialog = ChooseChatDialog::new(&window, session);
+        dialog.connect_chat_chosen(
+            clone!(@weak chat_history, @weak session => move |dialog, chat| {
+                dialog.close();
+                session.imp().sidebar.get().select_chat(chat);
+                chat_history
+                    .activate_action("chat-history.set-text", Some(&text.to_variant()))
+                    .unwrap();
+            }),
+        );
+        dialog.present();
+    }
+
+    fn show_callback_query_answer(&self, message: &Message, answer: CallbackQueryAnswer) {
+        if !answer.text.is_empty() {
+            if answer.show_alert {
+                let dialog = adw::MessageDialog::builder()
+                    .body(&answer.text)
+                    .transient_for(self.root().unwrap().downcast_ref::<gtk::Window>().unwrap())
+                    .build();
+
+                dialog.add_responses(&[("ok", &gettext("_OK"))]);
+                dialog.set_default_response(Some("ok"));
+                dialog.present();
+            } else if let Some(chat_history) = self.ancestor(ChatHistory::static_type()) {
+                chat_history
+                    .downcast::<ChatHistory>()
+                    .unwrap()
+                    .add_toast(adw::Toast::new(&answer.text));
+            }
+        }
+
+        if !answer.url.is_empty() {
+            message.chat().session().open_link(answer.url);
+        }
+    }
+}
@@ -1,13 +1,17 @@
 use gettextrs::gettext;
-use glib::closure;
+use glib::clone;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::{glib, CompositeTemplate};
 use tdlib::enums::MessageContent;
+use tdlib::types::FormattedText;
 
-use crate::session::content::message_row::{MessageBase, MessageBaseImpl, MessageBubble};
-use crate::tdlib::{BoxedMessageContent, Message, SponsoredMessage};
-use crate::utils::parse_formatted_text;
+use crate::session::content::message_row::{
+    MessageBase, MessageBaseImpl, MessageBubble, MessageCodeBlock, MessageLabel, MessageWebPage,
+};
+use crate::tdlib::{Message, SponsoredMessage};
+use crate::utils::{split_code_blocks, FormattedTextBlock};
+use crate::Session;
 
 use super::base::MessageBaseExt;
 
@@ -96,26 +100,36 @@ impl MessageBaseExt for MessageText {
         if let Some(message) = message.downcast_ref::<Message>() {
             imp.message_bubble.update_from_message(message, false);
 
-            // Set content label expression
-            let text_binding = Message::this_expression("content")
-                .chain_closure::<String>(closure!(|_: Message, content: BoxedMessageContent| {
-                    format_message_content_text(content.0)
-                }))
-                .bind(&*imp.message_bubble, "label", Some(message));
-            bindings.push(text_binding);
+            // Update the content when it changes
+            let content_watch = Message::this_expression("content").watch(
+                Some(message),
+                clone!(@weak self as obj, @weak message => move || {
+                    obj.update_content(message.content().0, &message.chat().session());
+                }),
+            );
+            bindings.push(content_watch);
+
+            self.update_content(message.content().0, &message.chat().session());
         } else if let Some(sponsored_message) = message.downcast_ref::<SponsoredMessage>() {
             imp.message_bubble
                 .update_from_sponsored_message(sponsored_message);
 
-            // Set content label expression
-            let text_binding = SponsoredMessage::this_expression("content")
-                .chain_closure::<String>(closure!(
-                    |_: SponsoredMessage, content: BoxedMessageContent| {
-                        format_message_content_text(content.0)
-                    }
-                ))
-                .bind(&*imp.message_bubble, "label", Some(sponsored_message));
-            bindings.push(text_binding);
+            // Update the content when it changes
+            let content_watch = SponsoredMessage::this_expression("content").watch(
+                Some(sponsored_message),
+                clone!(@weak self as obj, @weak sponsored_message => move || {
+                    obj.update_content(
+                        sponsored_message.content().0,
+                        &sponsored_message.sponsor_chat().session(),
+                    );
+                }),
+            );
+            bindings.push(content_watch);
+
+            self.update_content(
+                sponsored_message.content().0,
+                &sponsored_message.sponsor_chat().session(),
+            );
         } else {
             unreachable!("Unexpected message type: {:?}", message);
         }
@@ -125,9 +139,62 @@ impl MessageBaseExt for MessageText {
     }
 }
 
-fn format_message_content_text(content: MessageContent) -> String {
-    match content {
-        MessageContent::MessageText(content) => parse_formatted_text(content.text),
-        _ => format!("<i>{}</i>", gettext("This message is unsupported")),
+impl MessageText {
+    fn update_content(&self, content: MessageContent, session: &Session) {
+        let imp = self.imp();
+
+        match content {
+            MessageContent::MessageText(content) => {
+                let mut blocks = split_code_blocks(content.text);
+
+                // The last text block is shown in the label of the bubble, so that the
+                // indicators are placed next to it, unless there's a web page card below it
+                let label = if content.web_page.is_none()
+                    && matches!(blocks.last(), Some(FormattedTextBlock::Text(_)))
+                {
+                    match blocks.pop() {
+                        Some(FormattedTextBlock::Text(text)) => text,
+                        _ => unreachable!(),
+                    }
+                } else {
+                    FormattedText::default()
+                };
+
+                if blocks.is_empty() && content.web_page.is_none() {
+                    imp.message_bubble.set_prefix(None);
+                } else {
+                    let content_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+                    content_box.add_css_class("text-blocks");
+
+                    for block in blocks {
+                        match block {
+                            FormattedTextBlock::Text(text) => {
+                                let label: MessageLabel = glib::Object::new();
+                                label.set_session(session);
+                                label.set_formatted_text(text);
+                                content_box.append(&label);
+                            }
+                            FormattedTextBlock::Code { code, language } => {
+                                content_box.append(&MessageCodeBlock::new(code, language));
+                            }
+                        }
+                    }
+
+                    if let Some(web_page) = content.web_page {
+                        content_box.append(&MessageWebPage::new(web_page, session));
+                    }
+
+                    imp.message_bubble
+                        .set_prefix(Some(content_box.upcast_ref()));
+                }
+
+                imp.message_bubble.set_formatted_text(label);
+            }
+            _ => {
+                imp.message_bubble.set_prefix(None);
+                imp.message_bubble
+                    .set_label(format!("<i>{}</i>", gettext("This message is unsupported")));
+            }
+        }
     }
 }
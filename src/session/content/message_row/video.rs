@@ -3,16 +3,21 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use gtk::{gdk, glib, CompositeTemplate};
 use tdlib::enums::MessageContent;
+use tdlib::types::{File, Minithumbnail, Thumbnail};
 
 use crate::session::content::message_row::{
     MediaPicture, MessageBase, MessageBaseImpl, MessageBubble,
 };
 use crate::tdlib::Message;
-use crate::utils::{parse_formatted_text, spawn};
+use crate::utils::{format_duration, spawn};
 use crate::Session;
 
 use super::base::MessageBaseExt;
 
+/// The amount of bytes that needs to be downloaded before starting to play a video
+/// that is still being downloaded.
+const STREAMING_PREFIX_SIZE: i64 = 1024 * 1024;
+
 mod imp {
     use super::*;
     use once_cell::sync::Lazy;
@@ -25,7 +30,9 @@ mod imp {
             styles ["media"]
 
             prefix: Overlay {
-                $MessageMediaPicture picture {}
+                $MessageMediaPicture picture {
+                    notify::has-spoiler => $on_picture_has_spoiler_notify() swapped;
+                }
 
                 [overlay]
                 Label indicator {
@@ -34,6 +41,25 @@ mod imp {
 
                     styles ["osd-indicator"]
                 }
+
+                [overlay]
+                Button play_button {
+                    halign: center;
+                    valign: center;
+                    visible: false;
+                    icon-name: "media-playback-start-symbolic";
+                    clicked => $on_play_button_clicked() swapped;
+
+                    styles ["osd", "circular", "play-button"]
+                }
+
+                [overlay]
+                MediaControls media_controls {
+                    valign: end;
+                    visible: false;
+
+                    styles ["osd"]
+                }
             };
         }
     }
@@ -42,12 +68,20 @@ mod imp {
         pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
         pub(super) message: RefCell<Option<Message>>,
         pub(super) is_animation: Cell<bool>,
+        pub(super) file: RefCell<Option<File>>,
+        pub(super) duration: Cell<i32>,
+        pub(super) media: RefCell<Option<gtk::MediaFile>>,
+        pub(super) play_after_download: Cell<bool>,
         #[template_child]
         pub(super) message_bubble: TemplateChild<MessageBubble>,
         #[template_child]
         pub(super) picture: TemplateChild<MediaPicture>,
         #[template_child]
         pub(super) indicator: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) play_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) media_controls: TemplateChild<gtk::MediaControls>,
     }
 
     #[glib::object_subclass]
@@ -58,6 +92,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             Self::bind_template(klass);
+            klass.bind_template_instance_callbacks();
             klass.set_layout_manager_type::<gtk::BinLayout>();
         }
 
@@ -91,6 +126,12 @@ mod imp {
                 _ => unimplemented!(),
             }
         }
+
+        fn dispose(&self) {
+            if let Some(media) = self.media.take() {
+                media.pause();
+            }
+        }
     }
 
     impl WidgetImpl for MessageVideo {}
@@ -132,67 +173,206 @@ impl MessageBaseExt for MessageVideo {
     }
 }
 
+#[gtk::template_callbacks]
 impl MessageVideo {
+    #[template_callback]
+    fn on_play_button_clicked(&self) {
+        let imp = self.imp();
+
+        let file = match imp.file.borrow().clone() {
+            Some(file) => file,
+            None => return,
+        };
+
+        if file.local.is_downloading_completed {
+            self.load_video(&file.local.path);
+        } else {
+            let session = self.message().chat().session();
+
+            if file.local.is_downloading_active {
+                imp.play_after_download.set(false);
+                session.cancel_download_file(file.id);
+            } else {
+                imp.play_after_download.set(true);
+                session.download_file_with_updates(
+                    file.id,
+                    clone!(@weak self as obj => move |file| {
+                        obj.update_file(file);
+                    }),
+                );
+            }
+        }
+    }
+
+    #[template_callback]
+    fn on_picture_has_spoiler_notify(&self) {
+        let imp = self.imp();
+
+        // The play button is hidden while the spoiler is shown
+        if !imp.picture.has_spoiler() && !imp.is_animation.get() && imp.media.borrow().is_none() {
+            imp.play_button.set_visible(imp.file.borrow().is_some());
+        }
+    }
+
     fn update_content(&self, content: MessageContent, session: &Session) {
         let imp = self.imp();
 
-        let (caption, file, aspect_ratio, minithumbnail) =
-            if let MessageContent::MessageAnimation(data) = content {
+        match content {
+            MessageContent::MessageAnimation(data) => {
+                self.stop_playback();
+
                 imp.indicator.set_label("GIF");
                 imp.is_animation.set(true);
-                (
-                    data.caption,
-                    data.animation.animation,
-                    data.animation.width as f64 / data.animation.height as f64,
-                    data.animation.minithumbnail,
-                )
-            } else if let MessageContent::MessageVideo(data) = content {
-                self.update_remaining_time(data.video.duration as i64);
+                imp.play_button.set_visible(false);
+                imp.file.replace(None);
+
+                imp.message_bubble.set_formatted_text(data.caption);
+                imp.picture
+                    .set_aspect_ratio(data.animation.width as f64 / data.animation.height as f64);
+                imp.picture.set_has_spoiler(data.has_spoiler);
+
+                let file = data.animation.animation;
+                if file.local.is_downloading_completed {
+                    self.load_animation(&file.local.path);
+                } else {
+                    self.set_minithumbnail(data.animation.minithumbnail);
+
+                    let file_id = file.id;
+                    spawn(clone!(@weak self as obj, @weak session => async move {
+                        obj.download_animation(file_id, &session).await;
+                    }));
+                }
+            }
+            MessageContent::MessageVideo(data) => {
                 imp.is_animation.set(false);
-                (
-                    data.caption,
-                    data.video.video,
-                    data.video.width as f64 / data.video.height as f64,
-                    data.video.minithumbnail,
-                )
-            } else {
-                unreachable!();
-            };
 
-        let caption = parse_formatted_text(caption);
-        imp.message_bubble.set_label(caption);
+                imp.message_bubble.set_formatted_text(data.caption);
+                imp.picture
+                    .set_aspect_ratio(data.video.width as f64 / data.video.height as f64);
+
+                // Do not interrupt the playback if only other parts of the message
+                // content have changed (e.g. the caption)
+                let is_same_file = imp
+                    .file
+                    .borrow()
+                    .as_ref()
+                    .map(|f| f.id == data.video.video.id)
+                    .unwrap_or_default();
+                if is_same_file && imp.media.borrow().is_some() {
+                    return;
+                }
 
-        imp.picture.set_aspect_ratio(aspect_ratio);
+                self.stop_playback();
+                imp.picture.set_has_spoiler(data.has_spoiler);
 
-        if file.local.is_downloading_completed {
-            self.load_video(&file.local.path);
-        } else {
-            imp.picture.set_paintable(
-                minithumbnail
-                    .and_then(|m| {
-                        gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(
-                            &m.data,
-                        )))
-                        .ok()
-                    })
-                    .as_ref(),
-            );
+                imp.duration.set(data.video.duration);
+                self.update_remaining_time(data.video.duration as i64);
 
-            let file_id = file.id;
-            spawn(clone!(@weak self as obj, @weak session => async move {
-                obj.download_video(file_id, &session).await;
-            }));
+                self.set_minithumbnail(data.video.minithumbnail);
+                if let Some(thumbnail) = data.video.thumbnail {
+                    self.load_thumbnail(thumbnail, session);
+                }
+
+                // Make sure that the eventual file updates of a previous video are ignored
+                imp.file.replace(None);
+                self.update_file(data.video.video);
+            }
+            _ => unreachable!(),
         }
     }
 
-    async fn download_video(&self, file_id: i32, session: &Session) {
-        match session.download_file(file_id).await {
-            Ok(file) => {
-                self.load_video(&file.local.path);
+    fn set_minithumbnail(&self, minithumbnail: Option<Minithumbnail>) {
+        self.imp().picture.set_paintable(
+            minithumbnail
+                .and_then(|m| {
+                    gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data)))
+                        .ok()
+                })
+                .as_ref(),
+        );
+    }
+
+    fn load_thumbnail(&self, thumbnail: Thumbnail, session: &Session) {
+        let file_id = thumbnail.file.id;
+        spawn(clone!(@weak self as obj, @weak session => async move {
+            match session.download_file(file_id).await {
+                Ok(file) => {
+                    let imp = obj.imp();
+
+                    // Check that the widget is still showing the same video and that
+                    // the video itself has not started to play yet
+                    let is_current_video = imp
+                        .file
+                        .borrow()
+                        .as_ref()
+                        .map(|f| f.id)
+                        == obj.video_file_id();
+                    if !is_current_video || imp.media.borrow().is_some() {
+                        return;
+                    }
+
+                    match gdk::Texture::from_filename(&file.local.path) {
+                        Ok(texture) => imp.picture.set_paintable(Some(&texture)),
+                        Err(e) => log::warn!("Error loading a video thumbnail: {e:?}"),
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Failed to download a video thumbnail: {e:?}");
+                }
             }
-            Err(e) => {
-                log::warn!("Failed to download a video: {e:?}");
+        }));
+    }
+
+    fn video_file_id(&self) -> Option<i32> {
+        self.imp().message.borrow().as_ref().and_then(|message| {
+            if let MessageContent::MessageVideo(data) = message.content().0 {
+                Some(data.video.video.id)
+            } else {
+                None
             }
+        })
+    }
+
+    fn update_file(&self, file: File) {
+        let imp = self.imp();
+
+        if imp.is_animation.get() {
+            return;
+        }
+
+        if let Some(old_file) = imp.file.borrow().as_ref() {
+            if old_file.id != file.id {
+                return;
+            }
+        }
+
+        if imp.media.borrow().is_none() {
+            if file.local.is_downloading_active {
+                let size = file.size.max(file.expected_size) as u64;
+                let downloaded = glib::format_size(file.local.downloaded_size as u64);
+                let full_size = glib::format_size(size);
+
+                imp.play_button
+                    .set_icon_name("media-playback-stop-symbolic");
+                imp.indicator
+                    .set_label(&format!("{downloaded} / {full_size}"));
+            } else {
+                imp.play_button
+                    .set_icon_name("media-playback-start-symbolic");
+                self.update_remaining_time(imp.duration.get() as i64);
+            }
+            imp.play_button.set_visible(!imp.picture.has_spoiler());
+        }
+
+        // Start playing the video as soon as enough of it has been downloaded
+        let size = file.size.max(file.expected_size) as i64;
+        let can_stream = file.local.is_downloading_completed
+            || file.local.downloaded_prefix_size >= STREAMING_PREFIX_SIZE.min(size);
+        let path = file.local.path.clone();
+        imp.file.replace(Some(file));
+
+        if can_stream && !path.is_empty() && imp.play_after_download.replace(false) {
+            self.load_video(&path);
         }
     }
 
@@ -200,31 +380,53 @@ impl MessageVideo {
         let imp = self.imp();
 
         let media = gtk::MediaFile::for_filename(path);
-        media.set_muted(true);
-        media.set_loop(true);
+        media.connect_timestamp_notify(clone!(@weak self as obj => move |media| {
+            let time = (media.duration() - media.timestamp()) / i64::pow(10, 6);
+            obj.update_remaining_time(time);
+        }));
         media.play();
 
-        if !imp.is_animation.get() {
-            media.connect_timestamp_notify(clone!(@weak self as obj => move |media| {
-                let time = (media.duration() - media.timestamp()) / i64::pow(10, 6);
-                obj.update_remaining_time(time);
-            }));
-        }
-
+        imp.play_button.set_visible(false);
+        imp.media_controls.set_media_stream(Some(&media));
+        imp.media_controls.set_visible(true);
         imp.picture.set_paintable(Some(&media));
+
+        imp.media.replace(Some(media));
     }
 
-    fn update_remaining_time(&self, time: i64) {
+    fn stop_playback(&self) {
         let imp = self.imp();
-        let seconds = time % 60;
-        let minutes = (time % (60 * 60)) / 60;
-        let hours = time / (60 * 60);
 
-        if hours > 0 {
-            imp.indicator
-                .set_label(&format!("{hours}:{minutes:02}:{seconds:02}"));
-        } else {
-            imp.indicator.set_label(&format!("{minutes}:{seconds:02}"));
+        if let Some(media) = imp.media.take() {
+            media.pause();
         }
+
+        imp.play_after_download.set(false);
+        imp.media_controls.set_media_stream(gtk::MediaStream::NONE);
+        imp.media_controls.set_visible(false);
+    }
+
+    async fn download_animation(&self, file_id: i32, session: &Session) {
+        match session.download_file(file_id).await {
+            Ok(file) => {
+                self.load_animation(&file.local.path);
+            }
+            Err(e) => {
+                log::warn!("Failed to download an animation: {e:?}");
+            }
+        }
+    }
+
+    fn load_animation(&self, path: &str) {
+        let media = gtk::MediaFile::for_filename(path);
+        media.set_muted(true);
+        media.set_loop(true);
+        media.play();
+
+        self.imp().picture.set_paintable(Some(&media));
+    }
+
+    fn update_remaining_time(&self, time: i64) {
+        self.imp().indicator.set_label(&format_duration(time));
     }
 }
@@ -0,0 +1,421 @@
+use adw::prelude::*;
+use glib::clone;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, glib, CompositeTemplate};
+use tdlib::enums::MessageContent;
+use tdlib::functions;
+use tdlib::types::{File, Minithumbnail};
+
+use crate::session::content::message_row::{
+    MessageBase, MessageBaseImpl, MessageIndicators, MessageProgressRing, MessageReply,
+};
+use crate::tdlib::Message;
+use crate::utils::{format_duration, spawn};
+
+use super::base::MessageBaseExt;
+
+const MAX_REPLY_CHAR_WIDTH: i32 = 18;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageVideoNote : $MessageBase {
+        layout-manager: BoxLayout {};
+
+        Overlay overlay {
+            GestureClick {
+                button: 1;
+                released => $on_released() swapped;
+            }
+
+            Adw.Bin {
+                width-request: 200;
+                height-request: 200;
+            }
+
+            [overlay]
+            Picture picture {
+                content-fit: cover;
+                overflow: hidden;
+
+                styles ["video-note"]
+            }
+
+            [overlay]
+            $MessageProgressRing progress_ring {}
+
+            [overlay]
+            Box {
+                halign: start;
+                valign: end;
+                spacing: 3;
+
+                styles ["osd-indicator"]
+
+                Label indicator {}
+
+                Image muted_icon {
+                    icon-name: "audio-volume-muted-symbolic";
+                }
+            }
+
+            [overlay]
+            $MessageIndicators indicators {
+                halign: end;
+                valign: end;
+            }
+        }
+    }
+    "#)]
+    pub(crate) struct MessageVideoNote {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) reply: RefCell<Option<MessageReply>>,
+        pub(super) file: RefCell<Option<File>>,
+        pub(super) duration: Cell<i32>,
+        pub(super) media: RefCell<Option<gtk::MediaFile>>,
+        #[template_child]
+        pub(super) overlay: TemplateChild<gtk::Overlay>,
+        #[template_child]
+        pub(super) picture: TemplateChild<gtk::Picture>,
+        #[template_child]
+        pub(super) progress_ring: TemplateChild<MessageProgressRing>,
+        #[template_child]
+        pub(super) indicator: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) muted_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub(super) indicators: TemplateChild<MessageIndicators>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageVideoNote {
+        const NAME: &'static str = "MessageVideoNote";
+        type Type = super::MessageVideoNote;
+        type ParentType = MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_css_name("messagevideonote");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageVideoNote {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            // Only play video notes while they are visible
+            obj.connect_map(|obj| obj.start_autoplay());
+            obj.connect_unmap(|obj| obj.stop_sound_playback(false));
+        }
+
+        fn dispose(&self) {
+            if let Some(media) = self.media.take() {
+                media.pause();
+            }
+        }
+    }
+
+    impl WidgetImpl for MessageVideoNote {}
+    impl MessageBaseImpl for MessageVideoNote {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageVideoNote(ObjectSubclass<imp::MessageVideoNote>)
+        @extends gtk::Widget, MessageBase;
+}
+
+impl MessageBaseExt for MessageVideoNote {
+    type Message = Message;
+
+    fn set_message(&self, message: Self::Message) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == Some(&message) {
+            return;
+        }
+
+        if let Some(old_message) = imp.message.take() {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        imp.indicators.set_message(message.clone().upcast());
+        self.update_reply(&message);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message, _| {
+                obj.update_content(message.content().0);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+
+        imp.message.replace(Some(message.clone()));
+        self.update_content(message.content().0);
+
+        self.notify("message");
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessageVideoNote {
+    #[template_callback]
+    fn on_released(&self, _n_press: i32, _x: f64, _y: f64) {
+        let media = self.imp().media.borrow().clone();
+
+        match media {
+            Some(media) if media.is_muted() => self.start_sound_playback(&media),
+            Some(media) => {
+                if media.is_playing() {
+                    media.pause();
+                } else {
+                    media.play();
+                }
+            }
+            // The video note is still being downloaded
+            None => self.start_autoplay(),
+        }
+    }
+
+    fn update_reply(&self, message: &Message) {
+        let imp = self.imp();
+
+        if let Some(reply) = imp.reply.take() {
+            reply.unparent();
+        }
+
+        if message.reply_to_message_id() != 0 {
+            let reply = MessageReply::new(message);
+            reply.set_valign(gtk::Align::Start);
+            reply.set_max_char_width(MAX_REPLY_CHAR_WIDTH);
+
+            // The video note and the reply should be at the opposite sides of the box
+            if message.is_outgoing() {
+                reply.insert_before(self, Some(&imp.overlay.get()));
+            } else {
+                reply.insert_after(self, Some(&imp.overlay.get()));
+            }
+
+            imp.reply.replace(Some(reply));
+        }
+    }
+
+    fn update_content(&self, content: MessageContent) {
+        if let MessageContent::MessageVideoNote(data) = content {
+            let imp = self.imp();
+            let video_note = data.video_note;
+
+            // Do not restart the playback if only other parts of the message content
+            // have changed (e.g. the video note has been viewed)
+            let is_same_file = imp
+                .file
+                .borrow()
+                .as_ref()
+                .map(|f| f.id == video_note.video.id)
+                .unwrap_or_default();
+            if is_same_file && imp.media.borrow().is_some() {
+                return;
+            }
+
+            if let Some(media) = imp.media.take() {
+                media.pause();
+            }
+
+            imp.duration.set(video_note.duration);
+            imp.progress_ring.set_progress(0.0);
+            imp.muted_icon.set_visible(true);
+            imp.indicator
+                .set_label(&format_duration(video_note.duration as i64));
+
+            self.set_minithumbnail(video_note.minithumbnail);
+
+            // Make sure that the eventual file updates of a previous video note are ignored
+            imp.file.replace(None);
+            self.update_file(video_note.video);
+
+            if self.is_mapped() {
+                self.start_autoplay();
+            }
+        }
+    }
+
+    fn set_minithumbnail(&self, minithumbnail: Option<Minithumbnail>) {
+        self.imp().picture.set_paintable(
+            minithumbnail
+                .and_then(|m| {
+                    gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data)))
+                        .ok()
+                })
+                .as_ref(),
+        );
+    }
+
+    fn update_file(&self, file: File) {
+        let imp = self.imp();
+
+        if let Some(old_file) = imp.file.borrow().as_ref() {
+            if old_file.id != file.id {
+                return;
+            }
+        }
+
+        let path = file.local.path.clone();
+        let is_downloaded = file.local.is_downloading_completed;
+        imp.file.replace(Some(file));
+
+        if is_downloaded && imp.media.borrow().is_none() && self.is_mapped() {
+            self.load_media(&path);
+        }
+    }
+
+    /// Starts the muted playback of the video note, downloading it first if needed.
+    fn start_autoplay(&self) {
+        let imp = self.imp();
+
+        if let Some(media) = imp.media.borrow().as_ref() {
+            if media.is_muted() {
+                media.play();
+            }
+            return;
+        }
+
+        let file = match imp.file.borrow().clone() {
+            Some(file) => file,
+            None => return,
+        };
+
+        if file.local.is_downloading_completed {
+            self.load_media(&file.local.path);
+        } else if !file.local.is_downloading_active {
+            self.message().chat().session().download_file_with_updates(
+                file.id,
+                clone!(@weak self as obj => move |file| {
+                    obj.update_file(file);
+                }),
+            );
+        }
+    }
+
+    fn load_media(&self, path: &str) {
+        let imp = self.imp();
+
+        let media = gtk::MediaFile::for_filename(path);
+        media.set_muted(true);
+        media.set_loop(true);
+        media.connect_timestamp_notify(clone!(@weak self as obj => move |media| {
+            obj.update_playback_progress(media);
+        }));
+        media.connect_ended_notify(clone!(@weak self as obj => move |media| {
+            if media.is_ended() && !media.is_muted() {
+                obj.stop_sound_playback(true);
+            }
+        }));
+        media.play();
+
+        imp.picture.set_paintable(Some(&media));
+        imp.media.replace(Some(media));
+    }
+
+    fn start_sound_playback(&self, media: &gtk::MediaFile) {
+        let imp = self.imp();
+
+        media.set_loop(false);
+        media.set_muted(false);
+        media.seek(0);
+        media.play();
+
+        imp.muted_icon.set_visible(false);
+
+        let message = self.message();
+        let client_id = message.chat().session().client_id();
+        let chat_id = message.chat().id();
+        let message_id = message.id();
+        spawn(async move {
+            if let Err(e) = functions::open_message_content(chat_id, message_id, client_id).await {
+                log::warn!("Error opening a video note: {e:?}");
+            }
+        });
+    }
+
+    /// Goes back to the muted looping playback, which is only resumed if `resume` is true.
+    fn stop_sound_playback(&self, resume: bool) {
+        let imp = self.imp();
+
+        if let Some(media) = imp.media.borrow().as_ref() {
+            media.pause();
+
+            if !media.is_muted() {
+                media.set_muted(true);
+                media.set_loop(true);
+                media.seek(0);
+            }
+
+            if resume {
+                media.play();
+            }
+        }
+
+        imp.progress_ring.set_progress(0.0);
+        imp.muted_icon.set_visible(true);
+        imp.indicator
+            .set_label(&format_duration(imp.duration.get() as i64));
+    }
+
+    fn update_playback_progress(&self, media: &gtk::MediaFile) {
+        if media.is_muted() {
+            return;
+        }
+
+        let imp = self.imp();
+
+        let duration = if media.duration() > 0 {
+            media.duration()
+        } else {
+            imp.duration.get() as i64 * i64::pow(10, 6)
+        };
+        let timestamp = media.timestamp();
+
+        if duration > 0 {
+            imp.progress_ring
+                .set_progress((timestamp as f64 / duration as f64).clamp(0.0, 1.0));
+        }
+
+        imp.indicator
+            .set_label(&format_duration((duration - timestamp) / i64::pow(10, 6)));
+    }
+}
@@ -0,0 +1,206 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+use tdlib::enums::MessageContent;
+
+use crate::session::content::message_row::{
+    MessageBase, MessageBaseImpl, MessageBubble, MessageWaveform,
+};
+use crate::tdlib::Message;
+
+use super::audio_playback::{AudioPlayback, AudioPlaybackRow};
+use super::base::MessageBaseExt;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageVoiceNote : $MessageBase {
+        $MessageBubble message_bubble {
+            styles ["voice-note"]
+
+            prefix: Box {
+                spacing: 9;
+
+                styles ["file"]
+
+                Button play_button {
+                    valign: center;
+                    icon-name: "media-playback-start-symbolic";
+                    clicked => $on_play_button_clicked() swapped;
+
+                    styles ["circular", "play-button"]
+                }
+
+                Box {
+                    orientation: vertical;
+                    valign: center;
+                    hexpand: true;
+                    spacing: 3;
+
+                    $MessageWaveform waveform {}
+
+                    Label time_label {
+                        xalign: 0;
+
+                        styles ["numeric", "dim-label", "caption"]
+                    }
+                }
+            };
+        }
+    }
+    "#)]
+    pub(crate) struct MessageVoiceNote {
+        pub(super) handler_id: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) message: RefCell<Option<Message>>,
+        pub(super) playback: AudioPlayback,
+        #[template_child]
+        pub(super) message_bubble: TemplateChild<MessageBubble>,
+        #[template_child]
+        pub(super) play_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) waveform: TemplateChild<MessageWaveform>,
+        #[template_child]
+        pub(super) time_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageVoiceNote {
+        const NAME: &'static str = "MessageVoiceNote";
+        type Type = super::MessageVoiceNote;
+        type ParentType = MessageBase;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_instance_callbacks();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageVoiceNote {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "message" => self.message.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            self.waveform
+                .connect_seek(clone!(@weak obj => move |_, progress| {
+                    obj.seek(progress);
+                }));
+        }
+
+        fn dispose(&self) {
+            self.playback.stop();
+        }
+    }
+
+    impl WidgetImpl for MessageVoiceNote {}
+    impl MessageBaseImpl for MessageVoiceNote {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageVoiceNote(ObjectSubclass<imp::MessageVoiceNote>)
+        @extends gtk::Widget, MessageBase;
+}
+
+impl MessageBaseExt for MessageVoiceNote {
+    type Message = Message;
+
+    fn set_message(&self, message: Self::Message) {
+        let imp = self.imp();
+
+        if imp.message.borrow().as_ref() == Some(&message) {
+            return;
+        }
+
+        if let Some(old_message) = imp.message.take() {
+            let handler_id = imp.handler_id.take().unwrap();
+            old_message.disconnect(handler_id);
+        }
+
+        // Stop the playback of the previous voice note
+        imp.playback.stop();
+        imp.waveform.set_progress(0.0);
+
+        imp.message_bubble.update_from_message(&message, false);
+
+        let handler_id =
+            message.connect_content_notify(clone!(@weak self as obj => move |message, _| {
+                obj.update_content(message);
+            }));
+        imp.handler_id.replace(Some(handler_id));
+        self.update_content(&message);
+
+        imp.message.replace(Some(message));
+        self.notify("message");
+    }
+}
+
+impl AudioPlaybackRow for MessageVoiceNote {
+    fn playback(&self) -> &AudioPlayback {
+        &self.imp().playback
+    }
+
+    fn play_button(&self) -> &gtk::Button {
+        &self.imp().play_button
+    }
+
+    fn time_label(&self) -> &gtk::Label {
+        &self.imp().time_label
+    }
+
+    fn set_playback_progress(&self, progress: f64) {
+        self.imp().waveform.set_progress(progress);
+    }
+}
+
+#[gtk::template_callbacks]
+impl MessageVoiceNote {
+    #[template_callback]
+    fn on_play_button_clicked(&self) {
+        self.toggle_playback();
+    }
+
+    fn update_content(&self, message: &Message) {
+        if let MessageContent::MessageVoiceNote(data) = message.content().0 {
+            let imp = self.imp();
+
+            imp.message_bubble.set_formatted_text(data.caption);
+            imp.waveform.set_waveform(&data.voice_note.waveform);
+
+            self.set_audio_file(data.voice_note.voice, data.voice_note.duration);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, glib, graphene, gsk};
+
+const BAR_WIDTH: f32 = 3.0;
+const BAR_SPACING: f32 = 2.0;
+const MIN_BAR_HEIGHT: f32 = 2.0;
+const HEIGHT: i32 = 24;
+const MIN_WIDTH: i32 = 60;
+const NATURAL_WIDTH: i32 = 180;
+
+mod imp {
+    use super::*;
+    use glib::subclass::Signal;
+    use once_cell::sync::Lazy;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, Default)]
+    pub(crate) struct MessageWaveform {
+        pub(super) samples: RefCell<Vec<f32>>,
+        pub(super) progress: Cell<f64>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageWaveform {
+        const NAME: &'static str = "MessageWaveform";
+        type Type = super::MessageWaveform;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("messagewaveform");
+        }
+    }
+
+    impl ObjectImpl for MessageWaveform {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+                vec![Signal::builder("seek")
+                    .param_types([f64::static_type()])
+                    .build()]
+            });
+            SIGNALS.as_ref()
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecDouble::builder("progress")
+                    .minimum(0.0)
+                    .maximum(1.0)
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "progress" => obj.set_progress(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "progress" => obj.progress().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let click = gtk::GestureClick::new();
+            click.connect_released(clone!(@weak obj => move |_, _, x, _| {
+                obj.emit_seek(x);
+            }));
+            obj.add_controller(click);
+
+            let drag = gtk::GestureDrag::new();
+            drag.connect_drag_update(clone!(@weak obj => move |drag, offset_x, _| {
+                if let Some((start_x, _)) = drag.start_point() {
+                    obj.emit_seek(start_x + offset_x);
+                }
+            }));
+            obj.add_controller(drag);
+
+            obj.set_cursor_from_name(Some("pointer"));
+        }
+    }
+
+    impl WidgetImpl for MessageWaveform {
+        fn measure(&self, orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
+            if let gtk::Orientation::Horizontal = orientation {
+                (MIN_WIDTH, NATURAL_WIDTH, -1, -1)
+            } else {
+                (HEIGHT, HEIGHT, -1, -1)
+            }
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+            let width = obj.width() as f32;
+            let height = obj.height() as f32;
+
+            let samples = self.samples.borrow();
+            let n_bars = ((width + BAR_SPACING) / (BAR_WIDTH + BAR_SPACING)).floor() as usize;
+            if n_bars == 0 {
+                return;
+            }
+
+            let color = obj.color();
+            let dimmed_color = gdk::RGBA::new(
+                color.red(),
+                color.green(),
+                color.blue(),
+                color.alpha() * 0.4,
+            );
+            let played_bars = (self.progress.get() * n_bars as f64).round() as usize;
+
+            for index in 0..n_bars {
+                let value = if samples.is_empty() {
+                    0.0
+                } else {
+                    samples[index * samples.len() / n_bars]
+                };
+
+                let bar_height = (value * height).max(MIN_BAR_HEIGHT);
+                let x = index as f32 * (BAR_WIDTH + BAR_SPACING);
+                let y = (height - bar_height) / 2.0;
+
+                let rect = graphene::Rect::new(x, y, BAR_WIDTH, bar_height);
+                let rounded_rect = gsk::RoundedRect::from_rect(rect, BAR_WIDTH / 2.0);
+
+                snapshot.push_rounded_clip(&rounded_rect);
+                snapshot.append_color(
+                    if index < played_bars {
+                        &color
+                    } else {
+                        &dimmed_color
+                    },
+                    &rect,
+                );
+                snapshot.pop();
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageWaveform(ObjectSubclass<imp::MessageWaveform>)
+        @extends gtk::Widget;
+}
+
+impl Default for MessageWaveform {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl MessageWaveform {
+    /// Sets the waveform from the base64 encoded data provided by TDLib, which is a sequence
+    /// of 5-bit values.
+    pub(crate) fn set_waveform(&self, waveform: &str) {
+        let data = glib::base64_decode(waveform);
+        let n_samples = data.len() * 8 / 5;
+
+        let samples = (0..n_samples)
+            .map(|index| {
+                let bit_offset = index * 5;
+                let byte_index = bit_offset / 8;
+                let value = data[byte_index] as u16
+                    | (data.get(byte_index + 1).copied().unwrap_or_default() as u16) << 8;
+
+                ((value >> (bit_offset % 8)) & 31) as f32 / 31.0
+            })
+            .collect();
+
+        self.imp().samples.replace(samples);
+        self.queue_draw();
+    }
+
+    pub(crate) fn progress(&self) -> f64 {
+        self.imp().progress.get()
+    }
+
+    pub(crate) fn set_progress(&self, progress: f64) {
+        if self.progress() == progress {
+            return;
+        }
+
+        self.imp().progress.set(progress);
+        self.queue_draw();
+
+        self.notify("progress");
+    }
+
+    pub(crate) fn connect_seek<F: Fn(&Self, f64) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("seek", true, move |values| {
+            let obj = values[0].get().unwrap();
+            let progress = values[1].get().unwrap();
+            f(obj, progress);
+            None
+        })
+    }
+
+    fn emit_seek(&self, x: f64) {
+        let width = self.width();
+        if width > 0 {
+            let progress = (x / width as f64).clamp(0.0, 1.0);
+            self.emit_by_name::<()>("seek", &[&progress]);
+        }
+    }
+}
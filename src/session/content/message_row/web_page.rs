@@ -0,0 +1,194 @@
+use glib::clone;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gio, glib, CompositeTemplate};
+use tdlib::types::{Photo, WebPage};
+
+use crate::session::content::message_row::MediaPicture;
+use crate::utils::{decode_image_from_path, parse_formatted_text, spawn};
+use crate::Session;
+
+mod imp {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $MessageWebPage {
+        Box content {
+            spacing: 6;
+
+            Separator {
+                orientation: vertical;
+            }
+
+            Box {
+                orientation: vertical;
+                spacing: 3;
+                hexpand: true;
+
+                Label site_name_label {
+                    xalign: 0;
+                    ellipsize: end;
+
+                    styles ["caption-heading"]
+                }
+
+                Label title_label {
+                    xalign: 0;
+                    wrap: true;
+                    wrap-mode: word_char;
+                    lines: 2;
+                    ellipsize: end;
+
+                    styles ["heading"]
+                }
+
+                Label description_label {
+                    xalign: 0;
+                    wrap: true;
+                    wrap-mode: word_char;
+                    lines: 4;
+                    ellipsize: end;
+                    use-markup: true;
+                }
+
+                $MessageMediaPicture picture {
+                    visible: false;
+                }
+            }
+        }
+    }
+    "#)]
+    pub(crate) struct MessageWebPage {
+        pub(super) url: RefCell<String>,
+        pub(super) session: glib::WeakRef<Session>,
+        #[template_child]
+        pub(super) content: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) site_name_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) description_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) picture: TemplateChild<MediaPicture>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageWebPage {
+        const NAME: &'static str = "MessageWebPage";
+        type Type = super::MessageWebPage;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+            klass.set_css_name("messagewebpage");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageWebPage {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_cursor_from_name(Some("pointer"));
+
+            let click = gtk::GestureClick::new();
+            click.connect_released(clone!(@weak obj => move |click, _, _, _| {
+                click.set_state(gtk::EventSequenceState::Claimed);
+                obj.open();
+            }));
+            obj.add_controller(click);
+        }
+
+        fn dispose(&self) {
+            self.content.unparent();
+        }
+    }
+
+    impl WidgetImpl for MessageWebPage {}
+}
+
+glib::wrapper! {
+    pub(crate) struct MessageWebPage(ObjectSubclass<imp::MessageWebPage>)
+        @extends gtk::Widget;
+}
+
+impl MessageWebPage {
+    pub(crate) fn new(web_page: WebPage, session: &Session) -> Self {
+        let obj: Self = glib::Object::new();
+        let imp = obj.imp();
+
+        imp.site_name_label.set_label(&web_page.site_name);
+        imp.site_name_label
+            .set_visible(!web_page.site_name.is_empty());
+        imp.title_label.set_label(&web_page.title);
+        imp.title_label.set_visible(!web_page.title.is_empty());
+
+        let description = parse_formatted_text(web_page.description);
+        imp.description_label.set_visible(!description.is_empty());
+        imp.description_label.set_label(&description);
+
+        imp.url.replace(web_page.url);
+        imp.session.set(Some(session));
+
+        if let Some(photo) = web_page.photo {
+            obj.load_photo(photo, session);
+        }
+
+        obj
+    }
+
+    fn load_photo(&self, mut photo: Photo, session: &Session) {
+        // Use a medium sized photo, as the card is never too big
+        let photo_size = match photo.sizes.iter().position(|s| s.r#type == "x") {
+            Some(pos) => photo.sizes.swap_remove(pos),
+            None => match photo.sizes.pop() {
+                Some(photo_size) => photo_size,
+                None => return,
+            },
+        };
+
+        let picture = &*self.imp().picture;
+        picture.set_aspect_ratio(photo_size.width as f64 / photo_size.height as f64);
+        picture.set_visible(true);
+
+        let file_id = photo_size.photo.id;
+        spawn(clone!(@weak self as obj, @weak session => async move {
+            let path = if photo_size.photo.local.is_downloading_completed {
+                photo_size.photo.local.path
+            } else {
+                match session.download_file(file_id).await {
+                    Ok(file) => file.local.path,
+                    Err(e) => {
+                        log::warn!("Failed to download a web page photo: {e:?}");
+                        return;
+                    }
+                }
+            };
+
+            let result = gio::spawn_blocking(move || decode_image_from_path(&path))
+                .await
+                .unwrap();
+
+            match result {
+                Ok(texture) => obj.imp().picture.set_paintable(Some(&texture)),
+                Err(e) => log::warn!("Error decoding a web page photo: {e:?}"),
+            }
+        }));
+    }
+
+    fn open(&self) {
+        let imp = self.imp();
+
+        if let Some(session) = imp.session.upgrade() {
+            session.open_link(imp.url.borrow().clone());
+        }
+    }
+}
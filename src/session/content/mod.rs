@@ -3,19 +3,33 @@ mod chat_history;
 mod chat_history_item;
 mod chat_history_model;
 mod chat_history_row;
+mod chat_history_selection;
 mod chat_info_window;
+mod chat_pinned_messages_bar;
+mod chat_search_bar;
+mod chat_shared_media;
+mod choose_chat_dialog;
 mod event_row;
+mod forward_messages_dialog;
+mod media_viewer;
 mod message_row;
 mod send_photo_dialog;
 
 use self::chat_action_bar::ChatActionBar;
 use self::chat_history::ChatHistory;
-use self::chat_history_item::{ChatHistoryItem, ChatHistoryItemType};
+use self::chat_history_item::{album_id_of_message, ChatHistoryItem, ChatHistoryItemType};
 use self::chat_history_model::{ChatHistoryError, ChatHistoryModel};
 use self::chat_history_row::ChatHistoryRow;
+use self::chat_history_selection::ChatHistorySelection;
 use self::chat_info_window::ChatInfoWindow;
+use self::chat_pinned_messages_bar::ChatPinnedMessagesBar;
+use self::chat_search_bar::ChatSearchBar;
+use self::chat_shared_media::ChatSharedMedia;
+use self::choose_chat_dialog::ChooseChatDialog;
 use self::event_row::EventRow;
-use self::message_row::MessageRow;
+use self::forward_messages_dialog::ForwardMessagesDialog;
+use self::media_viewer::MediaViewer;
+use self::message_row::{can_send_messages_in_chat, MessageRow};
 use self::send_photo_dialog::SendPhotoDialog;
 
 use gtk::glib;
@@ -120,6 +134,16 @@ impl Content {
         glib::Object::new()
     }
 
+    pub(crate) fn show_message(&self, message_id: i64) {
+        self.imp()
+            .chat_history
+            .activate_action(
+                "chat-history.scroll-to-message",
+                Some(&message_id.to_variant()),
+            )
+            .unwrap();
+    }
+
     pub(crate) fn handle_paste_action(&self) {
         self.imp().chat_history.handle_paste_action();
     }
@@ -6,13 +6,13 @@ mod sidebar;
 use self::contacts_window::ContactsWindow;
 use self::content::Content;
 use self::preferences_window::PreferencesWindow;
-use self::sidebar::Sidebar;
+use self::sidebar::{search_known_chats, SearchItemRow, Sidebar};
 
 use glib::{clone, Sender};
 use gtk::glib::WeakRef;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use gtk::{glib, CompositeTemplate};
+use gtk::{gio, glib, CompositeTemplate};
 use std::collections::hash_map::{Entry, HashMap};
 use tdlib::enums::{self, ChatList as TdChatList, NotificationSettingsScope, Update};
 use tdlib::functions;
@@ -31,6 +31,7 @@ pub(crate) struct BoxedDatabaseInfo(pub(crate) DatabaseInfo);
 mod imp {
     use super::*;
     use adw::subclass::prelude::BinImpl;
+    use glib::subclass::Signal;
     use once_cell::sync::{Lazy, OnceCell};
     use std::cell::{Cell, RefCell};
 
@@ -104,6 +105,12 @@ mod imp {
     }
 
     impl ObjectImpl for Session {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> =
+                Lazy::new(|| vec![Signal::builder("contacts-changed").build()]);
+            SIGNALS.as_ref()
+        }
+
         fn properties() -> &'static [glib::ParamSpec] {
             static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
                 vec![
@@ -225,6 +232,7 @@ impl Session {
             }
             Update::ChatReadInbox(ref data) => self.chat(data.chat_id).handle_update(update),
             Update::ChatReadOutbox(ref data) => self.chat(data.chat_id).handle_update(update),
+            Update::ChatReplyMarkup(ref data) => self.chat(data.chat_id).handle_update(update),
             Update::ChatDraftMessage(ref data) => {
                 let chat = self.chat(data.chat_id);
                 for position in &data.positions {
@@ -244,6 +252,7 @@ impl Session {
             Update::ChatAction(ref data) => self.chat(data.chat_id).handle_update(update),
             Update::MessageContent(ref data) => self.chat(data.chat_id).handle_update(update),
             Update::MessageEdited(ref data) => self.chat(data.chat_id).handle_update(update),
+            Update::MessageIsPinned(ref data) => self.chat(data.chat_id).handle_update(update),
             Update::MessageInteractionInfo(ref data) => {
                 self.chat(data.chat_id).handle_update(update)
             }
@@ -313,11 +322,22 @@ impl Session {
             },
             Update::User(data) => {
                 let mut users = self.imp().users.borrow_mut();
-                match users.entry(data.user.id) {
-                    Entry::Occupied(entry) => entry.get().update(data.user),
+                let is_contact_changed = match users.entry(data.user.id) {
+                    Entry::Occupied(entry) => {
+                        let user = entry.get();
+                        let was_contact = user.is_contact();
+                        user.update(data.user);
+                        was_contact != user.is_contact()
+                    }
                     Entry::Vacant(entry) => {
                         entry.insert(User::from_td_object(data.user, self));
+                        false
                     }
+                };
+                drop(users);
+
+                if is_contact_changed {
+                    self.emit_by_name::<()>("contacts-changed", &[]);
                 }
             }
             Update::UserStatus(data) => {
@@ -428,6 +448,17 @@ impl Session {
         })
     }
 
+    pub(crate) fn connect_contacts_changed<F: Fn(&Self) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("contacts-changed", true, move |values| {
+            let obj = values[0].get().unwrap();
+            f(obj);
+            None
+        })
+    }
+
     /// Downloads a file of the specified id. This will only return when the file
     /// downloading has completed or has failed.
     pub(crate) async fn download_file(&self, file_id: i32) -> Result<File, TdError> {
@@ -489,6 +520,62 @@ impl Session {
         imp.leaflet.navigate(adw::NavigationDirection::Forward);
     }
 
+    /// Opens a link, either in the app if it's an internal Telegram link that we can handle
+    /// (e.g. a t.me link to a public chat) or in the default browser otherwise.
+    pub(crate) fn open_link(&self, url: String) {
+        spawn(clone!(@weak self as obj => async move {
+            let client_id = obj.client_id();
+
+            // The chat to open and, for message links, the message to show in it
+            let target = match functions::get_internal_link_type(url.clone(), client_id).await {
+                Ok(enums::InternalLinkType::PublicChat(data)) => {
+                    match functions::search_public_chat(data.chat_username, client_id).await {
+                        Ok(enums::Chat::Chat(chat)) => Some((chat.id, None)),
+                        Err(e) => {
+                            log::warn!("Error searching a public chat: {:?}", e);
+                            None
+                        }
+                    }
+                }
+                Ok(enums::InternalLinkType::Message(data)) => {
+                    match functions::get_message_link_info(data.url, client_id).await {
+                        Ok(enums::MessageLinkInfo::MessageLinkInfo(info)) if info.chat_id != 0 => {
+                            Some((info.chat_id, info.message.map(|m| m.id)))
+                        }
+                        Ok(_) => None,
+                        Err(e) => {
+                            log::warn!("Error getting message link info: {:?}", e);
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+
+            match target {
+                Some((chat_id, message_id)) => {
+                    obj.select_chat(chat_id);
+
+                    if let Some(message_id) = message_id {
+                        obj.show_message(message_id);
+                    }
+                }
+                None => {
+                    if let Err(e) =
+                        gio::AppInfo::launch_default_for_uri(&url, gio::AppLaunchContext::NONE)
+                    {
+                        log::warn!("Error opening a link: {:?}", e);
+                    }
+                }
+            }
+        }));
+    }
+
+    /// Scrolls the chat history of the selected chat to the message with the given id.
+    pub(crate) fn show_message(&self, message_id: i64) {
+        self.imp().content.show_message(message_id);
+    }
+
     pub(crate) fn handle_paste_action(&self) {
         self.imp().content.handle_paste_action();
     }
@@ -20,6 +20,7 @@ use crate::utils::spawn;
 use crate::Session;
 
 pub(crate) use self::avatar::Avatar;
+pub(crate) use self::search::{search_known_chats, ItemRow as SearchItemRow};
 
 mod imp {
     use super::*;
@@ -0,0 +1,266 @@
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, CompositeTemplate};
+
+use crate::session::sidebar::row::timestamp_text;
+use crate::session::sidebar::Search;
+use crate::strings;
+use crate::tdlib::{ChatType, Message};
+
+/// The number of characters shown before the first occurrence of the query in a snippet.
+const SNIPPET_CONTEXT_CHARS: usize = 15;
+
+mod imp {
+    use super::*;
+    use once_cell::sync::Lazy;
+    use std::cell::RefCell;
+
+    use crate::components::Avatar;
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(string = r#"
+    template $SidebarSearchMessageRow {
+        $ComponentsAvatar avatar {
+            size: 32;
+        }
+
+        Box labels_box {
+            orientation: vertical;
+            hexpand: true;
+
+            Box {
+                spacing: 6;
+
+                Inscription chat_label {
+                    hexpand: true;
+                    text-overflow: ellipsize_end;
+                }
+
+                Label date_label {
+                    styles [
+                        "dim-label",
+                        "caption",
+                        "numeric",
+                    ]
+                }
+            }
+
+            Inscription message_label {
+                styles ["dim-label"]
+
+                text-overflow: ellipsize_end;
+            }
+        }
+    }
+    "#)]
+    pub(crate) struct MessageRow {
+        pub(super) message: RefCell<Option<Message>>,
+        #[template_child]
+        pub(super) avatar: TemplateChild<Avatar>,
+        #[template_child]
+        pub(super) labels_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub(super) chat_label: TemplateChild<gtk::Inscription>,
+        #[template_child]
+        pub(super) date_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) message_label: TemplateChild<gtk::Inscription>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MessageRow {
+        const NAME: &'static str = "SidebarSearchMessageRow";
+        type Type = super::MessageRow;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+
+            klass.set_css_name("sidebarsearchitemrow");
+            klass.set_layout_manager_type::<gtk::BoxLayout>();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MessageRow {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+                vec![glib::ParamSpecObject::builder::<Message>("message")
+                    .explicit_notify()
+                    .build()]
+            });
+            PROPERTIES.as_ref()
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.set_message(value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "message" => obj.message().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn dispose(&self) {
+            self.avatar.unparent();
+            self.labels_box.unparent();
+        }
+    }
+
+    impl WidgetImpl for MessageRow {
+        fn root(&self) {
+            self.parent_root();
+
+            // The query to highlight is only known once the row is in the search widget
+            self.obj().update_message_label();
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A row of the sidebar search showing a message found by the global message search.
+    pub(crate) struct MessageRow(ObjectSubclass<imp::MessageRow>)
+        @extends gtk::Widget;
+}
+
+impl MessageRow {
+    pub(crate) fn new(message: &Message) -> Self {
+        glib::Object::builder().property("message", message).build()
+    }
+
+    pub(crate) fn message(&self) -> Option<Message> {
+        self.imp().message.borrow().clone()
+    }
+
+    pub(crate) fn set_message(&self, message: Option<Message>) {
+        if self.message() == message {
+            return;
+        }
+
+        let imp = self.imp();
+
+        if let Some(ref message) = message {
+            let chat = message.chat();
+
+            imp.avatar.set_item(Some(chat.clone().upcast()));
+            imp.chat_label.set_text(Some(&if chat.is_own_chat() {
+                gettext("Saved Messages")
+            } else {
+                chat.title()
+            }));
+            imp.date_label
+                .set_label(&timestamp_text(message.date() as i64));
+        }
+
+        imp.message.replace(message);
+
+        self.update_message_label();
+        self.notify("message");
+    }
+
+    fn update_message_label(&self) {
+        let imp = self.imp();
+
+        let message = match self.message() {
+            Some(message) => message,
+            None => {
+                imp.message_label.set_text(None);
+                return;
+            }
+        };
+
+        let query = self
+            .ancestor(Search::static_type())
+            .and_then(|search| search.downcast::<Search>().ok())
+            .map(|search| search.query())
+            .unwrap_or_default();
+
+        let content = strings::message_content(&message).replace('\n', " ");
+        let snippet = highlight_query(&content, &query);
+
+        // Only show the sender in chats with several senders
+        let show_sender = match message.chat().type_() {
+            ChatType::BasicGroup(_) => true,
+            ChatType::Supergroup(data) => !data.is_channel(),
+            _ => false,
+        };
+
+        let markup = if show_sender {
+            let sender = if message.is_outgoing() {
+                gettext("You")
+            } else {
+                strings::message_sender(message.sender(), false)
+            };
+
+            format!("{}: {}", glib::markup_escape_text(&sender), snippet)
+        } else {
+            snippet
+        };
+
+        imp.message_label.set_markup(Some(&markup));
+    }
+}
+
+/// Returns the markup of the text with the occurrences of the query in bold. If the first
+/// occurrence is far from the beginning of the text, the text is trimmed so that it's visible.
+fn highlight_query(text: &str, query: &str) -> String {
+    let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+
+    let query: Vec<char> = query.trim().chars().map(fold).collect();
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+    if query.is_empty() {
+        return glib::markup_escape_text(text).into();
+    }
+
+    let mut markup = String::new();
+    let mut last_end = 0;
+    let mut index = 0;
+
+    while index + query.len() <= chars.len() {
+        let is_match = chars[index..index + query.len()]
+            .iter()
+            .map(|(_, c)| fold(*c))
+            .eq(query.iter().copied());
+
+        if !is_match {
+            index += 1;
+            continue;
+        }
+
+        let start = chars[index].0;
+        let end = chars
+            .get(index + query.len())
+            .map(|(i, _)| *i)
+            .unwrap_or(text.len());
+
+        if last_end == 0 && index > SNIPPET_CONTEXT_CHARS {
+            last_end = chars[index - SNIPPET_CONTEXT_CHARS].0;
+            markup.push('…');
+        }
+
+        markup.push_str(&glib::markup_escape_text(&text[last_end..start]));
+        markup.push_str("<b>");
+        markup.push_str(&glib::markup_escape_text(&text[start..end]));
+        markup.push_str("</b>");
+
+        last_end = end;
+        index += query.len();
+    }
+
+    markup.push_str(&glib::markup_escape_text(&text[last_end..]));
+    markup
+}
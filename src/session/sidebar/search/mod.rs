@@ -1,9 +1,11 @@
 mod item_row;
+mod message_row;
 mod row;
 mod section;
 mod section_row;
 
-use self::item_row::ItemRow;
+pub(crate) use self::item_row::ItemRow;
+use self::message_row::MessageRow;
 use self::row::Row;
 use self::section::{Section, SectionType};
 use self::section_row::SectionRow;
@@ -16,10 +18,13 @@ use gtk::{gio, glib, CompositeTemplate};
 use tdlib::{enums, functions};
 
 use crate::session::Sidebar;
-use crate::tdlib::{Chat, User};
+use crate::tdlib::{Chat, Message, User};
 use crate::utils::spawn;
 use crate::Session;
 
+const MESSAGES_PAGE_SIZE: i32 = 30;
+const MAX_KNOWN_CHATS: i32 = 50;
+
 mod imp {
     use super::*;
     use glib::subclass::Signal;
@@ -31,6 +36,10 @@ mod imp {
     pub(crate) struct Search {
         pub(super) session: RefCell<Option<Session>>,
         pub(super) compact: Cell<bool>,
+        /// The offset date, chat id and message id of the next page of found messages, or
+        /// `None` if there are no more messages to load
+        pub(super) messages_offset: Cell<Option<(i32, i64, i64)>>,
+        pub(super) is_loading_messages: Cell<bool>,
         #[template_child]
         pub(super) toolbar_view: TemplateChild<adw::ToolbarView>,
         #[template_child]
@@ -38,6 +47,8 @@ mod imp {
         #[template_child]
         pub(super) stack: TemplateChild<gtk::Stack>,
         #[template_child]
+        pub(super) scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
         pub(super) selection: TemplateChild<gtk::NoSelection>,
     }
 
@@ -120,6 +131,19 @@ mod imp {
             }
         }
 
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            // Load more found messages when reaching the end of the results
+            self.scrolled_window.vadjustment().connect_value_changed(
+                clone!(@weak obj => move |_| {
+                    obj.load_more_messages_if_needed();
+                }),
+            );
+        }
+
         fn dispose(&self) {
             self.dispose_template();
         }
@@ -166,6 +190,11 @@ impl Search {
         self.notify("session");
     }
 
+    /// Returns the current search query.
+    pub(crate) fn query(&self) -> String {
+        self.imp().search_entry.text().trim().to_string()
+    }
+
     pub(crate) fn compact(&self) -> bool {
         self.imp().compact.get()
     }
@@ -186,7 +215,8 @@ impl Search {
         let list = gio::ListStore::new(glib::Object::static_type());
         let mut found_chat_ids: Vec<i64> = vec![];
 
-        const MAX_KNOWN_CHATS: i32 = 50;
+        imp.messages_offset.set(None);
+        imp.is_loading_messages.set(false);
 
         imp.selection.set_model(Some(&list));
         list.connect_items_changed(clone!(@weak self as obj => move |list, _, _, _| {
@@ -201,43 +231,17 @@ impl Search {
         // before even starting the search.
         imp.stack.set_visible_child_name("results");
 
-        if !query.is_empty()
-            && gettext("Saved Messages")
-                .to_lowercase()
-                .contains(&query.to_lowercase())
-        {
-            let own_user_id = session.me().id();
-            if let Some(own_chat) = session.try_chat(own_user_id) {
-                list.append(&Section::new(SectionType::Chats));
+        // Search the known chats (or get the recently found chats if the query is empty)
+        let chats = search_known_chats(&session, &query, MAX_KNOWN_CHATS).await;
+        if !chats.is_empty() {
+            list.append(&Section::new(if query.is_empty() {
+                SectionType::Recent
+            } else {
+                SectionType::Chats
+            }));
 
-                found_chat_ids.push(own_user_id);
-                list.append(&own_chat);
-            }
-        }
-
-        // Search chats locally (or get the recently found chats if the query is empty)
-        match functions::search_chats(query.clone(), 30, session.client_id()).await {
-            Ok(enums::Chats::Chats(mut data)) if !data.chat_ids.is_empty() => {
-                list.append(&Section::new(if query.is_empty() {
-                    SectionType::Recent
-                } else {
-                    SectionType::Chats
-                }));
-
-                let chats: Vec<Chat> = data
-                    .chat_ids
-                    .iter()
-                    .filter(|id| !found_chat_ids.contains(id))
-                    .map(|id| session.chat(*id))
-                    .collect();
-
-                found_chat_ids.append(&mut data.chat_ids);
-                list.extend_from_slice(&chats);
-            }
-            Err(e) => {
-                log::warn!("Error searching chats: {:?}", e);
-            }
-            _ => {}
+            found_chat_ids.extend(chats.iter().map(Chat::id));
+            list.extend_from_slice(&chats);
         }
 
         // Show the empty page if there are no results after the first part of the search
@@ -251,103 +255,161 @@ impl Search {
             return;
         }
 
-        // Search known chats on server
-        match functions::search_chats_on_server(
-            query.clone(),
-            MAX_KNOWN_CHATS - found_chat_ids.len() as i32,
-            session.client_id(),
-        )
-        .await
-        {
-            Ok(enums::Chats::Chats(data)) if !data.chat_ids.is_empty() => {
-                if found_chat_ids.is_empty() {
-                    list.append(&Section::new(SectionType::Chats));
-                }
-
-                let chats: Vec<Chat> = data
-                    .chat_ids
-                    .into_iter()
-                    .filter_map(|id| {
-                        if found_chat_ids.contains(&id) {
-                            None
-                        } else {
-                            found_chat_ids.push(id);
-                            Some(session.chat(id))
-                        }
-                    })
-                    .collect();
+        // Search more chats, unless enough of them have already been found
+        if (found_chat_ids.len() as i32) < MAX_KNOWN_CHATS {
+            // Search contacts
+            match functions::search_contacts(
+                query.clone(),
+                MAX_KNOWN_CHATS - found_chat_ids.len() as i32,
+                session.client_id(),
+            )
+            .await
+            {
+                Ok(enums::Users::Users(data)) if !data.user_ids.is_empty() => {
+                    if found_chat_ids.is_empty() {
+                        list.append(&Section::new(SectionType::Chats));
+                    }
 
-                list.extend_from_slice(&chats);
+                    let users: Vec<User> = data
+                        .user_ids
+                        .into_iter()
+                        .filter_map(|id| {
+                            // The user IDs are the same as their respective private chat IDs,
+                            // so we can just check for chat IDs here.
+                            if found_chat_ids.contains(&id) {
+                                None
+                            } else {
+                                found_chat_ids.push(id);
+                                Some(session.user(id))
+                            }
+                        })
+                        .collect();
+
+                    list.extend_from_slice(&users);
+                }
+                Err(e) => {
+                    log::warn!("Error searching contacts: {:?}", e);
+                }
+                _ => {}
             }
-            Err(e) => {
-                log::warn!("Error searching chats on server: {:?}", e);
+
+            // Search public chats
+            match functions::search_public_chats(query.clone(), session.client_id()).await {
+                Ok(enums::Chats::Chats(data)) if !data.chat_ids.is_empty() => {
+                    list.append(&Section::new(SectionType::Global));
+
+                    let chats: Vec<Chat> = data
+                        .chat_ids
+                        .into_iter()
+                        .filter_map(|id| {
+                            if found_chat_ids.contains(&id) {
+                                None
+                            } else {
+                                Some(session.chat(id))
+                            }
+                        })
+                        .collect();
+
+                    list.extend_from_slice(&chats);
+                }
+                Err(e) => {
+                    log::warn!("Error searching public chats: {:?}", e);
+                }
+                _ => {}
             }
-            _ => {}
         }
 
-        if found_chat_ids.len() as i32 >= MAX_KNOWN_CHATS {
+        // Search messages in all chats, if this search is still the current one
+        if imp.selection.model().as_ref() == Some(list.upcast_ref()) {
+            imp.messages_offset.set(Some((0, 0, 0)));
+            self.load_more_messages().await;
+        }
+    }
+
+    /// Loads the next page of the found messages if the end of the results is visible or
+    /// about to be.
+    fn load_more_messages_if_needed(&self) {
+        let adj = self.imp().scrolled_window.vadjustment();
+        if adj.value() + adj.page_size() * 2.0 >= adj.upper() {
+            spawn(clone!(@weak self as obj => async move {
+                obj.load_more_messages().await;
+            }));
+        }
+    }
+
+    /// Loads the next page of the messages found by the current search.
+    async fn load_more_messages(&self) {
+        let imp = self.imp();
+
+        let offset = match imp.messages_offset.get() {
+            Some(offset) => offset,
+            None => return,
+        };
+
+        if imp.is_loading_messages.get() {
             return;
         }
 
-        // Search contacts
-        match functions::search_contacts(
-            query.clone(),
-            MAX_KNOWN_CHATS - found_chat_ids.len() as i32,
+        let list = match imp
+            .selection
+            .model()
+            .and_then(|m| m.downcast::<gio::ListStore>().ok())
+        {
+            Some(list) => list,
+            None => return,
+        };
+        let session = self.session().unwrap();
+        let (offset_date, offset_chat_id, offset_message_id) = offset;
+
+        imp.is_loading_messages.set(true);
+
+        let result = functions::search_messages(
+            Some(enums::ChatList::Main),
+            self.query(),
+            offset_date,
+            offset_chat_id,
+            offset_message_id,
+            MESSAGES_PAGE_SIZE,
+            None,
+            0,
+            0,
             session.client_id(),
         )
-        .await
-        {
-            Ok(enums::Users::Users(data)) if !data.user_ids.is_empty() => {
-                if found_chat_ids.is_empty() {
-                    list.append(&Section::new(SectionType::Chats));
-                }
-
-                let users: Vec<User> = data
-                    .user_ids
-                    .into_iter()
-                    .filter_map(|id| {
-                        // The user IDs are the same as their respective private chat IDs,
-                        // so we can just check for chat IDs here.
-                        if found_chat_ids.contains(&id) {
-                            None
-                        } else {
-                            found_chat_ids.push(id);
-                            Some(session.user(id))
-                        }
-                    })
-                    .collect();
+        .await;
 
-                list.extend_from_slice(&users);
-            }
-            Err(e) => {
-                log::warn!("Error searching contacts: {:?}", e);
-            }
-            _ => {}
+        // Ignore the result if a new search was started in the meantime
+        if imp.selection.model().as_ref() != Some(list.upcast_ref()) {
+            return;
         }
 
-        // Search public chats
-        match functions::search_public_chats(query, session.client_id()).await {
-            Ok(enums::Chats::Chats(data)) if !data.chat_ids.is_empty() => {
-                list.append(&Section::new(SectionType::Global));
+        imp.is_loading_messages.set(false);
 
-                let chats: Vec<Chat> = data
-                    .chat_ids
+        match result {
+            Ok(enums::Messages::Messages(data)) => {
+                let messages: Vec<Message> = data
+                    .messages
                     .into_iter()
-                    .filter_map(|id| {
-                        if found_chat_ids.contains(&id) {
-                            None
-                        } else {
-                            Some(session.chat(id))
-                        }
-                    })
+                    .flatten()
+                    .map(|m| session.chat(m.chat_id).message_from_td_object(m))
                     .collect();
 
-                list.extend_from_slice(&chats);
+                imp.messages_offset
+                    .set(messages.last().map(|m| (m.date(), m.chat().id(), m.id())));
+
+                if offset == (0, 0, 0) && !messages.is_empty() {
+                    list.append(&Section::new(SectionType::Messages));
+                }
+
+                list.extend_from_slice(&messages);
+
+                // Keep loading until the results fill the view, as there's no scrolling
+                // to trigger the next page otherwise
+                self.load_more_messages_if_needed();
             }
             Err(e) => {
-                log::warn!("Error searching public chats: {:?}", e);
+                log::warn!("Error searching messages: {:?}", e);
+                imp.messages_offset.set(None);
             }
-            _ => {}
         }
     }
 
@@ -387,6 +449,9 @@ impl Search {
             {
                 log::warn!("Failed to add recently found chat: {:?}", e);
             }
+        } else if let Some(message) = item.downcast_ref::<Message>() {
+            sidebar.select_chat(message.chat());
+            session.show_message(message.id());
         } else {
             log::warn!("Unexpected item type: {:?}", item);
         }
@@ -394,3 +459,51 @@ impl Search {
         self.emit_by_name::<()>("close", &[]);
     }
 }
+
+/// Searches the chats known by the user, locally and on the server, or returns the
+/// recently found chats if the query is empty.
+pub(crate) async fn search_known_chats(session: &Session, query: &str, limit: i32) -> Vec<Chat> {
+    let mut chats: Vec<Chat> = vec![];
+
+    if !query.is_empty()
+        && gettext("Saved Messages")
+            .to_lowercase()
+            .contains(&query.to_lowercase())
+    {
+        if let Some(own_chat) = session.try_chat(session.me().id()) {
+            chats.push(own_chat);
+        }
+    }
+
+    // Search chats locally
+    match functions::search_chats(query.to_string(), 30, session.client_id()).await {
+        Ok(enums::Chats::Chats(data)) => append_chats(&mut chats, data.chat_ids, session),
+        Err(e) => log::warn!("Error searching chats: {:?}", e),
+    }
+
+    if query.is_empty() || chats.len() as i32 >= limit {
+        return chats;
+    }
+
+    // Search known chats on server
+    match functions::search_chats_on_server(
+        query.to_string(),
+        limit - chats.len() as i32,
+        session.client_id(),
+    )
+    .await
+    {
+        Ok(enums::Chats::Chats(data)) => append_chats(&mut chats, data.chat_ids, session),
+        Err(e) => log::warn!("Error searching chats on server: {:?}", e),
+    }
+
+    chats
+}
+
+fn append_chats(chats: &mut Vec<Chat>, chat_ids: Vec<i64>, session: &Session) {
+    for id in chat_ids {
+        if !chats.iter().any(|c| c.id() == id) {
+            chats.push(session.chat(id));
+        }
+    }
+}
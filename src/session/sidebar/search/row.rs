@@ -2,8 +2,8 @@ use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
-use crate::session::sidebar::search::{ItemRow, Section, SectionRow, SectionType};
-use crate::tdlib::{Chat, User};
+use crate::session::sidebar::search::{ItemRow, MessageRow, Section, SectionRow, SectionType};
+use crate::tdlib::{Chat, Message, User};
 
 mod imp {
     use super::*;
@@ -13,7 +13,7 @@ mod imp {
 
     #[derive(Debug, Default)]
     pub(crate) struct Row {
-        /// A `Chat` or `User`
+        /// A `Chat`, `User`, `Message` or `Section`
         pub(super) item: RefCell<Option<glib::Object>>,
         pub(super) list_item: OnceCell<gtk::ListItem>,
         pub(super) child: RefCell<Option<gtk::Widget>>,
@@ -95,6 +95,9 @@ impl Row {
         {
             imp.list_item.get().unwrap().set_activatable(true);
             self.update_or_create_item_row(item.clone());
+        } else if let Some(message) = item.as_ref().and_then(|i| i.downcast_ref::<Message>()) {
+            imp.list_item.get().unwrap().set_activatable(true);
+            self.update_or_create_message_row(message);
         } else if let Some(section) = item.as_ref().and_then(|i| i.downcast_ref::<Section>()) {
             imp.list_item.get().unwrap().set_activatable(false);
             self.update_or_create_section_row(section.section_type());
@@ -123,6 +126,10 @@ impl Row {
                 item_row.set_item(item);
             }
             None => {
+                if let Some(child) = child_ref.take() {
+                    child.unparent();
+                }
+
                 let item_row = ItemRow::new(&item);
                 item_row.set_parent(self);
                 *child_ref = Some(item_row.upcast());
@@ -130,6 +137,27 @@ impl Row {
         }
     }
 
+    fn update_or_create_message_row(&self, message: &Message) {
+        let mut child_ref = self.imp().child.borrow_mut();
+        match child_ref
+            .as_ref()
+            .and_then(|c| c.downcast_ref::<MessageRow>())
+        {
+            Some(message_row) => {
+                message_row.set_message(Some(message.clone()));
+            }
+            None => {
+                if let Some(child) = child_ref.take() {
+                    child.unparent();
+                }
+
+                let message_row = MessageRow::new(message);
+                message_row.set_parent(self);
+                *child_ref = Some(message_row.upcast());
+            }
+        }
+    }
+
     fn update_or_create_section_row(&self, section_type: SectionType) {
         let mut child_ref = self.imp().child.borrow_mut();
         match child_ref
@@ -140,6 +168,10 @@ impl Row {
                 section_row.set_section_type(section_type);
             }
             None => {
+                if let Some(child) = child_ref.take() {
+                    child.unparent();
+                }
+
                 let section_row = SectionRow::new(section_type);
                 section_row.set_parent(self);
                 *child_ref = Some(section_row.upcast());
@@ -8,6 +8,7 @@ pub(crate) enum SectionType {
     #[default]
     Chats,
     Global,
+    Messages,
     Recent,
 }
 
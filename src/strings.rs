@@ -460,6 +460,22 @@ pub(crate) fn message_content(message: &Message) -> String {
         MessageVideoNote(_) => gettext("Video Message"),
         MessageVoiceNote(data) => message_voice_note(&data.caption.text),
         MessageAnimatedEmoji(data) => data.emoji,
+        MessageLocation(data) => {
+            if data.live_period > 0 {
+                gettext("Live Location")
+            } else {
+                gettext("Location")
+            }
+        }
+        MessageVenue(data) => gettext_f("Location, {title}", &[("title", &data.venue.title)]),
+        MessageContact(data) => gettext_f(
+            "Contact, {name}",
+            &[(
+                "name",
+                format!("{} {}", data.contact.first_name, data.contact.last_name).trim(),
+            )],
+        ),
+        MessagePoll(data) => format!("📊 {}", data.poll.question),
         MessageDice(data) => data.emoji,
         MessageCall(data) => message_call(
             &data.discard_reason,
@@ -1,13 +1,13 @@
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use tdlib::enums::{ChatType as TdChatType, Update};
+use tdlib::enums::{ChatMemberStatus, ChatType as TdChatType, SearchMessagesFilter, Update};
 use tdlib::types::Chat as TelegramChat;
 use tdlib::{functions, types};
 
 use crate::tdlib::{
     Avatar, BasicGroup, BoxedChatNotificationSettings, BoxedChatPermissions, BoxedDraftMessage,
-    ChatActionList, Message, SecretChat, Supergroup, User,
+    ChatActionList, Message, MessageSender, SecretChat, Supergroup, User,
 };
 use crate::Session;
 
@@ -65,6 +65,17 @@ impl ChatType {
     }
 }
 
+/// A page of the messages found by a search in a chat.
+#[derive(Debug)]
+pub(crate) struct FoundChatMessages {
+    /// The approximate number of all the found messages
+    pub(crate) total_count: i32,
+    /// The found messages, from the newest to the oldest
+    pub(crate) messages: Vec<Message>,
+    /// The id of the message to continue the search from, or 0 if there are no more results
+    pub(crate) next_from_message_id: i64,
+}
+
 mod imp {
     use super::*;
     use glib::subclass::Signal;
@@ -81,11 +92,13 @@ mod imp {
         pub(super) is_blocked: Cell<bool>,
         pub(super) title: RefCell<String>,
         pub(super) avatar: RefCell<Option<Avatar>>,
+        pub(super) last_read_inbox_message_id: Cell<i64>,
         pub(super) last_read_outbox_message_id: Cell<i64>,
         pub(super) is_marked_as_unread: Cell<bool>,
         pub(super) last_message: RefCell<Option<Message>>,
         pub(super) unread_mention_count: Cell<i32>,
         pub(super) unread_count: Cell<i32>,
+        pub(super) reply_markup_message_id: Cell<i64>,
         pub(super) draft_message: RefCell<Option<BoxedDraftMessage>>,
         pub(super) notification_settings: RefCell<Option<BoxedChatNotificationSettings>>,
         pub(super) actions: OnceCell<ChatActionList>,
@@ -110,6 +123,7 @@ mod imp {
                     Signal::builder("deleted-message")
                         .param_types([Message::static_type()])
                         .build(),
+                    Signal::builder("pinned-messages-changed").build(),
                 ]
             });
             SIGNALS.as_ref()
@@ -129,6 +143,9 @@ mod imp {
                     glib::ParamSpecBoxed::builder::<Avatar>("avatar")
                         .read_only()
                         .build(),
+                    glib::ParamSpecInt64::builder("last-read-inbox-message-id")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecInt64::builder("last-read-outbox-message-id")
                         .read_only()
                         .build(),
@@ -144,6 +161,9 @@ mod imp {
                     glib::ParamSpecInt::builder("unread-count")
                         .read_only()
                         .build(),
+                    glib::ParamSpecInt64::builder("reply-markup-message-id")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecBoxed::builder::<BoxedDraftMessage>("draft-message")
                         .read_only()
                         .build(),
@@ -175,11 +195,13 @@ mod imp {
                 "is-blocked" => obj.is_blocked().to_value(),
                 "title" => obj.title().to_value(),
                 "avatar" => obj.avatar().to_value(),
+                "last-read-inbox-message-id" => obj.last_read_inbox_message_id().to_value(),
                 "last-read-outbox-message-id" => obj.last_read_outbox_message_id().to_value(),
                 "is-marked-as-unread" => obj.is_marked_as_unread().to_value(),
                 "last-message" => obj.last_message().to_value(),
                 "unread-mention-count" => obj.unread_mention_count().to_value(),
                 "unread-count" => obj.unread_count().to_value(),
+                "reply-markup-message-id" => obj.reply_markup_message_id().to_value(),
                 "draft-message" => obj.draft_message().to_value(),
                 "notification-settings" => obj.notification_settings().to_value(),
                 "actions" => obj.actions().to_value(),
@@ -212,12 +234,16 @@ impl Chat {
         imp.is_blocked.set(td_chat.is_blocked);
         imp.title.replace(td_chat.title);
         imp.avatar.replace(avatar);
+        imp.last_read_inbox_message_id
+            .set(td_chat.last_read_inbox_message_id);
         imp.last_read_outbox_message_id
             .set(td_chat.last_read_outbox_message_id);
         imp.is_marked_as_unread.set(td_chat.is_marked_as_unread);
         imp.last_message.replace(last_message);
         imp.unread_mention_count.set(td_chat.unread_mention_count);
         imp.unread_count.set(td_chat.unread_count);
+        imp.reply_markup_message_id
+            .set(td_chat.reply_markup_message_id);
         imp.draft_message.replace(draft_message);
         imp.notification_settings
             .replace(Some(notification_settings));
@@ -255,7 +281,13 @@ impl Chat {
                 self.set_permissions(BoxedChatPermissions(update.permissions))
             }
             ChatPhoto(update) => self.set_avatar(update.photo.map(Into::into)),
-            ChatReadInbox(update) => self.set_unread_count(update.unread_count),
+            ChatReadInbox(update) => {
+                self.set_last_read_inbox_message_id(update.last_read_inbox_message_id);
+                self.set_unread_count(update.unread_count);
+            }
+            ChatReplyMarkup(update) => {
+                self.set_reply_markup_message_id(update.reply_markup_message_id)
+            }
             ChatReadOutbox(update) => {
                 self.set_last_read_outbox_message_id(update.last_read_outbox_message_id);
             }
@@ -295,6 +327,13 @@ impl Chat {
                     message.handle_update(update);
                 }
             }
+            MessageIsPinned(ref data) => {
+                if let Some(message) = self.message(data.message_id) {
+                    message.handle_update(update);
+                }
+
+                self.emit_by_name::<()>("pinned-messages-changed", &[]);
+            }
             MessageSendSucceeded(data) => {
                 let mut messages = imp.messages.borrow_mut();
                 let old_message = messages.remove(&data.old_message_id);
@@ -304,7 +343,11 @@ impl Chat {
                 messages.insert(message_id, message.clone());
 
                 drop(messages);
-                self.emit_by_name::<()>("deleted-message", &[&old_message]);
+
+                // The pending message is only known if it was sent from this session
+                if let Some(old_message) = old_message {
+                    self.emit_by_name::<()>("deleted-message", &[&old_message]);
+                }
                 self.emit_by_name::<()>("new-message", &[&message]);
             }
             NewMessage(data) => {
@@ -374,6 +417,20 @@ impl Chat {
         self.connect_notify_local(Some("avatar"), f)
     }
 
+    pub(crate) fn last_read_inbox_message_id(&self) -> i64 {
+        self.imp().last_read_inbox_message_id.get()
+    }
+
+    fn set_last_read_inbox_message_id(&self, last_read_inbox_message_id: i64) {
+        if self.last_read_inbox_message_id() == last_read_inbox_message_id {
+            return;
+        }
+        self.imp()
+            .last_read_inbox_message_id
+            .set(last_read_inbox_message_id);
+        self.notify("last-read-inbox-message-id");
+    }
+
     pub(crate) fn last_read_outbox_message_id(&self) -> i64 {
         self.imp().last_read_outbox_message_id.get()
     }
@@ -436,6 +493,22 @@ impl Chat {
         self.notify("unread-count");
     }
 
+    /// Returns the id of the message whose reply markup is the default custom keyboard of the
+    /// chat, or 0 if there is none.
+    pub(crate) fn reply_markup_message_id(&self) -> i64 {
+        self.imp().reply_markup_message_id.get()
+    }
+
+    fn set_reply_markup_message_id(&self, reply_markup_message_id: i64) {
+        if self.reply_markup_message_id() == reply_markup_message_id {
+            return;
+        }
+        self.imp()
+            .reply_markup_message_id
+            .set(reply_markup_message_id);
+        self.notify("reply-markup-message-id");
+    }
+
     pub(crate) fn draft_message(&self) -> Option<BoxedDraftMessage> {
         self.imp().draft_message.borrow().to_owned()
     }
@@ -481,6 +554,37 @@ impl Chat {
         self.type_().user() == Some(&self.session().me())
     }
 
+    /// Returns whether the current user can pin and unpin messages in the chat.
+    pub(crate) fn can_pin_messages(&self) -> bool {
+        let can_pin_messages = self.permissions().0.can_pin_messages;
+
+        match self.type_() {
+            ChatType::Private(_) => true,
+            ChatType::Secret(_) => false,
+            ChatType::BasicGroup(basic_group) => match basic_group.status().0 {
+                ChatMemberStatus::Creator(_) => true,
+                ChatMemberStatus::Administrator(data) => data.rights.can_pin_messages,
+                ChatMemberStatus::Member => can_pin_messages,
+                _ => false,
+            },
+            ChatType::Supergroup(supergroup) => match supergroup.status().0 {
+                ChatMemberStatus::Creator(_) => true,
+                ChatMemberStatus::Administrator(data) => {
+                    if supergroup.is_channel() {
+                        data.rights.can_edit_messages
+                    } else {
+                        data.rights.can_pin_messages
+                    }
+                }
+                ChatMemberStatus::Member => !supergroup.is_channel() && can_pin_messages,
+                ChatMemberStatus::Restricted(data) => {
+                    can_pin_messages && data.permissions.can_pin_messages
+                }
+                _ => false,
+            },
+        }
+    }
+
     pub(crate) fn permissions(&self) -> BoxedChatPermissions {
         self.imp().permissions.borrow().to_owned().unwrap()
     }
@@ -517,11 +621,32 @@ impl Chat {
         })
     }
 
+    pub(crate) fn connect_pinned_messages_changed<F: Fn(&Self) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("pinned-messages-changed", true, move |values| {
+            let obj = values[0].get().unwrap();
+            f(obj);
+            None
+        })
+    }
+
     /// Returns the `Message` of the specified id, if present in the cache.
     pub(crate) fn message(&self, message_id: i64) -> Option<Message> {
         self.imp().messages.borrow().get(&message_id).cloned()
     }
 
+    /// Returns the object of the message, reusing the existing one if the message is already
+    /// known.
+    ///
+    /// Unknown messages, like search results, aren't added to the cache: they aren't part of
+    /// the loaded chat history, so their deletion mustn't be reported to it.
+    pub(crate) fn message_from_td_object(&self, message: types::Message) -> Message {
+        self.message(message.id)
+            .unwrap_or_else(|| Message::new(message, self))
+    }
+
     /// Returns the `Message` of the specified id, if present in the cache. Otherwise it
     /// fetches it from the server and then it returns the result.
     pub(crate) async fn fetch_message(&self, message_id: i64) -> Result<Message, types::Error> {
@@ -544,15 +669,24 @@ impl Chat {
         })
     }
 
+    /// Returns the messages of the chat starting from the given message, from the newest to the
+    /// oldest. A negative `offset` also returns up to `-offset` messages newer than it.
     pub(crate) async fn get_chat_history(
         &self,
         from_message_id: i64,
+        offset: i32,
         limit: i32,
     ) -> Result<Vec<Message>, types::Error> {
         let client_id = self.session().client_id();
-        let result =
-            functions::get_chat_history(self.id(), from_message_id, 0, limit, false, client_id)
-                .await;
+        let result = functions::get_chat_history(
+            self.id(),
+            from_message_id,
+            offset,
+            limit,
+            false,
+            client_id,
+        )
+        .await;
 
         let tdlib::enums::Messages::Messages(data) = result?;
 
@@ -571,6 +705,70 @@ impl Chat {
         Ok(loaded_messages)
     }
 
+    /// Returns the pinned messages of the chat, from the newest to the oldest.
+    pub(crate) async fn pinned_messages(&self) -> Result<Vec<Message>, types::Error> {
+        self.search_messages(
+            String::new(),
+            None,
+            Some(SearchMessagesFilter::Pinned),
+            0,
+            0,
+            100,
+        )
+        .await
+        .map(|found| found.messages)
+    }
+
+    /// Searches the messages of the chat matching the query, the sender and the filter, from
+    /// the newest to the oldest. The search starts from the message with id
+    /// `from_message_id`, or from the last message of the chat if it's 0. A negative `offset`
+    /// also returns up to `-offset` messages newer than `from_message_id`.
+    pub(crate) async fn search_messages(
+        &self,
+        query: String,
+        sender: Option<&MessageSender>,
+        filter: Option<SearchMessagesFilter>,
+        from_message_id: i64,
+        offset: i32,
+        limit: i32,
+    ) -> Result<FoundChatMessages, types::Error> {
+        let client_id = self.session().client_id();
+        let result = functions::search_chat_messages(
+            self.id(),
+            query,
+            sender.map(MessageSender::to_td_object),
+            from_message_id,
+            offset,
+            limit,
+            filter,
+            0,
+            client_id,
+        )
+        .await;
+
+        let tdlib::enums::FoundChatMessages::FoundChatMessages(data) = result?;
+
+        let found_messages = data
+            .messages
+            .into_iter()
+            .map(|m| self.message_from_td_object(m))
+            .collect();
+
+        Ok(FoundChatMessages {
+            total_count: data.total_count,
+            messages: found_messages,
+            next_from_message_id: data.next_from_message_id,
+        })
+    }
+
+    pub(crate) async fn delete_messages(
+        &self,
+        message_ids: Vec<i64>,
+        revoke: bool,
+    ) -> Result<(), types::Error> {
+        functions::delete_messages(self.id(), message_ids, revoke, self.session().client_id()).await
+    }
+
     pub(crate) async fn mark_as_read(&self) -> Result<(), types::Error> {
         if let Some(message) = self.last_message() {
             functions::view_messages(
@@ -1,13 +1,15 @@
 use gtk::glib;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
-use tdlib::enums::{MessageSender as TdMessageSender, Update};
+use tdlib::enums::{self, MessageSender as TdMessageSender, ReactionType, Update};
 use tdlib::functions;
-use tdlib::types::{Error as TdError, Message as TdMessage};
+use tdlib::types::{
+    self, CallbackQueryAnswer, CallbackQueryPayloadData, Error as TdError, Message as TdMessage,
+};
 
 use crate::tdlib::{
-    BoxedMessageContent, BoxedMessageSendingState, Chat, MessageForwardInfo, MessageForwardOrigin,
-    MessageInteractionInfo, User,
+    BoxedMessageContent, BoxedMessageSendingState, BoxedReplyMarkup, Chat, MessageForwardInfo,
+    MessageForwardOrigin, MessageInteractionInfo, User,
 };
 use crate::{expressions, Session};
 
@@ -32,6 +34,17 @@ impl MessageSender {
         }
     }
 
+    pub(crate) fn to_td_object(&self) -> TdMessageSender {
+        match self {
+            MessageSender::User(user) => {
+                TdMessageSender::User(types::MessageSenderUser { user_id: user.id() })
+            }
+            MessageSender::Chat(chat) => {
+                TdMessageSender::Chat(types::MessageSenderChat { chat_id: chat.id() })
+            }
+        }
+    }
+
     pub(crate) fn as_user(&self) -> Option<&User> {
         match self {
             MessageSender::User(user) => Some(user),
@@ -58,7 +71,9 @@ mod imp {
         pub(super) id: Cell<i64>,
         pub(super) sender: OnceCell<MessageSender>,
         pub(super) is_outgoing: Cell<bool>,
+        pub(super) is_pinned: Cell<bool>,
         pub(super) can_be_edited: Cell<bool>,
+        pub(super) can_be_forwarded: Cell<bool>,
         pub(super) can_be_deleted_only_for_self: Cell<bool>,
         pub(super) can_be_deleted_for_all_users: Cell<bool>,
         pub(super) sending_state: RefCell<Option<BoxedMessageSendingState>>,
@@ -70,6 +85,8 @@ mod imp {
         pub(super) forward_info: OnceCell<Option<MessageForwardInfo>>,
         pub(super) reply_in_chat_id: Cell<i64>,
         pub(super) reply_to_message_id: Cell<i64>,
+        pub(super) media_album_id: Cell<i64>,
+        pub(super) reply_markup: RefCell<Option<BoxedReplyMarkup>>,
     }
 
     #[glib::object_subclass]
@@ -89,9 +106,15 @@ mod imp {
                     glib::ParamSpecBoolean::builder("is-outgoing")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("is-pinned")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecBoolean::builder("can-be-edited")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("can-be-forwarded")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecBoolean::builder("can-be-deleted-only-for-self")
                         .read_only()
                         .build(),
@@ -123,6 +146,9 @@ mod imp {
                     glib::ParamSpecInt64::builder("reply-to-message-id")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoxed::builder::<BoxedReplyMarkup>("reply-markup")
+                        .read_only()
+                        .build(),
                 ]
             });
             PROPERTIES.as_ref()
@@ -135,7 +161,9 @@ mod imp {
                 "id" => obj.id().to_value(),
                 "sender" => obj.sender().to_value(),
                 "is-outgoing" => obj.is_outgoing().to_value(),
+                "is-pinned" => obj.is_pinned().to_value(),
                 "can-be-edited" => obj.can_be_edited().to_value(),
+                "can-be-forwarded" => obj.can_be_forwarded().to_value(),
                 "can-be-deleted-only-for-self" => obj.can_be_deleted_only_for_self().to_value(),
                 "can-be-deleted-for-all-users" => obj.can_be_deleted_for_all_users().to_value(),
                 "sending-state" => obj.sending_state().to_value(),
@@ -147,6 +175,7 @@ mod imp {
                 "forward-info" => obj.forward_info().to_value(),
                 "reply-in-chat-id" => obj.reply_in_chat_id().to_value(),
                 "reply-to-message-id" => obj.reply_to_message_id().to_value(),
+                "reply-markup" => obj.reply_markup().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -173,7 +202,9 @@ impl Message {
         imp.id.set(td_message.id);
         imp.sender.set(sender).unwrap();
         imp.is_outgoing.set(td_message.is_outgoing);
+        imp.is_pinned.set(td_message.is_pinned);
         imp.can_be_edited.set(td_message.can_be_edited);
+        imp.can_be_forwarded.set(td_message.can_be_forwarded);
         imp.can_be_deleted_only_for_self
             .set(td_message.can_be_deleted_only_for_self);
         imp.can_be_deleted_for_all_users
@@ -189,6 +220,9 @@ impl Message {
         imp.forward_info.set(forward_info).unwrap();
         imp.reply_in_chat_id.set(td_message.reply_in_chat_id);
         imp.reply_to_message_id.set(td_message.reply_to_message_id);
+        imp.media_album_id.set(td_message.media_album_id);
+        imp.reply_markup
+            .replace(td_message.reply_markup.map(BoxedReplyMarkup));
 
         message
     }
@@ -199,14 +233,37 @@ impl Message {
                 let new_content = BoxedMessageContent(data.new_content);
                 self.set_content(new_content);
             }
-            Update::MessageEdited(data) => self.set_is_edited(data.edit_date > 0),
+            Update::MessageEdited(data) => {
+                self.set_is_edited(data.edit_date > 0);
+                self.set_reply_markup(data.reply_markup.map(BoxedReplyMarkup));
+            }
             Update::MessageInteractionInfo(data) => {
                 self.interaction_info().update(data.interaction_info)
             }
+            Update::MessageIsPinned(data) => self.set_is_pinned(data.is_pinned),
             _ => {}
         }
     }
 
+    /// Sends a callback query with the data of a callback button to the bot and returns
+    /// its answer.
+    pub(crate) async fn get_callback_query_answer(
+        &self,
+        data: String,
+    ) -> Result<CallbackQueryAnswer, TdError> {
+        let payload = enums::CallbackQueryPayload::Data(CallbackQueryPayloadData { data });
+        let enums::CallbackQueryAnswer::CallbackQueryAnswer(answer) =
+            functions::get_callback_query_answer(
+                self.chat().id(),
+                self.id(),
+                payload,
+                self.chat().session().client_id(),
+            )
+            .await?;
+
+        Ok(answer)
+    }
+
     pub(crate) async fn delete(&self, revoke: bool) -> Result<(), TdError> {
         functions::delete_messages(
             self.chat().id(),
@@ -217,6 +274,97 @@ impl Message {
         .await
     }
 
+    pub(crate) async fn pin(&self, disable_notification: bool) -> Result<(), TdError> {
+        functions::pin_chat_message(
+            self.chat().id(),
+            self.id(),
+            disable_notification,
+            false,
+            self.chat().session().client_id(),
+        )
+        .await
+    }
+
+    pub(crate) async fn unpin(&self) -> Result<(), TdError> {
+        functions::unpin_chat_message(
+            self.chat().id(),
+            self.id(),
+            self.chat().session().client_id(),
+        )
+        .await
+    }
+
+    /// Votes for the given poll options. Passing no options retracts the vote.
+    pub(crate) async fn set_poll_answer(&self, option_ids: Vec<i32>) -> Result<(), TdError> {
+        functions::set_poll_answer(
+            self.chat().id(),
+            self.id(),
+            option_ids,
+            self.chat().session().client_id(),
+        )
+        .await
+    }
+
+    pub(crate) async fn stop_poll(&self) -> Result<(), TdError> {
+        functions::stop_poll(
+            self.chat().id(),
+            self.id(),
+            None,
+            self.chat().session().client_id(),
+        )
+        .await
+    }
+
+    /// Returns the reactions that can be added to the message, taking into account the
+    /// reactions that are available in the chat. The top reactions come first, followed by the
+    /// recently used and the popular ones.
+    pub(crate) async fn available_reactions(&self) -> Result<Vec<ReactionType>, TdError> {
+        let enums::AvailableReactions::AvailableReactions(data) =
+            functions::get_message_available_reactions(
+                self.chat().id(),
+                self.id(),
+                8,
+                self.chat().session().client_id(),
+            )
+            .await?;
+
+        let mut reactions: Vec<ReactionType> = Vec::new();
+        for reaction in data
+            .top_reactions
+            .into_iter()
+            .chain(data.recent_reactions)
+            .chain(data.popular_reactions)
+        {
+            if !reaction.needs_premium && !reactions.contains(&reaction.r#type) {
+                reactions.push(reaction.r#type);
+            }
+        }
+
+        Ok(reactions)
+    }
+
+    pub(crate) async fn add_reaction(&self, reaction_type: ReactionType) -> Result<(), TdError> {
+        functions::add_message_reaction(
+            self.chat().id(),
+            self.id(),
+            reaction_type,
+            false,
+            true,
+            self.chat().session().client_id(),
+        )
+        .await
+    }
+
+    pub(crate) async fn remove_reaction(&self, reaction_type: ReactionType) -> Result<(), TdError> {
+        functions::remove_message_reaction(
+            self.chat().id(),
+            self.id(),
+            reaction_type,
+            self.chat().session().client_id(),
+        )
+        .await
+    }
+
     pub(crate) fn id(&self) -> i64 {
         self.imp().id.get()
     }
@@ -229,10 +377,26 @@ impl Message {
         self.imp().is_outgoing.get()
     }
 
+    pub(crate) fn is_pinned(&self) -> bool {
+        self.imp().is_pinned.get()
+    }
+
+    fn set_is_pinned(&self, is_pinned: bool) {
+        if self.is_pinned() == is_pinned {
+            return;
+        }
+        self.imp().is_pinned.set(is_pinned);
+        self.notify("is-pinned");
+    }
+
     pub(crate) fn can_be_edited(&self) -> bool {
         self.imp().can_be_edited.get()
     }
 
+    pub(crate) fn can_be_forwarded(&self) -> bool {
+        self.imp().can_be_forwarded.get()
+    }
+
     pub(crate) fn can_be_deleted_only_for_self(&self) -> bool {
         self.imp().can_be_deleted_only_for_self.get()
     }
@@ -300,6 +464,24 @@ impl Message {
         self.imp().reply_to_message_id.get()
     }
 
+    /// Returns the identifier of the media album the message belongs to, or 0 if it is not
+    /// part of an album.
+    pub(crate) fn media_album_id(&self) -> i64 {
+        self.imp().media_album_id.get()
+    }
+
+    pub(crate) fn reply_markup(&self) -> Option<BoxedReplyMarkup> {
+        self.imp().reply_markup.borrow().clone()
+    }
+
+    fn set_reply_markup(&self, reply_markup: Option<BoxedReplyMarkup>) {
+        if self.reply_markup() == reply_markup {
+            return;
+        }
+        self.imp().reply_markup.replace(reply_markup);
+        self.notify("reply-markup");
+    }
+
     pub(crate) fn sender_name_expression(&self) -> gtk::Expression {
         match self.sender() {
             MessageSender::User(user) => {
@@ -3,14 +3,17 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use tdlib::types;
 
+use crate::tdlib::BoxedMessageReactions;
+
 mod imp {
     use super::*;
     use once_cell::sync::Lazy;
-    use std::cell::Cell;
+    use std::cell::{Cell, RefCell};
 
     #[derive(Debug, Default)]
     pub(crate) struct MessageInteractionInfo {
         pub(super) reply_count: Cell<u32>,
+        pub(super) reactions: RefCell<BoxedMessageReactions>,
     }
 
     #[glib::object_subclass]
@@ -22,16 +25,24 @@ mod imp {
     impl ObjectImpl for MessageInteractionInfo {
         fn properties() -> &'static [glib::ParamSpec] {
             static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
-                vec![glib::ParamSpecUInt::builder("reply-count")
-                    .read_only()
-                    .build()]
+                vec![
+                    glib::ParamSpecUInt::builder("reply-count")
+                        .read_only()
+                        .build(),
+                    glib::ParamSpecBoxed::builder::<BoxedMessageReactions>("reactions")
+                        .read_only()
+                        .build(),
+                ]
             });
             PROPERTIES.as_ref()
         }
 
         fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
             match pspec.name() {
-                "reply-count" => self.obj().reply_count().to_value(),
+                "reply-count" => obj.reply_count().to_value(),
+                "reactions" => obj.reactions().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -45,16 +56,18 @@ glib::wrapper! {
 impl From<Option<types::MessageInteractionInfo>> for MessageInteractionInfo {
     fn from(interaction_info: Option<types::MessageInteractionInfo>) -> Self {
         let obj: Self = glib::Object::builder().build();
-        obj.imp()
-            .reply_count
-            .set(extract_reply_count(interaction_info));
+        let imp = obj.imp();
+        imp.reply_count
+            .set(extract_reply_count(interaction_info.as_ref()));
+        imp.reactions.replace(extract_reactions(interaction_info));
         obj
     }
 }
 
 impl MessageInteractionInfo {
     pub(crate) fn update(&self, interaction_info: Option<types::MessageInteractionInfo>) {
-        self.set_reply_count(extract_reply_count(interaction_info));
+        self.set_reply_count(extract_reply_count(interaction_info.as_ref()));
+        self.set_reactions(extract_reactions(interaction_info));
     }
 
     pub(crate) fn reply_count(&self) -> u32 {
@@ -68,11 +81,33 @@ impl MessageInteractionInfo {
         self.imp().reply_count.set(reply_count);
         self.notify("reply-count");
     }
+
+    pub(crate) fn reactions(&self) -> BoxedMessageReactions {
+        self.imp().reactions.borrow().clone()
+    }
+
+    fn set_reactions(&self, reactions: BoxedMessageReactions) {
+        if *self.imp().reactions.borrow() == reactions {
+            return;
+        }
+        self.imp().reactions.replace(reactions);
+        self.notify("reactions");
+    }
 }
 
-fn extract_reply_count(interaction_info: Option<types::MessageInteractionInfo>) -> u32 {
+fn extract_reply_count(interaction_info: Option<&types::MessageInteractionInfo>) -> u32 {
     interaction_info
-        .and_then(|interaction_info| interaction_info.reply_info)
+        .and_then(|interaction_info| interaction_info.reply_info.as_ref())
         .map(|reply_info| reply_info.reply_count)
         .unwrap_or(0) as u32
 }
+
+fn extract_reactions(
+    interaction_info: Option<types::MessageInteractionInfo>,
+) -> BoxedMessageReactions {
+    BoxedMessageReactions(
+        interaction_info
+            .map(|interaction_info| interaction_info.reactions)
+            .unwrap_or_default(),
+    )
+}
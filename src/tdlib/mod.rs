@@ -33,9 +33,11 @@ pub(crate) use self::supergroup::Supergroup;
 pub(crate) use self::user::User;
 
 use gtk::glib;
-use tdlib::enums::{ChatMemberStatus, MessageContent, MessageSendingState, UserStatus, UserType};
+use tdlib::enums::{
+    ChatMemberStatus, MessageContent, MessageSendingState, ReplyMarkup, UserStatus, UserType,
+};
 use tdlib::types::{
-    ChatNotificationSettings, ChatPermissions, DraftMessage, FormattedText,
+    ChatNotificationSettings, ChatPermissions, DraftMessage, FormattedText, MessageReaction,
     ScopeNotificationSettings,
 };
 
@@ -63,6 +65,14 @@ pub(crate) struct BoxedFormattedText(pub(crate) FormattedText);
 #[boxed_type(name = "BoxedMessageContent")]
 pub(crate) struct BoxedMessageContent(pub(crate) MessageContent);
 
+#[derive(Clone, Debug, Default, PartialEq, glib::Boxed)]
+#[boxed_type(name = "BoxedMessageReactions")]
+pub(crate) struct BoxedMessageReactions(pub(crate) Vec<MessageReaction>);
+
+#[derive(Clone, Debug, PartialEq, glib::Boxed)]
+#[boxed_type(name = "BoxedReplyMarkup", nullable)]
+pub(crate) struct BoxedReplyMarkup(pub(crate) ReplyMarkup);
+
 #[derive(Clone, Debug, PartialEq, glib::Boxed)]
 #[boxed_type(name = "BoxedScopeNotificationSettings", nullable)]
 pub(crate) struct BoxedScopeNotificationSettings(pub(crate) ScopeNotificationSettings);
@@ -23,6 +23,7 @@ mod imp {
         pub(super) phone_number: RefCell<String>,
         pub(super) avatar: RefCell<Option<Avatar>>,
         pub(super) status: RefCell<Option<BoxedUserStatus>>,
+        pub(super) is_contact: Cell<bool>,
         pub(super) session: WeakRef<Session>,
     }
 
@@ -58,6 +59,9 @@ mod imp {
                     glib::ParamSpecBoxed::builder::<BoxedUserStatus>("status")
                         .read_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("is-contact")
+                        .read_only()
+                        .build(),
                     glib::ParamSpecObject::builder::<Session>("session")
                         .read_only()
                         .build(),
@@ -78,6 +82,7 @@ mod imp {
                 "phone-number" => obj.phone_number().to_value(),
                 "avatar" => obj.avatar().to_value(),
                 "status" => obj.status().to_value(),
+                "is-contact" => obj.is_contact().to_value(),
                 "session" => obj.session().to_value(),
                 _ => unimplemented!(),
             }
@@ -110,6 +115,7 @@ impl User {
         imp.phone_number.replace(td_user.phone_number);
         imp.avatar.replace(avatar);
         imp.status.replace(Some(status));
+        imp.is_contact.set(td_user.is_contact);
         imp.session.set(Some(session));
 
         user
@@ -128,6 +134,7 @@ impl User {
         self.set_phone_number(td_user.phone_number);
         self.set_status(BoxedUserStatus(td_user.status));
         self.set_avatar(td_user.profile_photo.map(Into::into));
+        self.set_is_contact(td_user.is_contact);
     }
 
     pub(crate) fn update_status(&self, status: TdUserStatus) {
@@ -229,6 +236,25 @@ impl User {
         self.notify("status");
     }
 
+    pub(crate) fn is_contact(&self) -> bool {
+        self.imp().is_contact.get()
+    }
+
+    fn set_is_contact(&self, is_contact: bool) {
+        if self.is_contact() == is_contact {
+            return;
+        }
+        self.imp().is_contact.set(is_contact);
+        self.notify("is-contact");
+    }
+
+    pub(crate) fn connect_is_contact_notify<F: Fn(&Self, &glib::ParamSpec) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_notify_local(Some("is-contact"), f)
+    }
+
     pub(crate) fn session(&self) -> Session {
         self.imp().session.upgrade().unwrap()
     }
@@ -16,6 +16,9 @@ use crate::{config, APPLICATION_OPTS, TEMP_DIR};
 
 static PROTOCOL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\w+://").unwrap());
 
+pub(crate) const CUSTOM_EMOJI_URI_PREFIX: &str = "tg-emoji:";
+pub(crate) const SPOILER_URI: &str = "tg-spoiler:";
+
 pub(crate) fn escape(text: &str) -> String {
     text.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -45,7 +48,30 @@ pub(crate) fn linkify(text: &str) -> String {
     }
 }
 
-pub(crate) fn convert_to_markup(text: String, entity: &TextEntityType) -> String {
+/// How custom emoji and spoilers are represented in the markup of a formatted text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MarkupStyle {
+    /// Custom emoji are shown as their fallback emoji and spoilers in clear, for labels
+    /// that can't render them.
+    Plain,
+    /// Custom emoji are encoded as links, that `MessageLabel` replaces with the actual
+    /// sticker, and spoilers are obscured until they are revealed.
+    MessageLabel { spoilers_revealed: bool },
+}
+
+impl MarkupStyle {
+    fn obscures_spoilers(self) -> bool {
+        self == Self::MessageLabel {
+            spoilers_revealed: false,
+        }
+    }
+}
+
+pub(crate) fn convert_to_markup(
+    text: String,
+    entity: &TextEntityType,
+    style: MarkupStyle,
+) -> String {
     match entity {
         TextEntityType::Url => format!("<a href='{}'>{}</a>", linkify(&text), text),
         TextEntityType::EmailAddress => format!("<a href='mailto:{text}'>{text}</a>"),
@@ -58,11 +84,42 @@ pub(crate) fn convert_to_markup(text: String, entity: &TextEntityType) -> String
             format!("<tt>{text}</tt>")
         }
         TextEntityType::TextUrl(data) => format!("<a href='{}'>{}</a>", escape(&data.url), text),
+        TextEntityType::CustomEmoji(data) if matches!(style, MarkupStyle::MessageLabel { .. }) => {
+            format!(
+                "<a href='{}{}'>{}</a>",
+                CUSTOM_EMOJI_URI_PREFIX, data.custom_emoji_id, text
+            )
+        }
+        // Obscured spoilers are links, so that `MessageLabel` can reveal them when clicked
+        TextEntityType::Spoiler if style.obscures_spoilers() => format!(
+            "<a href='{SPOILER_URI}'><span fgalpha='1' background='#808080' \
+            underline='none'>{text}</span></a>"
+        ),
         _ => text,
     }
 }
 
+fn is_link(entity: &TextEntityType) -> bool {
+    matches!(
+        entity,
+        TextEntityType::Url
+            | TextEntityType::EmailAddress
+            | TextEntityType::PhoneNumber
+            | TextEntityType::TextUrl(_)
+            | TextEntityType::CustomEmoji(_)
+    )
+}
+
+/// Converts the formatted text to markup, for labels that can't render custom emoji and
+/// spoilers.
 pub(crate) fn parse_formatted_text(formatted_text: FormattedText) -> String {
+    parse_formatted_text_with_style(formatted_text, MarkupStyle::Plain)
+}
+
+pub(crate) fn parse_formatted_text_with_style(
+    formatted_text: FormattedText,
+    style: MarkupStyle,
+) -> String {
     let mut entities = formatted_text.entities.iter();
     let mut entity = entities.next();
     let mut output = String::new();
@@ -92,25 +149,31 @@ pub(crate) fn parse_formatted_text(formatted_text: FormattedText) -> String {
 
         if let Some(entity_) = entity {
             if code_units_offset >= (entity_.offset + entity_.length) as usize {
-                buffer = escape(&buffer);
-
+                // Collect the eventual nested entities, the outermost one being the last
+                let mut types = Vec::new();
                 entity = loop {
-                    let entity = entities.next();
-
-                    // Handle eventual nested entities
-                    match entity {
-                        Some(entity) => {
-                            if entity.offset == entity_.offset {
-                                buffer = convert_to_markup(buffer, &entity.r#type);
-                            } else {
-                                break Some(entity);
-                            }
+                    match entities.next() {
+                        Some(entity) if entity.offset == entity_.offset => {
+                            types.push(&entity.r#type)
                         }
-                        None => break None,
+                        entity => break entity,
                     }
                 };
+                types.push(&entity_.r#type);
+
+                // Links can't be nested, so the ones inside an obscured spoiler are dropped
+                // in favor of the link revealing it
+                let is_obscured = style.obscures_spoilers()
+                    && types.iter().any(|t| matches!(t, TextEntityType::Spoiler));
+
+                let markup = types
+                    .into_iter()
+                    .filter(|t| !(is_obscured && is_link(t)))
+                    .fold(escape(&buffer), |markup, t| {
+                        convert_to_markup(markup, t, style)
+                    });
 
-                output.push_str(&convert_to_markup(buffer, &entity_.r#type));
+                output.push_str(&markup);
                 buffer = String::new();
                 is_inside_entity = false;
             }
@@ -125,6 +188,110 @@ pub(crate) fn parse_formatted_text(formatted_text: FormattedText) -> String {
     output
 }
 
+/// A part of a formatted text, which is either regular text or a block of code.
+#[derive(Debug)]
+pub(crate) enum FormattedTextBlock {
+    Text(FormattedText),
+    Code { code: String, language: String },
+}
+
+/// Splits a formatted text at its `Pre` and `PreCode` entities, so that code blocks can be
+/// rendered separately from the rest of the text.
+pub(crate) fn split_code_blocks(formatted_text: FormattedText) -> Vec<FormattedTextBlock> {
+    let code_entities: Vec<&types::TextEntity> = formatted_text
+        .entities
+        .iter()
+        .filter(|e| matches!(e.r#type, TextEntityType::Pre | TextEntityType::PreCode(_)))
+        .collect();
+
+    if code_entities.is_empty() {
+        return vec![FormattedTextBlock::Text(formatted_text)];
+    }
+
+    // Entity offsets and lengths are expressed in utf16 code units
+    let utf16: Vec<u16> = formatted_text.text.encode_utf16().collect();
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+
+    for entity in code_entities {
+        let start = (entity.offset as usize).min(utf16.len());
+        let end = ((entity.offset + entity.length) as usize).min(utf16.len());
+
+        // Skip eventual overlapping entities
+        if start < offset {
+            continue;
+        }
+
+        if let Some(text) = formatted_text_range(&formatted_text, &utf16, offset, start) {
+            blocks.push(FormattedTextBlock::Text(text));
+        }
+
+        let language = match &entity.r#type {
+            TextEntityType::PreCode(data) => data.language.clone(),
+            _ => String::new(),
+        };
+        blocks.push(FormattedTextBlock::Code {
+            code: String::from_utf16_lossy(&utf16[start..end]),
+            language,
+        });
+
+        offset = end;
+    }
+
+    if let Some(text) = formatted_text_range(&formatted_text, &utf16, offset, utf16.len()) {
+        blocks.push(FormattedTextBlock::Text(text));
+    }
+
+    blocks
+}
+
+/// Returns the part of the formatted text between `start` and `end` (in utf16 code units),
+/// without leading and trailing newlines, or `None` if it would be empty.
+fn formatted_text_range(
+    formatted_text: &FormattedText,
+    utf16: &[u16],
+    mut start: usize,
+    mut end: usize,
+) -> Option<FormattedText> {
+    let newline = '\n' as u16;
+
+    // Don't trust the entities to be inside the text
+    end = end.min(utf16.len());
+    start = start.min(end);
+
+    while start < end && utf16[start] == newline {
+        start += 1;
+    }
+    while end > start && utf16[end - 1] == newline {
+        end -= 1;
+    }
+
+    if start == end {
+        return None;
+    }
+
+    let entities = formatted_text
+        .entities
+        .iter()
+        .filter(|e| !matches!(e.r#type, TextEntityType::Pre | TextEntityType::PreCode(_)))
+        .filter_map(|e| {
+            let entity_start = (e.offset as usize).max(start);
+            let entity_end = ((e.offset + e.length) as usize).min(end);
+
+            (entity_start < entity_end).then(|| types::TextEntity {
+                offset: (entity_start - start) as i32,
+                length: (entity_end - entity_start) as i32,
+                r#type: e.r#type.clone(),
+            })
+        })
+        .collect();
+
+    Some(FormattedText {
+        text: String::from_utf16_lossy(&utf16[start..end]),
+        entities,
+    })
+}
+
 pub(crate) fn human_friendly_duration(mut seconds: i32) -> String {
     let hours = seconds / (60 * 60);
     if hours > 0 {
@@ -151,6 +318,19 @@ pub(crate) fn human_friendly_duration(mut seconds: i32) -> String {
     }
 }
 
+/// Formats a duration in seconds like a media player would (e.g. "1:05:09" or "5:09").
+pub(crate) fn format_duration(seconds: i64) -> String {
+    let hours = seconds / (60 * 60);
+    let minutes = (seconds % (60 * 60)) / 60;
+    let seconds = seconds % 60;
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
 /// Returns the Paper Plane data directory (e.g. /home/bob/.local/share/paper-plane).
 pub(crate) fn data_dir() -> &'static PathBuf {
     &APPLICATION_OPTS.get().unwrap().data_dir
@@ -230,6 +410,15 @@ pub(crate) enum DecodeError {
     Unimplemented,
 }
 
+/// Decodes the blurred low-resolution preview that is shown while a media is being downloaded.
+pub(crate) fn minithumbnail_texture(
+    minithumbnail: Option<types::Minithumbnail>,
+) -> Option<gdk::Texture> {
+    minithumbnail.and_then(|m| {
+        gdk::Texture::from_bytes(&glib::Bytes::from_owned(glib::base64_decode(&m.data))).ok()
+    })
+}
+
 pub(crate) fn decode_image_from_path(path: &str) -> Result<gdk::MemoryTexture, DecodeError> {
     use image::DynamicImage::*;
 